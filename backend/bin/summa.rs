@@ -0,0 +1,1091 @@
+#![feature(generic_const_exprs)]
+//! Command-line tool running the exchange side of a Summa round.
+//!
+//! A round is run with the following subcommands, in order:
+//!
+//! 1. `setup` generates the proving and verifying keys of the circuit.
+//! 2. `prove` generates the SNARK proof from the entries CSV and saves the snapshot to disk.
+//! 3. `submit-ownership` submits the proofs of address ownership to the Summa contract, signed over the messages
+//!    generated by `ownership-message`.
+//! 4. `submit-commitment` submits the commitment of the saved snapshot to the Summa contract.
+//! 5. `export-proofs` exports the inclusion proof bundles of all the users as JSON files.
+//!
+//! `status` reports the state of the Summa contract and of the saved snapshot at any time, and `audit-ownership`
+//! verifies the signatures of all the proofs of address ownership submitted to the Summa contract.
+//! `assets` collects the balances of the proven addresses and compares them with the liabilities of the snapshot,
+//! and `report` writes the solvency report of the round once its commitment is submitted.
+//!
+//! The settings are given as flags or in a TOML config file passed with `--config`, the flags taking precedence.
+//! The relative paths of the config file are resolved against the directory of the config file.
+//!
+//! The circuit shape, i.e., `k`, `n-currencies` and `n-users`, has to match the verifier contracts deployed along with
+//! the Summa contract. The tool is built for the shapes listed in `main`.
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use clap::{Args, Parser, Subcommand};
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{Address, U256},
+};
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr as Fp, G1Affine},
+    plonk::ProvingKey,
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+    SerdeFormat,
+};
+use serde::Deserialize;
+use serde_json::to_string_pretty;
+
+use summa_backend::{
+    apis::{
+        address_ownership::{AddressOwnership, DEFAULT_MAX_BATCH_GAS},
+        assets::{AssetCollector, AssetReport, CurrencyManifest},
+        bundle::{InclusionProofBundle, RoundContext},
+        ownership_message::{MessageBinding, OwnershipMessage},
+        report::SolvencyReport,
+        round::{Round, Snapshot},
+        signature_verification::{SignatureValidation, SignatureVerifiers},
+        tenant::CircuitShape,
+    },
+    contracts::{
+        reader::SummaReader,
+        remote_signer::RemoteSigner,
+        signer::{AddressInput, SummaSigner},
+        transaction::TransactionConfig,
+    },
+};
+use summa_solvency::{
+    circuits::{
+        univariate_grand_sum::{UnivariateGrandSum, UnivariateGrandSumConfig},
+        utils::{
+            full_prover, full_verifier, generate_setup_artifacts, parse_proving_seed,
+            MultiOpenScheme, ProvingSeed,
+        },
+    },
+    cryptocurrency::Cryptocurrency,
+    entry::Entry,
+    utils::parse_csv_to_entries,
+};
+
+// The default circuit shape, matching the verifier contracts of the backend
+const DEFAULT_K: u32 = 17;
+const DEFAULT_N_CURRENCIES: usize = 2;
+const DEFAULT_N_USERS: usize = 16;
+
+type SummaCircuit<const N_CURRENCIES: usize, const N_USERS: usize> =
+    UnivariateGrandSum<N_USERS, N_CURRENCIES, UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>>;
+
+// The ownership messages are valid for 30 days by default
+const DEFAULT_MESSAGE_VALIDITY: u64 = 30 * 24 * 60 * 60;
+
+const PROVING_KEY_FILE: &str = "pk.bin";
+const VERIFYING_KEY_FILE: &str = "vk.bin";
+
+#[derive(Parser)]
+#[command(name = "summa", about = "Run a Summa proof of solvency round")]
+struct Cli {
+    /// Path to a TOML config file providing default values for the settings
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    settings: Settings,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate the proving and verifying keys of the circuit
+    Setup,
+    /// Generate the SNARK proof from the entries CSV and save the snapshot
+    Prove,
+    /// Generate the structured ownership message to be signed with the addresses of the exchange
+    OwnershipMessage,
+    /// Submit the proofs of address ownership from the signatures CSV
+    SubmitOwnership,
+    /// Submit the commitment of the saved snapshot
+    SubmitCommitment,
+    /// Export the inclusion proofs of all the users of the saved snapshot
+    ExportProofs,
+    /// Report the state of the Summa contract and of the saved snapshot
+    Status,
+    /// Verify the signatures of the proofs of address ownership submitted to the Summa contract
+    AuditOwnership,
+    /// Collect the balances of the proven addresses and compare them with the liabilities of the saved snapshot
+    Assets,
+    /// Write the solvency report of the saved snapshot as JSON, Markdown and HTML
+    Report,
+}
+
+/// The signer of the transactions of the exchange
+enum ExchangeSigner {
+    Local(SummaSigner<LocalWallet>),
+    Remote(SummaSigner<RemoteSigner>),
+}
+
+/// The settings of the tool, given either as flags or in the config file with the same names in kebab-case.
+///
+/// The secrets, i.e., the private key, the keystore password and the mnemonic, are only given as flags or
+/// environment variables: the config file is rejected if it contains any of them.
+#[derive(Args, Deserialize, Default)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct Settings {
+    /// Endpoint of the JSON-RPC node
+    #[arg(long, global = true)]
+    rpc_url: Option<String>,
+
+    /// Degree parameter of the circuit, 17 by default
+    #[arg(long, global = true)]
+    k: Option<u32>,
+
+    /// Number of currencies of the circuit, 2 by default
+    #[arg(long, global = true)]
+    n_currencies: Option<usize>,
+
+    /// Number of users of the circuit, i.e., the maximum number of entries, 16 by default
+    #[arg(long, global = true)]
+    n_users: Option<usize>,
+
+    /// Private key of the wallet submitting the transactions on behalf of the exchange
+    #[arg(long, global = true, env = "SUMMA_PRIVATE_KEY", hide_env_values = true)]
+    #[serde(skip)]
+    private_key: Option<String>,
+
+    /// Path to the encrypted JSON keystore of the wallet, instead of the private key
+    #[arg(long, global = true)]
+    keystore_path: Option<PathBuf>,
+
+    /// Password of the keystore
+    #[arg(
+        long,
+        global = true,
+        env = "SUMMA_KEYSTORE_PASSWORD",
+        hide_env_values = true
+    )]
+    #[serde(skip)]
+    keystore_password: Option<String>,
+
+    /// Mnemonic the wallet is derived from, instead of the private key
+    #[arg(long, global = true, env = "SUMMA_MNEMONIC", hide_env_values = true)]
+    #[serde(skip)]
+    mnemonic: Option<String>,
+
+    /// Derivation path of the wallet from the mnemonic, `m/44'/60'/0'/0/0` by default
+    #[arg(long, global = true)]
+    derivation_path: Option<String>,
+
+    /// JSON-RPC endpoint of a remote signer holding the key, instead of a local wallet
+    #[arg(long, global = true)]
+    remote_signer_url: Option<String>,
+
+    /// Address of the account of the exchange on the remote signer
+    #[arg(long, global = true)]
+    signer_address: Option<String>,
+
+    /// Address of the Summa contract, looked up in the deployments file if not given
+    #[arg(long, global = true)]
+    summa_address: Option<String>,
+
+    /// Path to the deployments file mapping the chain IDs to the Summa contract addresses
+    #[arg(long, global = true)]
+    deployments_path: Option<PathBuf>,
+
+    /// Block the Summa contract was deployed at, the logs of the contract are queried from, 0 by default
+    #[arg(long, global = true)]
+    start_block: Option<u64>,
+
+    /// Path to the ptau file of the KZG parameters
+    #[arg(long, global = true)]
+    params_path: Option<PathBuf>,
+
+    /// Directory of the proving and verifying keys
+    #[arg(long, global = true)]
+    keys_dir: Option<PathBuf>,
+
+    /// Path to the CSV file of the user entries
+    #[arg(long, global = true)]
+    entries_csv: Option<PathBuf>,
+
+    /// Path to the CSV file of the address ownership signatures
+    #[arg(long, global = true)]
+    signatures_csv: Option<PathBuf>,
+
+    /// Name of the exchange the structured ownership messages are signed for
+    #[arg(long, global = true)]
+    exchange_name: Option<String>,
+
+    /// Accept the free-text ownership messages signed before the structured ones, which can be replayed by other exchanges
+    #[arg(long, global = true)]
+    allow_legacy_messages: bool,
+
+    /// Seconds during which the generated ownership messages are valid, 30 days by default
+    #[arg(long, global = true)]
+    message_validity: Option<u64>,
+
+    /// Path to the JSON manifest of the cryptocurrencies whose assets are collected
+    #[arg(long, global = true)]
+    currency_manifest: Option<PathBuf>,
+
+    /// Chain of the proven addresses whose assets are collected, ETH by default
+    #[arg(long, global = true)]
+    assets_chain: Option<String>,
+
+    /// Endpoint of the node the assets are read from, the RPC URL by default
+    #[arg(long, global = true)]
+    assets_rpc_url: Option<String>,
+
+    /// Block the assets are read at, the latest one by default
+    #[arg(long, global = true)]
+    assets_block: Option<u64>,
+
+    /// Directory the solvency reports are written to
+    #[arg(long, global = true)]
+    reports_dir: Option<PathBuf>,
+
+    /// Path to the snapshot file written by `prove`
+    #[arg(long, global = true)]
+    snapshot_path: Option<PathBuf>,
+
+    /// Directory the inclusion proofs are exported to
+    #[arg(long, global = true)]
+    proofs_dir: Option<PathBuf>,
+
+    /// Timestamp of the round, the current time by default
+    #[arg(long, global = true)]
+    timestamp: Option<u64>,
+
+    /// Multi-open scheme of the SNARK proof, `shplonk` or `gwc`, matching the SNARK verifier contract
+    #[arg(long, global = true)]
+    scheme: Option<String>,
+
+    /// Secret 32-byte seed of the prover randomness as hex, making the proof reproducible.
+    /// It reveals the blinding factors of the proof, so it is only to be shared with the Auditor.
+    #[arg(
+        long,
+        global = true,
+        env = "SUMMA_PROVING_SEED",
+        hide_env_values = true
+    )]
+    seed: Option<String>,
+
+    /// Number of blocks to wait for after the inclusion of a transaction, 1 by default
+    #[arg(long, global = true)]
+    confirmations: Option<u64>,
+
+    /// Upper bound of the max fee per gas of the transactions, in wei
+    #[arg(long, global = true)]
+    max_fee_per_gas: Option<u64>,
+
+    /// Upper bound of the max priority fee per gas of the transactions, in wei
+    #[arg(long, global = true)]
+    max_priority_fee_per_gas: Option<u64>,
+
+    /// Seconds to wait for a transaction to be mined before replacing it with higher fees, 60 by default
+    #[arg(long, global = true)]
+    replacement_timeout: Option<u64>,
+
+    /// Upper bound of the gas used by a single submission of proofs of address ownership, 10000000 by default
+    #[arg(long, global = true)]
+    max_batch_gas: Option<u64>,
+}
+
+impl Settings {
+    /// Returns the settings with the unset values taken from `defaults`
+    fn or(self, defaults: Settings) -> Settings {
+        Settings {
+            rpc_url: self.rpc_url.or(defaults.rpc_url),
+            k: self.k.or(defaults.k),
+            n_currencies: self.n_currencies.or(defaults.n_currencies),
+            n_users: self.n_users.or(defaults.n_users),
+            // The secrets are never read from the config file
+            private_key: self.private_key,
+            keystore_path: self.keystore_path.or(defaults.keystore_path),
+            keystore_password: self.keystore_password,
+            mnemonic: self.mnemonic,
+            derivation_path: self.derivation_path.or(defaults.derivation_path),
+            remote_signer_url: self.remote_signer_url.or(defaults.remote_signer_url),
+            signer_address: self.signer_address.or(defaults.signer_address),
+            summa_address: self.summa_address.or(defaults.summa_address),
+            deployments_path: self.deployments_path.or(defaults.deployments_path),
+            start_block: self.start_block.or(defaults.start_block),
+            params_path: self.params_path.or(defaults.params_path),
+            keys_dir: self.keys_dir.or(defaults.keys_dir),
+            entries_csv: self.entries_csv.or(defaults.entries_csv),
+            signatures_csv: self.signatures_csv.or(defaults.signatures_csv),
+            exchange_name: self.exchange_name.or(defaults.exchange_name),
+            allow_legacy_messages: self.allow_legacy_messages || defaults.allow_legacy_messages,
+            message_validity: self.message_validity.or(defaults.message_validity),
+            currency_manifest: self.currency_manifest.or(defaults.currency_manifest),
+            assets_chain: self.assets_chain.or(defaults.assets_chain),
+            assets_rpc_url: self.assets_rpc_url.or(defaults.assets_rpc_url),
+            assets_block: self.assets_block.or(defaults.assets_block),
+            reports_dir: self.reports_dir.or(defaults.reports_dir),
+            snapshot_path: self.snapshot_path.or(defaults.snapshot_path),
+            proofs_dir: self.proofs_dir.or(defaults.proofs_dir),
+            timestamp: self.timestamp.or(defaults.timestamp),
+            scheme: self.scheme.or(defaults.scheme),
+            seed: self.seed.or(defaults.seed),
+            confirmations: self.confirmations.or(defaults.confirmations),
+            max_fee_per_gas: self.max_fee_per_gas.or(defaults.max_fee_per_gas),
+            max_priority_fee_per_gas: self
+                .max_priority_fee_per_gas
+                .or(defaults.max_priority_fee_per_gas),
+            replacement_timeout: self.replacement_timeout.or(defaults.replacement_timeout),
+            max_batch_gas: self.max_batch_gas.or(defaults.max_batch_gas),
+        }
+    }
+
+    /// Returns the settings with their relative paths resolved against the given directory, e.g., the one of the config file
+    fn resolve_paths(self, dir: &Path) -> Settings {
+        let resolve = |path: Option<PathBuf>| path.map(|path| dir.join(path));
+        Settings {
+            keystore_path: resolve(self.keystore_path),
+            deployments_path: resolve(self.deployments_path),
+            params_path: resolve(self.params_path),
+            keys_dir: resolve(self.keys_dir),
+            entries_csv: resolve(self.entries_csv),
+            signatures_csv: resolve(self.signatures_csv),
+            currency_manifest: resolve(self.currency_manifest),
+            reports_dir: resolve(self.reports_dir),
+            snapshot_path: resolve(self.snapshot_path),
+            proofs_dir: resolve(self.proofs_dir),
+            ..self
+        }
+    }
+
+    fn rpc_url(&self) -> Result<&str, Box<dyn Error>> {
+        self.rpc_url
+            .as_deref()
+            .ok_or_else(|| "Missing setting: rpc-url".into())
+    }
+
+    /// Returns the reader of the Summa contract, querying its logs from the start block
+    async fn reader(&self) -> Result<SummaReader, Box<dyn Error>> {
+        Ok(SummaReader::new(self.rpc_url()?, self.address_input()?)
+            .await?
+            .with_start_block(self.start_block.unwrap_or(0)))
+    }
+
+    /// Returns the signer of the exchange, from the first of the remote signer, the keystore, the mnemonic and the private key that is set
+    async fn signer(&self) -> Result<ExchangeSigner, Box<dyn Error>> {
+        let url = self.rpc_url()?;
+        if let Some(remote_signer_url) = &self.remote_signer_url {
+            let address = self
+                .signer_address
+                .as_deref()
+                .ok_or("Missing setting: signer-address")?
+                .parse()?;
+            let signer = SummaSigner::with_signer(
+                RemoteSigner::new(remote_signer_url, address),
+                url,
+                self.address_input()?,
+            )
+            .await?;
+            return Ok(ExchangeSigner::Remote(
+                signer.with_transaction_config(self.transaction_config()),
+            ));
+        }
+
+        let signer = if let Some(keystore_path) = &self.keystore_path {
+            let password = self
+                .keystore_password
+                .as_deref()
+                .ok_or("Missing setting: keystore-password (or SUMMA_KEYSTORE_PASSWORD)")?;
+            SummaSigner::from_keystore(keystore_path, password, url, self.address_input()?).await?
+        } else if let Some(mnemonic) = &self.mnemonic {
+            SummaSigner::from_mnemonic(
+                mnemonic,
+                self.derivation_path
+                    .as_deref()
+                    .unwrap_or("m/44'/60'/0'/0/0"),
+                url,
+                self.address_input()?,
+            )
+            .await?
+        } else if let Some(private_key) = &self.private_key {
+            SummaSigner::new(private_key, url, self.address_input()?).await?
+        } else {
+            return Err("Missing signer setting: one of remote-signer-url, keystore-path, mnemonic or private-key".into());
+        };
+        Ok(ExchangeSigner::Local(
+            signer.with_transaction_config(self.transaction_config()),
+        ))
+    }
+
+    fn circuit_shape(&self) -> CircuitShape {
+        CircuitShape {
+            n_currencies: self.n_currencies.unwrap_or(DEFAULT_N_CURRENCIES),
+            n_users: self.n_users.unwrap_or(DEFAULT_N_USERS),
+            k: self.k.unwrap_or(DEFAULT_K),
+        }
+    }
+
+    fn address_input(&self) -> Result<AddressInput, Box<dyn Error>> {
+        match &self.summa_address {
+            Some(address) => Ok(AddressInput::Address(address.parse()?)),
+            None => Ok(AddressInput::Path(
+                self.deployments_path()
+                    .to_str()
+                    .ok_or("Invalid deployments path")?
+                    .to_owned(),
+            )),
+        }
+    }
+
+    fn deployments_path(&self) -> PathBuf {
+        self.deployments_path
+            .clone()
+            .unwrap_or_else(|| "src/contracts/deployments.json".into())
+    }
+
+    fn params_path(&self) -> PathBuf {
+        self.params_path
+            .clone()
+            .unwrap_or_else(|| "ptau/hermez-raw-17".into())
+    }
+
+    fn keys_dir(&self) -> PathBuf {
+        self.keys_dir.clone().unwrap_or_else(|| "keys".into())
+    }
+
+    fn entries_csv(&self) -> Result<&Path, Box<dyn Error>> {
+        self.entries_csv
+            .as_deref()
+            .ok_or_else(|| "Missing setting: entries-csv".into())
+    }
+
+    fn signatures_csv(&self) -> Result<&Path, Box<dyn Error>> {
+        self.signatures_csv
+            .as_deref()
+            .ok_or_else(|| "Missing setting: signatures-csv".into())
+    }
+
+    fn exchange_name(&self) -> Result<&str, Box<dyn Error>> {
+        self.exchange_name
+            .as_deref()
+            .ok_or_else(|| "Missing setting: exchange-name".into())
+    }
+
+    /// Returns the binding of the ownership messages to the given contract, and to the exchange if its name is set.
+    /// The free-text messages are rejected unless `allow-legacy-messages` is set.
+    fn message_binding(&self, summa_address: Address, chain_id: u64) -> MessageBinding {
+        let mut binding = MessageBinding::new(summa_address, chain_id);
+        if let Some(exchange_name) = &self.exchange_name {
+            binding = binding.with_exchange(exchange_name);
+        }
+        if self.allow_legacy_messages {
+            binding = binding.allow_legacy_messages();
+        }
+        binding
+    }
+
+    fn currency_manifest(&self) -> Result<&Path, Box<dyn Error>> {
+        self.currency_manifest
+            .as_deref()
+            .ok_or_else(|| "Missing setting: currency-manifest".into())
+    }
+
+    fn assets_chain(&self) -> &str {
+        self.assets_chain.as_deref().unwrap_or("ETH")
+    }
+
+    fn reports_dir(&self) -> PathBuf {
+        self.reports_dir.clone().unwrap_or_else(|| "reports".into())
+    }
+
+    fn snapshot_path(&self) -> PathBuf {
+        self.snapshot_path
+            .clone()
+            .unwrap_or_else(|| "snapshot.bin".into())
+    }
+
+    fn proofs_dir(&self) -> PathBuf {
+        self.proofs_dir.clone().unwrap_or_else(|| "proofs".into())
+    }
+
+    fn timestamp(&self) -> Result<u64, Box<dyn Error>> {
+        match self.timestamp {
+            Some(timestamp) => Ok(timestamp),
+            None => Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()),
+        }
+    }
+
+    fn scheme(&self) -> Result<MultiOpenScheme, Box<dyn Error>> {
+        match &self.scheme {
+            Some(scheme) => Ok(scheme.parse()?),
+            None => Ok(MultiOpenScheme::default()),
+        }
+    }
+
+    fn seed(&self) -> Result<Option<ProvingSeed>, Box<dyn Error>> {
+        match &self.seed {
+            Some(seed) => Ok(Some(parse_proving_seed(seed)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn transaction_config(&self) -> TransactionConfig {
+        let defaults = TransactionConfig::default();
+        TransactionConfig {
+            max_fee_per_gas_cap: self.max_fee_per_gas.map(U256::from),
+            max_priority_fee_per_gas_cap: self.max_priority_fee_per_gas.map(U256::from),
+            confirmations: self.confirmations.unwrap_or(defaults.confirmations),
+            replacement_timeout: self
+                .replacement_timeout
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.replacement_timeout),
+            ..defaults
+        }
+    }
+}
+
+/// Reads the KZG parameters from the ptau file, downsized to the circuit size
+fn load_params(path: &Path, k: u32) -> Result<ParamsKZG<Bn256>, Box<dyn Error>> {
+    let mut params = ParamsKZG::<Bn256>::read(&mut BufReader::new(File::open(path)?))?;
+    if params.k() < k {
+        return Err(format!(
+            "The params support up to k = {}, expected {}",
+            params.k(),
+            k
+        )
+        .into());
+    }
+    if params.k() > k {
+        params.downsize(k);
+    }
+    Ok(params)
+}
+
+fn setup<const N_CURRENCIES: usize, const N_USERS: usize>(
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    let params_path = settings.params_path();
+    let (_, pk, vk) = generate_setup_artifacts(
+        settings.circuit_shape().k,
+        Some(params_path.to_str().ok_or("Invalid params path")?),
+        &SummaCircuit::<N_CURRENCIES, N_USERS>::init_empty(),
+    )?;
+
+    let keys_dir = settings.keys_dir();
+    fs::create_dir_all(&keys_dir)?;
+    pk.write(
+        &mut BufWriter::new(File::create(keys_dir.join(PROVING_KEY_FILE))?),
+        SerdeFormat::RawBytes,
+    )?;
+    vk.write(
+        &mut BufWriter::new(File::create(keys_dir.join(VERIFYING_KEY_FILE))?),
+        SerdeFormat::RawBytes,
+    )?;
+
+    println!("Keys written to {}", keys_dir.display());
+    Ok(())
+}
+
+fn prove<const N_CURRENCIES: usize, const N_USERS: usize>(
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+    let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+    parse_csv_to_entries::<&Path, N_CURRENCIES>(
+        settings.entries_csv()?,
+        &mut entries,
+        &mut cryptos,
+    )?;
+
+    let params = load_params(&settings.params_path(), settings.circuit_shape().k)?;
+    let pk = ProvingKey::<G1Affine>::read::<_, SummaCircuit<N_CURRENCIES, N_USERS>>(
+        &mut BufReader::new(File::open(settings.keys_dir().join(PROVING_KEY_FILE))?),
+        SerdeFormat::RawBytes,
+    )?;
+
+    let scheme = settings.scheme()?;
+    // This instance is necessary to verify proof on solidity verifier.
+    let instances = vec![Fp::zero(); 1];
+    let (zk_snark_proof, advice_polys, _) = full_prover(
+        &params,
+        &pk,
+        SummaCircuit::<N_CURRENCIES, N_USERS>::init(entries.clone()),
+        &[instances.clone()],
+        scheme,
+        settings.seed()?,
+    );
+    if !full_verifier(&params, pk.get_vk(), &zk_snark_proof, &[instances], scheme) {
+        return Err("The generated SNARK proof does not verify".into());
+    }
+
+    let timestamp = settings.timestamp()?;
+    let snapshot_path = settings.snapshot_path();
+    Snapshot::<N_CURRENCIES, N_USERS>::new(
+        zk_snark_proof,
+        advice_polys,
+        params,
+        pk.get_vk().clone(),
+        &entries,
+    )?
+    .save(timestamp, &snapshot_path)?;
+
+    println!(
+        "Snapshot of round {} written to {}",
+        timestamp,
+        snapshot_path.display()
+    );
+    Ok(())
+}
+
+async fn ownership_message(settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let summa_reader = settings.reader().await?;
+
+    let valid_from = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let message = OwnershipMessage {
+        exchange: settings.exchange_name()?.to_string(),
+        summa_address: summa_reader.get_summa_address(),
+        chain_id: summa_reader.get_chain_id(),
+        // The message is bound to the round it is generated for
+        nonce: settings.timestamp()?,
+        valid_from,
+        valid_until: valid_from
+            + settings
+                .message_validity
+                .unwrap_or(DEFAULT_MESSAGE_VALIDITY),
+    };
+
+    println!("Message, to be signed as is by the non-EVM addresses and put in the signatures CSV:");
+    println!("{}", message.to_message_string()?);
+    println!("Typed data, to be signed with eth_signTypedData_v4 by the EVM addresses:");
+    println!("{}", to_string_pretty(&message.to_typed_data())?);
+    Ok(())
+}
+
+async fn submit_ownership<S: Signer + 'static>(
+    signer: &SummaSigner<S>,
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>> {
+    let mut address_ownership = AddressOwnership::new(
+        signer,
+        settings
+            .signatures_csv()?
+            .to_str()
+            .ok_or("Invalid signatures CSV path")?,
+    )?
+    .with_max_batch_gas(settings.max_batch_gas.unwrap_or(DEFAULT_MAX_BATCH_GAS))
+    .with_message_binding(
+        &settings
+            .message_binding(signer.get_summa_address(), signer.get_chain_id())
+            .at_timestamp(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()),
+    );
+    for validation in address_ownership.get_validation_report() {
+        println!("Signature of {}", validation);
+    }
+    let report = address_ownership
+        .dispatch_proof_of_address_ownership()
+        .await?;
+
+    for skipped in &report.skipped {
+        println!("Skipped {}", skipped);
+    }
+    for receipt in &report.receipts {
+        println!(
+            "Submitted {} proofs of address ownership in transaction {:?} (block {}, gas used {})",
+            receipt.event.address_ownership_proofs.len(),
+            receipt.transaction_hash,
+            receipt.block_number,
+            receipt.gas_used
+        );
+    }
+    println!(
+        "{} proofs of address ownership submitted, {} skipped",
+        report.submitted.len(),
+        report.skipped.len()
+    );
+    Ok(())
+}
+
+async fn submit_commitment<const N_CURRENCIES: usize, const N_USERS: usize, S: Signer + 'static>(
+    signer: &SummaSigner<S>,
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    let params = load_params(&settings.params_path(), settings.circuit_shape().k)?;
+    let mut round = Round::<N_CURRENCIES, N_USERS, S>::load::<
+        SummaCircuit<N_CURRENCIES, N_USERS>,
+        _,
+    >(signer, settings.snapshot_path(), params)?;
+    let receipt = round.dispatch_commitment().await?;
+
+    println!(
+        "Submitted the commitment of round {} in transaction {:?} (block {}, gas used {})",
+        receipt.event.timestamp, receipt.transaction_hash, receipt.block_number, receipt.gas_used
+    );
+    Ok(())
+}
+
+async fn export_proofs<const N_CURRENCIES: usize, const N_USERS: usize>(
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    let summa_reader = settings.reader().await?;
+    let params = load_params(&settings.params_path(), settings.circuit_shape().k)?;
+    let (timestamp, snapshot) = Snapshot::<N_CURRENCIES, N_USERS>::load::<
+        SummaCircuit<N_CURRENCIES, N_USERS>,
+        _,
+    >(settings.snapshot_path(), params)?;
+    let context = RoundContext {
+        chain_id: summa_reader.get_chain_id(),
+        summa_address: summa_reader.get_summa_address(),
+        timestamp,
+        k: snapshot.get_k(),
+        cryptocurrencies: read_cryptocurrencies(&summa_reader).await?,
+    };
+
+    let proofs_dir = settings.proofs_dir();
+    fs::create_dir_all(&proofs_dir)?;
+
+    let mut n_exported = 0;
+    for user_index in 0..N_USERS {
+        let inclusion_proof = snapshot.generate_proof_of_inclusion(user_index)?;
+        // Skip the empty rows padding the entries up to the number of users of the circuit
+        if inclusion_proof.get_username() == Some("") {
+            continue;
+        }
+
+        let bundle = InclusionProofBundle::new(context.clone(), inclusion_proof)?;
+        let mut file = File::create(proofs_dir.join(format!("user_{}_proof.json", user_index)))?;
+        file.write_all(bundle.to_json()?.as_bytes())?;
+        n_exported += 1;
+    }
+
+    println!(
+        "Exported {} inclusion proofs of round {} to {}",
+        n_exported,
+        timestamp,
+        proofs_dir.display()
+    );
+    Ok(())
+}
+
+async fn status<const N_CURRENCIES: usize, const N_USERS: usize>(
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    let summa_reader = settings.reader().await?;
+
+    println!("Chain ID: {}", summa_reader.get_chain_id());
+    println!("Summa contract: {:?}", summa_reader.get_summa_address());
+    println!("Owner: {:?}", summa_reader.get_owner().await?);
+
+    println!(
+        "Proofs of address ownership: {}",
+        summa_reader.get_address_ownership_proofs().await?.len()
+    );
+
+    let commitments = summa_reader.get_commitment_events().await?;
+    println!("Commitments: {}", commitments.len());
+    for commitment in &commitments {
+        println!(
+            "  round {}: total balances {:?}",
+            commitment.timestamp, commitment.total_balances
+        );
+    }
+
+    let snapshot_path = settings.snapshot_path();
+    if snapshot_path.exists() {
+        let params = load_params(&settings.params_path(), settings.circuit_shape().k)?;
+        let (timestamp, _) = Snapshot::<N_CURRENCIES, N_USERS>::load::<
+            SummaCircuit<N_CURRENCIES, N_USERS>,
+            _,
+        >(&snapshot_path, params)?;
+        let committed = summa_reader.get_commitment(timestamp).await?.is_some();
+        println!(
+            "Snapshot {}: round {}, {}",
+            snapshot_path.display(),
+            timestamp,
+            if committed {
+                "committed"
+            } else {
+                "not committed"
+            }
+        );
+    } else {
+        println!("Snapshot {}: not found", snapshot_path.display());
+    }
+
+    Ok(())
+}
+
+async fn audit_ownership(settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let summa_reader = settings.reader().await?;
+    let proofs = summa_reader.get_address_ownership_proofs().await?;
+
+    // The proofs are audited after their submission, so their validity window is not checked
+    let validations = SignatureVerifiers::default().validate_with_binding(
+        &proofs,
+        &settings.message_binding(
+            summa_reader.get_summa_address(),
+            summa_reader.get_chain_id(),
+        ),
+    );
+    for validation in &validations {
+        println!("Signature of {}", validation);
+    }
+
+    let n_rejected = validations
+        .iter()
+        .filter(|validation| validation.result.is_rejected())
+        .count();
+    let n_unverified = validations
+        .iter()
+        .filter(|validation| validation.result == SignatureValidation::Unsupported)
+        .count();
+    println!(
+        "{} proofs of address ownership: {} valid, {} rejected, {} not verified",
+        validations.len(),
+        validations.len() - n_rejected - n_unverified,
+        n_rejected,
+        n_unverified
+    );
+
+    if n_rejected > 0 {
+        return Err(format!("{} proofs of address ownership are rejected", n_rejected).into());
+    }
+    Ok(())
+}
+
+/// Collects the assets of the addresses of the assets chain registered on the Summa contract
+async fn collect_assets(
+    settings: &Settings,
+    summa_reader: &SummaReader,
+) -> Result<AssetReport, Box<dyn Error>> {
+    let proofs = summa_reader.get_address_ownership_proofs().await?;
+    let assets_rpc_url = match &settings.assets_rpc_url {
+        Some(url) => url.as_str(),
+        None => settings.rpc_url()?,
+    };
+    AssetCollector::new(CurrencyManifest::from_file(settings.currency_manifest()?)?)
+        .with_chain(
+            settings.assets_chain(),
+            assets_rpc_url,
+            settings.assets_block,
+        )?
+        .collect(&proofs)
+        .await
+}
+
+/// Returns the cryptocurrencies of the Summa contract, in the order of the liabilities
+async fn read_cryptocurrencies(
+    summa_reader: &SummaReader,
+) -> Result<Vec<Cryptocurrency>, Box<dyn Error>> {
+    let config = summa_reader.get_config().await?;
+    Ok(config
+        .cryptocurrency_names
+        .into_iter()
+        .zip(config.cryptocurrency_chains)
+        .map(|(name, chain)| Cryptocurrency { name, chain })
+        .collect())
+}
+
+async fn assets<const N_CURRENCIES: usize, const N_USERS: usize>(
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    let summa_reader = settings.reader().await?;
+    let assets_chain = settings.assets_chain();
+    let report = collect_assets(settings, &summa_reader).await?;
+
+    println!(
+        "Balances of the {} addresses at block {}:",
+        assets_chain, report.blocks[assets_chain]
+    );
+    for balance in &report.balances {
+        println!(
+            "  {:?}: {} {}",
+            balance.cex_address, balance.balance, balance.cryptocurrency.name
+        );
+    }
+    println!(
+        "Addresses of other chains not collected: {}",
+        report.skipped.len()
+    );
+
+    let snapshot_path = settings.snapshot_path();
+    if !snapshot_path.exists() {
+        for (cryptocurrency, total) in &report.totals {
+            println!(
+                "Total {} ({}): {}",
+                cryptocurrency.name, cryptocurrency.chain, total
+            );
+        }
+        println!("Snapshot {}: not found", snapshot_path.display());
+        return Ok(());
+    }
+
+    // The liabilities are the grand sums of the snapshot, in the order of the cryptocurrencies of the contract
+    let params = load_params(&settings.params_path(), settings.circuit_shape().k)?;
+    let (timestamp, snapshot) = Snapshot::<N_CURRENCIES, N_USERS>::load::<
+        SummaCircuit<N_CURRENCIES, N_USERS>,
+        _,
+    >(&snapshot_path, params)?;
+    let checks = report.compare(
+        &read_cryptocurrencies(&summa_reader).await?,
+        snapshot.generate_grand_sum_proof()?.get_input_values(),
+    )?;
+
+    println!("Solvency of round {}:", timestamp);
+    for check in &checks {
+        println!("  {}", check);
+    }
+    if checks.iter().any(|check| !check.is_solvent()) {
+        return Err("The assets do not cover the liabilities".into());
+    }
+    Ok(())
+}
+
+async fn report<const N_CURRENCIES: usize, const N_USERS: usize>(
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    let summa_reader = settings.reader().await?;
+    let assets = collect_assets(settings, &summa_reader).await?;
+
+    let params = load_params(&settings.params_path(), settings.circuit_shape().k)?;
+    let (timestamp, snapshot) = Snapshot::<N_CURRENCIES, N_USERS>::load::<
+        SummaCircuit<N_CURRENCIES, N_USERS>,
+        _,
+    >(settings.snapshot_path(), params)?;
+    let context = RoundContext {
+        chain_id: summa_reader.get_chain_id(),
+        summa_address: summa_reader.get_summa_address(),
+        timestamp,
+        k: snapshot.get_k(),
+        cryptocurrencies: read_cryptocurrencies(&summa_reader).await?,
+    };
+    let liabilities = snapshot
+        .generate_grand_sum_proof()?
+        .get_input_values()
+        .clone();
+    let report = SolvencyReport::generate(&summa_reader, context, &liabilities, &assets).await?;
+
+    let reports_dir = settings.reports_dir();
+    fs::create_dir_all(&reports_dir)?;
+    let report_path = reports_dir.join(format!("round_{}", timestamp));
+    fs::write(report_path.with_extension("json"), report.to_json()?)?;
+    fs::write(report_path.with_extension("md"), report.to_markdown())?;
+    fs::write(report_path.with_extension("html"), report.to_html())?;
+
+    print!("{}", report.to_markdown());
+    println!(
+        "Report of round {} written to {}",
+        timestamp,
+        reports_dir.display()
+    );
+    Ok(())
+}
+
+/// Runs the command with the circuit of the given number of currencies and users
+async fn run<const N_CURRENCIES: usize, const N_USERS: usize>(
+    command: Command,
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    match command {
+        Command::Setup => setup::<N_CURRENCIES, N_USERS>(settings),
+        Command::Prove => prove::<N_CURRENCIES, N_USERS>(settings),
+        Command::OwnershipMessage => ownership_message(settings).await,
+        Command::SubmitOwnership => match settings.signer().await? {
+            ExchangeSigner::Local(signer) => submit_ownership(&signer, settings).await,
+            ExchangeSigner::Remote(signer) => submit_ownership(&signer, settings).await,
+        },
+        Command::SubmitCommitment => match settings.signer().await? {
+            ExchangeSigner::Local(signer) => {
+                submit_commitment::<N_CURRENCIES, N_USERS, _>(&signer, settings).await
+            }
+            ExchangeSigner::Remote(signer) => {
+                submit_commitment::<N_CURRENCIES, N_USERS, _>(&signer, settings).await
+            }
+        },
+        Command::ExportProofs => export_proofs::<N_CURRENCIES, N_USERS>(settings).await,
+        Command::Status => status::<N_CURRENCIES, N_USERS>(settings).await,
+        Command::AuditOwnership => audit_ownership(settings).await,
+        Command::Assets => assets::<N_CURRENCIES, N_USERS>(settings).await,
+        Command::Report => report::<N_CURRENCIES, N_USERS>(settings).await,
+    }
+}
+
+/// Runs the command with the circuit of the shape given in the settings, among the listed `(n_currencies, n_users)`
+macro_rules! run_with_circuit_shape {
+    ($command:expr, $settings:expr, [$(($n_currencies:literal, $n_users:literal)),+ $(,)?]) => {{
+        let shape = $settings.circuit_shape();
+        match (shape.n_currencies, shape.n_users) {
+            $(($n_currencies, $n_users) => run::<$n_currencies, $n_users>($command, $settings).await,)+
+            (n_currencies, n_users) => Err(format!(
+                "Unsupported circuit shape: {} currencies and {} users",
+                n_currencies, n_users
+            )
+            .into()),
+        }
+    }};
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    let settings = match &cli.config {
+        Some(path) => {
+            let config: Settings = toml::from_str(&fs::read_to_string(path)?)?;
+            cli.settings
+                .or(config.resolve_paths(path.parent().unwrap_or_else(|| Path::new(""))))
+        }
+        None => cli.settings,
+    };
+
+    // The shapes the tool is built for, each one compiling its own instance of the circuit
+    run_with_circuit_shape!(
+        cli.command,
+        &settings,
+        [
+            (1, 16),
+            (2, 16),
+            (3, 16),
+            (1, 1024),
+            (2, 1024),
+            (3, 1024),
+            (1, 65536),
+            (2, 65536),
+            (3, 65536),
+        ]
+    )
+}
@@ -0,0 +1,138 @@
+//! Command-line tool for the users to check the inclusion proof bundle they received from the exchange.
+//!
+//! The proof is checked against the commitment of its round on the Summa contract, both with `verifyInclusionProof`
+//! through an `eth_call` and offline with pairings. In both checks, the challenge `[ω^i]_2 - [s]_2` is recomputed
+//! from the verifying key contract of the Summa contract and the user index `i`, instead of being taken from the proof.
+//! The username and the balances of the user are then printed, with the cryptocurrency names read from the
+//! configuration of the contract.
+use std::{error::Error, fs, path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use ethers::types::Address;
+
+use summa_backend::{
+    apis::{
+        bundle::InclusionProofBundle,
+        verification::{
+            decode_username, verify_inclusion_proof_with_verifying_key, DeployedVerifyingKey,
+        },
+    },
+    contracts::{reader::SummaReader, signer::AddressInput},
+};
+use summa_solvency::cryptocurrency::Cryptocurrency;
+
+#[derive(Parser)]
+#[command(
+    name = "summa-verify",
+    about = "Verify a Summa inclusion proof against the chain"
+)]
+struct Cli {
+    /// Path to the inclusion proof bundle JSON received from the exchange
+    proof: PathBuf,
+
+    /// Endpoint of the JSON-RPC node
+    #[arg(long, env = "SUMMA_RPC_URL")]
+    rpc_url: String,
+
+    /// Address of the Summa contract of the exchange, which has to match the one of the bundle
+    #[arg(long, env = "SUMMA_ADDRESS")]
+    summa_address: Option<Address>,
+}
+
+async fn verify(cli: &Cli) -> Result<bool, Box<dyn Error>> {
+    let bundle = InclusionProofBundle::from_json(&fs::read_to_string(&cli.proof)?)?;
+    let context = &bundle.context;
+    let inclusion_proof = &bundle.proof;
+
+    if let Some(summa_address) = cli.summa_address {
+        if summa_address != context.summa_address {
+            return Err(format!(
+                "The bundle is for the Summa contract {:?}, expected {:?}",
+                context.summa_address, summa_address
+            )
+            .into());
+        }
+    }
+    let summa_reader =
+        SummaReader::new(&cli.rpc_url, AddressInput::Address(context.summa_address)).await?;
+    if summa_reader.get_chain_id() != context.chain_id {
+        return Err(format!(
+            "The bundle is for the chain {}, the node is on the chain {}",
+            context.chain_id,
+            summa_reader.get_chain_id()
+        )
+        .into());
+    }
+
+    let commitment = summa_reader
+        .get_commitment(context.timestamp)
+        .await?
+        .ok_or_else(|| {
+            format!(
+                "No commitment was submitted for round {}",
+                context.timestamp
+            )
+        })?;
+
+    // The challenge of the row of the user is recomputed from the verifying key contract, not read from the proof
+    let verifying_key =
+        DeployedVerifyingKey::from_code(&summa_reader.get_verifying_key_code().await?)?;
+    let user_index = inclusion_proof
+        .get_user_index()
+        .ok_or("The inclusion proof does not record the user index")?;
+    let challenge_matches =
+        inclusion_proof.get_challenge().as_ref() == Some(&verifying_key.challenge_s_g2(user_index));
+
+    let verified_onchain = challenge_matches
+        && summa_reader
+            .verify_inclusion_proof(context.timestamp, inclusion_proof)
+            .await?;
+    let verified_offline =
+        verify_inclusion_proof_with_verifying_key(&verifying_key, &commitment, inclusion_proof)?;
+
+    let config = summa_reader.get_config().await?;
+    let cryptocurrencies: Vec<Cryptocurrency> = config
+        .cryptocurrency_names
+        .into_iter()
+        .zip(config.cryptocurrency_chains)
+        .map(|(name, chain)| Cryptocurrency { name, chain })
+        .collect();
+    if cryptocurrencies != context.cryptocurrencies {
+        return Err(
+            "The cryptocurrencies of the bundle do not match the contract configuration".into(),
+        );
+    }
+
+    let values = inclusion_proof.get_input_values();
+    println!("Round: {}", context.timestamp);
+    println!("Username: {}", decode_username(&values[0])?);
+    for (cryptocurrency, balance) in bundle.balances() {
+        println!(
+            "Balance {} ({}): {}",
+            cryptocurrency.name, cryptocurrency.chain, balance
+        );
+    }
+    println!("User index: {}", user_index);
+    println!("Challenge of the user index: {}", challenge_matches);
+    println!("On-chain verification: {}", verified_onchain);
+    println!("Offline verification: {}", verified_offline);
+
+    Ok(verified_onchain && verified_offline)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match verify(&cli).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => {
+            eprintln!("The inclusion proof is invalid");
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -16,7 +16,7 @@ use summa_backend::{
 use summa_solvency::{
     circuits::{
         univariate_grand_sum::{UnivariateGrandSum, UnivariateGrandSumConfig},
-        utils::{full_prover, generate_setup_artifacts},
+        utils::{full_prover, generate_setup_artifacts, MultiOpenScheme},
     },
     cryptocurrency::Cryptocurrency,
     entry::Entry,
@@ -53,11 +53,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
     .await?;
 
     // Each CEX prepares its own `signature` CSV file.
+    // The example file holds free-text messages, which have to be allowed explicitly.
     let signature_csv_path = "../csv/signatures.csv";
-    let mut address_ownership_client = AddressOwnership::new(&signer, signature_csv_path).unwrap();
+    let mut address_ownership_client = AddressOwnership::new(&signer, signature_csv_path)
+        .unwrap()
+        .allow_legacy_messages();
 
     // Dispatch the proof of address ownership.
-    // the `dispatch_proof_of_address_ownership` function sends a transaction to the Summa contract.
+    // the `dispatch_proof_of_address_ownership` function sends transactions to the Summa contract, skipping the registered addresses.
     address_ownership_client
         .dispatch_proof_of_address_ownership()
         .await?;
@@ -91,6 +94,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         &pk,
         univariate_grand_sum_circuit.clone(),
         &[instances.clone()],
+        MultiOpenScheme::Shplonk,
+        None,
     );
 
     // Using the `round` instance, the commitment is dispatched to the Summa contract with the `dispatch_commitment` method.
@@ -101,8 +106,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
         advice_polys,
         params,
         vk,
+        &entries,
         timestamp,
-    );
+    )?;
 
     // Sends the commitment, which should ideally complete without errors.
     round.dispatch_commitment().await?;
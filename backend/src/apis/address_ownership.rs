@@ -1,38 +1,259 @@
-use crate::contracts::{generated::summa_contract::AddressOwnershipProof, signer::SummaSigner};
-use std::{error::Error, result::Result};
+use crate::contracts::{
+    generated::summa_contract::AddressOwnershipProof, reader::hash_address, signer::SummaSigner,
+    transaction::AddressOwnershipReceipt,
+};
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::U256,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    result::Result,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use super::csv_parser::parse_signature_csv;
+use super::{
+    csv_parser::parse_signature_csv,
+    ownership_message::MessageBinding,
+    signature_verification::{RowValidation, SignatureVerifiers},
+};
 
-pub struct AddressOwnership<'a> {
+/// The default upper bound of the gas used by a single submission of proofs of address ownership
+pub const DEFAULT_MAX_BATCH_GAS: u64 = 10_000_000;
+
+/// The reason why a proof of address ownership is not submitted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The address is already registered on the Summa contract
+    AlreadyRegistered,
+    /// The address is the same as the one of an earlier row of the CSV
+    Duplicate { first_row: usize },
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::AlreadyRegistered => write!(f, "already registered"),
+            SkipReason::Duplicate { first_row } => write!(f, "duplicate of row {}", first_row),
+        }
+    }
+}
+
+/// A proof of address ownership which is not submitted, by its row in the CSV
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedProof {
+    /// The 1-based row of the proof in the CSV, excluding the header
+    pub row: usize,
+    pub chain: String,
+    pub cex_address: String,
+    pub reason: SkipReason,
+}
+
+impl fmt::Display for SkippedProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "row {} ({} {}): {}",
+            self.row, self.chain, self.cex_address, self.reason
+        )
+    }
+}
+
+/// The outcome of the dispatch of the proofs of address ownership
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnershipDispatchReport {
+    /// The submitted proofs, in the order of the CSV
+    pub submitted: Vec<AddressOwnershipProof>,
+    /// The proofs which are not submitted, along with the reason
+    pub skipped: Vec<SkippedProof>,
+    /// The receipts of the transactions submitting the batches of proofs, in order
+    pub receipts: Vec<AddressOwnershipReceipt>,
+}
+
+pub struct AddressOwnership<'a, S: Signer = LocalWallet> {
     address_ownership_proofs: Vec<AddressOwnershipProof>,
-    signer: &'a SummaSigner,
+    validations: Vec<RowValidation>,
+    max_batch_gas: U256,
+    binding: MessageBinding,
+    signer: &'a SummaSigner<S>,
 }
 
-impl AddressOwnership<'_> {
+impl<S: Signer + 'static> AddressOwnership<'_, S> {
+    /// Creates a new AddressOwnership instance from the signatures CSV.
+    ///
+    /// The ownership messages are expected to be structured messages bound to the Summa contract of the signer and
+    /// valid at the current time, which can be changed with `with_exchange`, `allow_legacy_messages` and
+    /// `with_message_binding`.
     pub fn new<'a>(
-        signer: &'a SummaSigner,
+        signer: &'a SummaSigner<S>,
         signature_csv_path: &str,
-    ) -> Result<AddressOwnership<'a>, Box<dyn Error>> {
+    ) -> Result<AddressOwnership<'a, S>, Box<dyn Error>> {
         let address_ownership_proofs = parse_signature_csv(signature_csv_path)?;
+        let binding = MessageBinding::new(signer.get_summa_address(), signer.get_chain_id())
+            .at_timestamp(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs());
+        let validations = SignatureVerifiers::default()
+            .validate_with_binding(&address_ownership_proofs, &binding);
 
         Ok(AddressOwnership {
             address_ownership_proofs,
+            validations,
+            max_batch_gas: U256::from(DEFAULT_MAX_BATCH_GAS),
+            binding,
             signer,
         })
     }
 
+    /// Sets the upper bound of the gas used by a single submission, above which the proofs are split into batches
+    pub fn with_max_batch_gas(mut self, max_batch_gas: u64) -> Self {
+        self.max_batch_gas = U256::from(max_batch_gas);
+        self
+    }
+
+    /// Validates the proofs again against the given binding of the ownership messages
+    pub fn with_message_binding(mut self, binding: &MessageBinding) -> Self {
+        self.validations = SignatureVerifiers::default()
+            .validate_with_binding(&self.address_ownership_proofs, binding);
+        self.binding = binding.clone();
+        self
+    }
+
+    /// Validates the proofs again, requiring the messages to be signed for the given exchange
+    pub fn with_exchange(self, exchange: &str) -> Self {
+        let binding = self.binding.clone().with_exchange(exchange);
+        self.with_message_binding(&binding)
+    }
+
+    /// Validates the proofs again, accepting the free-text messages signed before the structured ones
+    pub fn allow_legacy_messages(self) -> Self {
+        let binding = self.binding.clone().allow_legacy_messages();
+        self.with_message_binding(&binding)
+    }
+
     pub fn get_ownership_proofs(&self) -> &Vec<AddressOwnershipProof> {
         &self.address_ownership_proofs
     }
 
-    // This function dispatches the proof of address ownership. Before calling this function,
-    // ensure externally that the provided `addresses` in `address_ownership_proof` are not already registered
-    // on the Summa contract.
-    pub async fn dispatch_proof_of_address_ownership(&mut self) -> Result<(), Box<dyn Error>> {
-        self.signer
-            .submit_proof_of_address_ownership(self.address_ownership_proofs.clone())
-            .await?;
+    /// Returns the results of the off-chain verification of the signatures, one per row of the CSV
+    pub fn get_validation_report(&self) -> &Vec<RowValidation> {
+        &self.validations
+    }
+
+    /// Returns the proofs to submit along with the skipped ones, i.e., the repeated addresses of the CSV and the
+    /// addresses already registered on the Summa contract
+    async fn select_new_proofs(
+        &self,
+    ) -> Result<(Vec<AddressOwnershipProof>, Vec<SkippedProof>), Box<dyn Error>> {
+        let mut first_rows: HashMap<[u8; 32], usize> = HashMap::new();
+        let mut new_proofs = Vec::new();
+        let mut skipped = Vec::new();
+
+        for (index, proof) in self.address_ownership_proofs.iter().enumerate() {
+            let row = index + 1;
+            // The Summa contract indexes the proofs by the hash of the address only, regardless of the chain
+            let address_hash = hash_address(&proof.cex_address);
+            let reason = if let Some(&first_row) = first_rows.get(&address_hash) {
+                Some(SkipReason::Duplicate { first_row })
+            } else if self
+                .signer
+                .get_reader()
+                .get_address_ownership_proof(address_hash)
+                .await?
+                .is_some()
+            {
+                Some(SkipReason::AlreadyRegistered)
+            } else {
+                None
+            };
+            first_rows.entry(address_hash).or_insert(row);
+
+            match reason {
+                Some(reason) => skipped.push(SkippedProof {
+                    row,
+                    chain: proof.chain.clone(),
+                    cex_address: proof.cex_address.clone(),
+                    reason,
+                }),
+                None => new_proofs.push(proof.clone()),
+            }
+        }
+
+        Ok((new_proofs, skipped))
+    }
+
+    /// Returns the number of the first proofs whose submission fits in the max batch gas, halving the batch until it fits
+    async fn next_batch_size(
+        &self,
+        proofs: &[AddressOwnershipProof],
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut batch_size = proofs.len();
+        loop {
+            let gas = self
+                .signer
+                .estimate_proof_of_address_ownership_gas(proofs[..batch_size].to_vec())
+                .await?;
+            if gas <= self.max_batch_gas {
+                return Ok(batch_size);
+            }
+            if batch_size == 1 {
+                return Err(format!(
+                    "The submission of the proof of {} needs {} gas, above the max batch gas {}",
+                    proofs[0].cex_address, gas, self.max_batch_gas
+                )
+                .into());
+            }
+            batch_size = (batch_size + 1) / 2;
+        }
+    }
+
+    /// Dispatches the proofs of address ownership to the Summa contract.
+    ///
+    /// Nothing is sent if the signature of any row is rejected by the off-chain verification.
+    /// The addresses repeated in the CSV or already registered on the Summa contract are skipped,
+    /// as the contract reverts with "Address already verified" for them, and the remaining proofs are submitted
+    /// in batches whose gas is bounded by the max batch gas. As the registered addresses are skipped,
+    /// the dispatch can be run again to resume after a failed batch.
+    ///
+    /// # Returns
+    ///
+    /// * `OwnershipDispatchReport` - the submitted and skipped proofs, with the receipts of the batches
+    pub async fn dispatch_proof_of_address_ownership(
+        &mut self,
+    ) -> Result<OwnershipDispatchReport, Box<dyn Error>> {
+        let rejected_rows: Vec<String> = self
+            .validations
+            .iter()
+            .filter(|validation| validation.result.is_rejected())
+            .map(|validation| validation.to_string())
+            .collect();
+        if !rejected_rows.is_empty() {
+            return Err(format!(
+                "Rejected proofs of address ownership: {}",
+                rejected_rows.join("; ")
+            )
+            .into());
+        }
+
+        let (new_proofs, skipped) = self.select_new_proofs().await?;
 
-        Ok(())
+        let mut remaining_proofs = new_proofs.as_slice();
+        let mut receipts = Vec::new();
+        while !remaining_proofs.is_empty() {
+            let batch_size = self.next_batch_size(remaining_proofs).await?;
+            let (batch, rest) = remaining_proofs.split_at(batch_size);
+            receipts.push(
+                self.signer
+                    .submit_proof_of_address_ownership(batch.to_vec())
+                    .await?,
+            );
+            remaining_proofs = rest;
+        }
+
+        Ok(OwnershipDispatchReport {
+            submitted: new_proofs,
+            skipped,
+            receipts,
+        })
     }
 }
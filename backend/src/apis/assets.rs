@@ -0,0 +1,284 @@
+//! Collection of the assets held by the addresses whose ownership is proven on the Summa contract.
+//!
+//! Summa proves the liabilities of the exchange. The assets are the balances of the proven addresses, which are
+//! fetched at a given block for each cryptocurrency of the currency manifest, either the native coin of the chain
+//! or an ERC-20 token, and compared with the grand sums of the liabilities of a round.
+use std::{collections::HashMap, error::Error, fmt, fs::File, io::BufReader, path::Path};
+
+use ethers::{
+    abi::{self, Token},
+    providers::{Http, Middleware, Provider},
+    types::{Address, BlockId, Bytes, TransactionRequest, U256},
+    utils::id,
+};
+use serde::{Deserialize, Serialize};
+use summa_solvency::cryptocurrency::Cryptocurrency;
+
+use crate::contracts::generated::summa_contract::AddressOwnershipProof;
+
+/// A cryptocurrency of the currency manifest, along with the contract of its token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CurrencyAsset {
+    pub name: String,
+    /// The chain of the cryptocurrency, matching the `chain` of the proofs of address ownership
+    pub chain: String,
+    /// The ERC-20 contract of the token, or `None` for the native coin of the chain
+    #[serde(default)]
+    pub token: Option<Address>,
+}
+
+impl CurrencyAsset {
+    pub fn get_cryptocurrency(&self) -> Cryptocurrency {
+        Cryptocurrency {
+            name: self.name.clone(),
+            chain: self.chain.clone(),
+        }
+    }
+}
+
+/// The cryptocurrencies whose assets are collected, as listed in a JSON currency manifest such as
+/// `{"currencies": [{"name": "ETH", "chain": "ETH"}, {"name": "USDT", "chain": "ETH", "token": "0x..."}]}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CurrencyManifest {
+    pub currencies: Vec<CurrencyAsset>,
+}
+
+impl CurrencyManifest {
+    /// Reads the currency manifest from a JSON file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+/// The balance of a cryptocurrency held by a proven address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressBalance {
+    pub cex_address: Address,
+    pub cryptocurrency: Cryptocurrency,
+    pub balance: U256,
+}
+
+/// The comparison of the assets and the liabilities of a cryptocurrency
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolvencyCheck {
+    pub cryptocurrency: Cryptocurrency,
+    pub assets: U256,
+    pub liabilities: U256,
+}
+
+impl SolvencyCheck {
+    /// Returns whether the assets cover the liabilities
+    pub fn is_solvent(&self) -> bool {
+        self.assets >= self.liabilities
+    }
+}
+
+impl fmt::Display for SolvencyCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}): assets {}, liabilities {}, {}",
+            self.cryptocurrency.name,
+            self.cryptocurrency.chain,
+            self.assets,
+            self.liabilities,
+            if self.is_solvent() {
+                "solvent"
+            } else {
+                "insolvent"
+            }
+        )
+    }
+}
+
+/// The assets collected for the proven addresses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetReport {
+    /// The block the balances of each chain are read at
+    pub blocks: HashMap<String, u64>,
+    /// The balance of each cryptocurrency of the chain of each proven address
+    pub balances: Vec<AddressBalance>,
+    /// The total balance of each cryptocurrency of the manifest
+    pub totals: Vec<(Cryptocurrency, U256)>,
+    /// The proofs whose address is not collected, as their chain has no endpoint or their address is not an EVM one
+    pub skipped: Vec<AddressOwnershipProof>,
+}
+
+impl AssetReport {
+    /// Returns the total balance of the given cryptocurrency, zero if it is not in the manifest
+    pub fn get_total(&self, cryptocurrency: &Cryptocurrency) -> U256 {
+        self.totals
+            .iter()
+            .find(|(total_cryptocurrency, _)| total_cryptocurrency == cryptocurrency)
+            .map(|(_, total)| *total)
+            .unwrap_or_default()
+    }
+
+    /// Compares the total assets with the liabilities of each cryptocurrency.
+    ///
+    /// The liabilities have to be expressed in the smallest unit of the cryptocurrency, like the balances.
+    ///
+    /// # Arguments
+    ///
+    /// * `cryptocurrencies` - the cryptocurrencies of the round, in the order of the Summa contract config
+    /// * `liabilities` - the grand sums of the balances of the users of the round, in the same order
+    pub fn compare(
+        &self,
+        cryptocurrencies: &[Cryptocurrency],
+        liabilities: &[U256],
+    ) -> Result<Vec<SolvencyCheck>, Box<dyn Error>> {
+        if cryptocurrencies.len() != liabilities.len() {
+            return Err(format!(
+                "{} cryptocurrencies for {} liabilities",
+                cryptocurrencies.len(),
+                liabilities.len()
+            )
+            .into());
+        }
+
+        Ok(cryptocurrencies
+            .iter()
+            .zip(liabilities)
+            .map(|(cryptocurrency, liabilities)| SolvencyCheck {
+                cryptocurrency: cryptocurrency.clone(),
+                assets: self.get_total(cryptocurrency),
+                liabilities: *liabilities,
+            })
+            .collect())
+    }
+}
+
+/// The `AssetCollector` fetches the balances of the proven EVM addresses from the node of each chain
+pub struct AssetCollector {
+    manifest: CurrencyManifest,
+    endpoints: HashMap<String, (Provider<Http>, Option<u64>)>,
+}
+
+impl AssetCollector {
+    pub fn new(manifest: CurrencyManifest) -> Self {
+        AssetCollector {
+            manifest,
+            endpoints: HashMap::new(),
+        }
+    }
+
+    /// Sets the node the balances of a chain are read from
+    ///
+    /// # Arguments
+    ///
+    /// * `chain` - the chain, as named in the proofs of address ownership and the manifest
+    /// * `url` - the endpoint of the node of the chain
+    /// * `block` - the block the balances are read at, the latest one at the time of the collection if `None`
+    pub fn with_chain(
+        mut self,
+        chain: &str,
+        url: &str,
+        block: Option<u64>,
+    ) -> Result<Self, Box<dyn Error>> {
+        self.endpoints
+            .insert(chain.to_string(), (Provider::try_from(url)?, block));
+        Ok(self)
+    }
+
+    async fn get_token_balance(
+        provider: &Provider<Http>,
+        token: Address,
+        owner: Address,
+        block: BlockId,
+    ) -> Result<U256, Box<dyn Error>> {
+        let data = [
+            &id("balanceOf(address)")[..],
+            &abi::encode(&[Token::Address(owner)]),
+        ]
+        .concat();
+        let call = TransactionRequest::new().to(token).data(Bytes::from(data));
+        let result = provider.call(&call.into(), Some(block)).await?;
+        if result.len() != 32 {
+            return Err(format!("Unexpected balanceOf result of the token {:?}", token).into());
+        }
+        Ok(U256::from_big_endian(&result))
+    }
+
+    /// Collects the balances of the cryptocurrencies of the manifest held by the proven addresses
+    ///
+    /// # Arguments
+    ///
+    /// * `proofs` - the proofs of address ownership, e.g., as registered on the Summa contract
+    ///
+    /// # Returns
+    ///
+    /// * `AssetReport` - the balances per address and the totals per cryptocurrency
+    pub async fn collect(
+        &self,
+        proofs: &[AddressOwnershipProof],
+    ) -> Result<AssetReport, Box<dyn Error>> {
+        // All the balances of a chain are read at the same block
+        let mut blocks = HashMap::new();
+        for (chain, (provider, block)) in &self.endpoints {
+            let block = match block {
+                Some(block) => *block,
+                None => provider.get_block_number().await?.as_u64(),
+            };
+            blocks.insert(chain.clone(), block);
+        }
+
+        let mut balances = Vec::new();
+        let mut skipped = Vec::new();
+        for proof in proofs {
+            let (provider, cex_address) = match (
+                self.endpoints.get(&proof.chain),
+                proof.cex_address.parse::<Address>(),
+            ) {
+                (Some((provider, _)), Ok(cex_address)) => (provider, cex_address),
+                _ => {
+                    skipped.push(proof.clone());
+                    continue;
+                }
+            };
+            let block = BlockId::from(blocks[&proof.chain]);
+
+            for asset in self
+                .manifest
+                .currencies
+                .iter()
+                .filter(|asset| asset.chain == proof.chain)
+            {
+                let balance = match asset.token {
+                    Some(token) => {
+                        Self::get_token_balance(provider, token, cex_address, block).await?
+                    }
+                    None => provider.get_balance(cex_address, Some(block)).await?,
+                };
+                balances.push(AddressBalance {
+                    cex_address,
+                    cryptocurrency: asset.get_cryptocurrency(),
+                    balance,
+                });
+            }
+        }
+
+        let totals = self
+            .manifest
+            .currencies
+            .iter()
+            .map(|asset| {
+                let cryptocurrency = asset.get_cryptocurrency();
+                let total = balances
+                    .iter()
+                    .filter(|balance| balance.cryptocurrency == cryptocurrency)
+                    .fold(U256::zero(), |total, balance| total + balance.balance);
+                (cryptocurrency, total)
+            })
+            .collect();
+
+        Ok(AssetReport {
+            blocks,
+            balances,
+            totals,
+            skipped,
+        })
+    }
+}
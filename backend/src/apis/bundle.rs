@@ -0,0 +1,216 @@
+use ethers::types::{Address, Bytes, U256};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::error::Error;
+
+use super::round::{KZGProof, Snapshot};
+use summa_solvency::cryptocurrency::Cryptocurrency;
+
+/// The version of the bundle formats, to be bumped on any breaking change of the bundles.
+///
+/// The bare `KZGProof` JSON, which predates the bundles, is considered as version 0.
+pub const BUNDLE_VERSION: u16 = 1;
+
+/// Magic bytes at the start of a binary inclusion proof bundle
+const INCLUSION_PROOF_BUNDLE_MAGIC: [u8; 8] = *b"SUMMAIPB";
+
+/// Magic bytes at the start of a binary commitment bundle
+const COMMITMENT_BUNDLE_MAGIC: [u8; 8] = *b"SUMMACMB";
+
+/// The context of a round, needed to interpret its proofs without any other source of information
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundContext {
+    /// The ID of the chain the Summa contract is deployed on
+    pub chain_id: u64,
+    /// The address of the Summa contract the commitment is submitted to
+    pub summa_address: Address,
+    /// The timestamp of the round, i.e., the key of the commitment on the Summa contract
+    pub timestamp: u64,
+    /// The degree parameter of the circuit
+    pub k: u32,
+    /// The cryptocurrencies of the balance columns, in order
+    pub cryptocurrencies: Vec<Cryptocurrency>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BundleHeader {
+    magic: [u8; 8],
+    version: u16,
+}
+
+#[derive(Deserialize)]
+struct JsonVersion {
+    version: Option<u16>,
+}
+
+fn check_version(version: u16) -> Result<(), Box<dyn Error>> {
+    if version != BUNDLE_VERSION {
+        return Err(format!(
+            "Unsupported bundle version {}, expected {}",
+            version, BUNDLE_VERSION
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, Box<dyn Error>> {
+    match serde_json::from_str::<JsonVersion>(json)?.version {
+        Some(version) => check_version(version)?,
+        None => return Err(
+            "Not a versioned bundle, a bare KZGProof has to be migrated with `from_kzg_proof_json`"
+                .into(),
+        ),
+    }
+    Ok(serde_json::from_str(json)?)
+}
+
+fn to_bytes<T: Serialize>(magic: [u8; 8], bundle: &T) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut bytes = bincode::serialize(&BundleHeader {
+        magic,
+        version: BUNDLE_VERSION,
+    })?;
+    bytes.extend(bincode::serialize(bundle)?);
+    Ok(bytes)
+}
+
+fn from_bytes<T: DeserializeOwned>(magic: [u8; 8], mut bytes: &[u8]) -> Result<T, Box<dyn Error>> {
+    let header: BundleHeader = bincode::deserialize_from(&mut bytes)?;
+    if header.magic != magic {
+        return Err("Not a bundle of the expected kind".into());
+    }
+    check_version(header.version)?;
+    Ok(bincode::deserialize(bytes)?)
+}
+
+/// Self-describing inclusion proof of a user, carrying the context of its round along with the `KZGProof`.
+///
+/// The bundle is encoded either as JSON, for the users, or as a compact binary, for the storage and the transport.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InclusionProofBundle {
+    pub version: u16,
+    pub context: RoundContext,
+    pub proof: KZGProof,
+}
+
+impl InclusionProofBundle {
+    pub fn new(context: RoundContext, proof: KZGProof) -> Result<Self, Box<dyn Error>> {
+        InclusionProofBundle {
+            version: BUNDLE_VERSION,
+            context,
+            proof,
+        }
+        .validate()
+    }
+
+    /// Checks that the proof is an inclusion proof matching the context, as done on every construction and decoding
+    fn validate(self) -> Result<Self, Box<dyn Error>> {
+        check_version(self.version)?;
+        if self.proof.get_challenge().is_none() {
+            return Err("The proof is not an inclusion proof".into());
+        }
+        if self.proof.get_input_values().len() != self.context.cryptocurrencies.len() + 1 {
+            return Err("The proof values do not match the cryptocurrencies of the round".into());
+        }
+        Ok(self)
+    }
+
+    /// Migrates a bare `KZGProof` JSON, as exported before the bundles, by attaching the context of its round
+    pub fn from_kzg_proof_json(json: &str, context: RoundContext) -> Result<Self, Box<dyn Error>> {
+        Self::new(context, serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        from_json::<Self>(json)?.validate()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        to_bytes(INCLUSION_PROOF_BUNDLE_MAGIC, self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        from_bytes::<Self>(INCLUSION_PROOF_BUNDLE_MAGIC, bytes)?.validate()
+    }
+
+    /// Returns the balances of the user along with their cryptocurrencies
+    pub fn balances(&self) -> Vec<(&Cryptocurrency, &U256)> {
+        self.context
+            .cryptocurrencies
+            .iter()
+            .zip(&self.proof.get_input_values()[1..])
+            .collect()
+    }
+}
+
+/// Self-describing commitment of a round, carrying its context along with the arguments of `Summa.submitCommitment`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommitmentBundle {
+    pub version: u16,
+    pub context: RoundContext,
+    pub snark_proof: Bytes,
+    pub grand_sum_proof: Bytes,
+    pub total_balances: Vec<U256>,
+}
+
+impl CommitmentBundle {
+    pub fn new(
+        context: RoundContext,
+        snark_proof: Bytes,
+        grand_sum_proof: Bytes,
+        total_balances: Vec<U256>,
+    ) -> Result<Self, Box<dyn Error>> {
+        CommitmentBundle {
+            version: BUNDLE_VERSION,
+            context,
+            snark_proof,
+            grand_sum_proof,
+            total_balances,
+        }
+        .validate()
+    }
+
+    /// Checks that the total balances match the context, as done on every construction and decoding
+    fn validate(self) -> Result<Self, Box<dyn Error>> {
+        check_version(self.version)?;
+        if self.total_balances.len() != self.context.cryptocurrencies.len() {
+            return Err("The total balances do not match the cryptocurrencies of the round".into());
+        }
+        Ok(self)
+    }
+
+    /// Builds the commitment bundle of a snapshot, generating its grand sum proof
+    pub fn from_snapshot<const N_CURRENCIES: usize, const N_USERS: usize>(
+        context: RoundContext,
+        snapshot: &Snapshot<N_CURRENCIES, N_USERS>,
+    ) -> Result<Self, Box<dyn Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+    {
+        let grand_sum_proof = snapshot.generate_grand_sum_proof()?;
+        Self::new(
+            context,
+            Bytes::from(snapshot.get_zk_snark_proof().to_vec()),
+            grand_sum_proof.get_proof().clone(),
+            grand_sum_proof.get_input_values().clone(),
+        )
+    }
+
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        from_json::<Self>(json)?.validate()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        to_bytes(COMMITMENT_BUNDLE_MAGIC, self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        from_bytes::<Self>(COMMITMENT_BUNDLE_MAGIC, bytes)?.validate()
+    }
+}
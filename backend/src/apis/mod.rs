@@ -1,3 +1,12 @@
 pub mod address_ownership;
+pub mod assets;
+pub mod bundle;
 pub mod csv_parser;
+pub mod ownership_message;
+pub mod preflight;
+pub mod report;
 pub mod round;
+pub mod round_manager;
+pub mod signature_verification;
+pub mod tenant;
+pub mod verification;
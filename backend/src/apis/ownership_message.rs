@@ -0,0 +1,312 @@
+//! Structured messages of the proofs of address ownership, bound to an exchange, a Summa contract and a validity window.
+//!
+//! A free-text message such as "Summa proof of solvency for CryptoExchange" can be replayed by any exchange, on any
+//! contract and in any round. An `OwnershipMessage` is instead serialized as JSON in the `message` column of the
+//! signatures CSV. The EVM addresses sign it as EIP-712 typed data, in the domain of the Summa contract, while the
+//! addresses of the other chains sign its JSON text as is. The free-text messages are rejected by the
+//! [`MessageBinding`] unless the legacy messages are allowed.
+use std::{convert::Infallible, error::Error};
+
+use ethers::{
+    abi::{self, AbiDecode, Token},
+    types::{
+        transaction::eip712::{EIP712Domain, Eip712},
+        Address, U256,
+    },
+    utils::{keccak256, to_checksum},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::contracts::generated::summa_contract::AddressOwnershipProof;
+
+/// The name of the EIP-712 domain of the ownership messages
+pub const DOMAIN_NAME: &str = "Summa";
+
+/// The version of the EIP-712 domain of the ownership messages
+pub const DOMAIN_VERSION: &str = "1";
+
+/// The EIP-712 type of the ownership messages, the contract and the chain being bound by the domain
+pub const OWNERSHIP_MESSAGE_TYPE: &str =
+    "AddressOwnership(string exchange,uint256 nonce,uint64 validFrom,uint64 validUntil)";
+
+/// A message proving the ownership of an address to a given exchange, on a given Summa contract, for a limited time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct OwnershipMessage {
+    /// The name of the exchange owning the address
+    pub exchange: String,
+    /// The address of the Summa contract the proof is submitted to
+    pub summa_address: Address,
+    /// The ID of the chain of the Summa contract
+    pub chain_id: u64,
+    /// A number chosen by the exchange, e.g., the round, to tell the messages of the same window apart
+    pub nonce: u64,
+    /// The UNIX timestamp from which the message is valid
+    pub valid_from: u64,
+    /// The UNIX timestamp until which the message is valid
+    pub valid_until: u64,
+}
+
+impl OwnershipMessage {
+    /// Parses the message of a proof of address ownership, returning `None` for the free-text messages
+    pub fn from_proof(proof: &AddressOwnershipProof) -> Option<Self> {
+        let text = String::decode(proof.message.as_ref()).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    /// Returns the JSON text of the message, to be put in the `message` column of the signatures CSV
+    pub fn to_message_string(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Returns the typed data of the message, as expected by the `eth_signTypedData_v4` method of the wallets
+    pub fn to_typed_data(&self) -> Value {
+        json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                "AddressOwnership": [
+                    { "name": "exchange", "type": "string" },
+                    { "name": "nonce", "type": "uint256" },
+                    { "name": "validFrom", "type": "uint64" },
+                    { "name": "validUntil", "type": "uint64" },
+                ],
+            },
+            "primaryType": "AddressOwnership",
+            "domain": {
+                "name": DOMAIN_NAME,
+                "version": DOMAIN_VERSION,
+                "chainId": self.chain_id,
+                "verifyingContract": to_checksum(&self.summa_address, None),
+            },
+            "message": {
+                "exchange": self.exchange,
+                "nonce": self.nonce,
+                "validFrom": self.valid_from,
+                "validUntil": self.valid_until,
+            },
+        })
+    }
+
+    /// Checks that the message is bound to the expected exchange, contract and chain, and valid at the expected time
+    pub fn check(&self, binding: &MessageBinding) -> Result<(), String> {
+        if let Some(exchange) = &binding.exchange {
+            if &self.exchange != exchange {
+                return Err(format!("signed for the exchange {}", self.exchange));
+            }
+        }
+        if self.summa_address != binding.summa_address {
+            return Err(format!(
+                "signed for the Summa contract {}",
+                to_checksum(&self.summa_address, None)
+            ));
+        }
+        if self.chain_id != binding.chain_id {
+            return Err(format!("signed for the chain {}", self.chain_id));
+        }
+        if let Some(timestamp) = binding.timestamp {
+            if timestamp < self.valid_from {
+                return Err(format!("not valid before {}", self.valid_from));
+            }
+            if timestamp > self.valid_until {
+                return Err(format!("expired at {}", self.valid_until));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Eip712 for OwnershipMessage {
+    type Error = Infallible;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(EIP712Domain {
+            name: Some(DOMAIN_NAME.to_string()),
+            version: Some(DOMAIN_VERSION.to_string()),
+            chain_id: Some(U256::from(self.chain_id)),
+            verifying_contract: Some(self.summa_address),
+            salt: None,
+        })
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(OWNERSHIP_MESSAGE_TYPE))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(abi::encode(&[
+            Token::FixedBytes(Self::type_hash()?.to_vec()),
+            Token::FixedBytes(keccak256(&self.exchange).to_vec()),
+            Token::Uint(U256::from(self.nonce)),
+            Token::Uint(U256::from(self.valid_from)),
+            Token::Uint(U256::from(self.valid_until)),
+        ])))
+    }
+}
+
+/// The exchange, contract, chain and time the ownership messages are expected to be bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageBinding {
+    pub summa_address: Address,
+    pub chain_id: u64,
+    /// The name of the exchange, not checked if `None`
+    pub exchange: Option<String>,
+    /// The time at which the messages must be valid, not checked if `None`
+    pub timestamp: Option<u64>,
+    /// Whether the free-text messages are rejected, which is the default
+    pub require_typed: bool,
+}
+
+impl MessageBinding {
+    /// Creates a binding to the given Summa contract, rejecting the free-text messages
+    pub fn new(summa_address: Address, chain_id: u64) -> Self {
+        MessageBinding {
+            summa_address,
+            chain_id,
+            exchange: None,
+            timestamp: None,
+            require_typed: true,
+        }
+    }
+
+    /// Sets the name of the exchange the messages must be signed for
+    pub fn with_exchange(mut self, exchange: &str) -> Self {
+        self.exchange = Some(exchange.to_string());
+        self
+    }
+
+    /// Sets the time at which the messages must be valid
+    pub fn at_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Accepts the free-text messages signed before the structured ones, which can be replayed by other exchanges
+    pub fn allow_legacy_messages(mut self) -> Self {
+        self.require_typed = false;
+        self
+    }
+
+    /// Checks the message of a proof of address ownership against the binding
+    pub fn check_proof(&self, proof: &AddressOwnershipProof) -> Result<(), String> {
+        match OwnershipMessage::from_proof(proof) {
+            Some(message) => message.check(self),
+            None if self.require_typed => Err("not a structured ownership message".to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::signature_verification::{verify_signature, SignatureValidation};
+    use ethers::{
+        abi::AbiEncode,
+        signers::{LocalWallet, Signer},
+        types::{transaction::eip712::TypedData, H256},
+    };
+
+    fn message() -> OwnershipMessage {
+        OwnershipMessage {
+            exchange: "CryptoExchange".to_string(),
+            summa_address: "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+                .parse()
+                .unwrap(),
+            chain_id: 31337,
+            nonce: 1,
+            valid_from: 1_700_000_000,
+            valid_until: 1_800_000_000,
+        }
+    }
+
+    fn signed_proof(wallet: &LocalWallet, message: &OwnershipMessage) -> AddressOwnershipProof {
+        let signature = wallet
+            .sign_hash(H256::from(message.encode_eip712().unwrap()))
+            .unwrap();
+        AddressOwnershipProof {
+            chain: "ETH".to_string(),
+            cex_address: to_checksum(&wallet.address(), None),
+            signature: signature.to_vec().into(),
+            message: message.to_message_string().unwrap().encode().into(),
+        }
+    }
+
+    #[test]
+    fn test_typed_data_hash() {
+        let message = message();
+        let typed_data: TypedData = serde_json::from_value(message.to_typed_data()).unwrap();
+        assert_eq!(
+            typed_data.encode_eip712().unwrap(),
+            message.encode_eip712().unwrap()
+        );
+
+        let parsed =
+            serde_json::from_str::<OwnershipMessage>(&message.to_message_string().unwrap());
+        assert_eq!(parsed.unwrap(), message);
+    }
+
+    #[test]
+    fn test_verify_typed_message_signature() {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let proof = signed_proof(&wallet, &message());
+        assert_eq!(OwnershipMessage::from_proof(&proof), Some(message()));
+        assert_eq!(verify_signature(&proof), SignatureValidation::Valid);
+
+        // The signature is bound to the contract of the message
+        let mut other_contract_message = message();
+        other_contract_message.summa_address = Address::random();
+        let mut replayed_proof = proof.clone();
+        replayed_proof.message = other_contract_message
+            .to_message_string()
+            .unwrap()
+            .encode()
+            .into();
+        assert!(verify_signature(&replayed_proof).is_rejected());
+    }
+
+    #[test]
+    fn test_check_message_binding() {
+        let message = message();
+        let binding = MessageBinding::new(message.summa_address, message.chain_id)
+            .with_exchange("CryptoExchange")
+            .at_timestamp(1_750_000_000);
+        assert_eq!(message.check(&binding), Ok(()));
+
+        assert!(message
+            .check(&binding.clone().with_exchange("OtherExchange"))
+            .is_err());
+        assert!(message
+            .check(&MessageBinding::new(Address::random(), message.chain_id))
+            .is_err());
+        assert!(message
+            .check(&MessageBinding::new(message.summa_address, 1))
+            .is_err());
+        assert!(message
+            .check(&binding.clone().at_timestamp(1_600_000_000))
+            .is_err());
+        assert!(message
+            .check(&binding.clone().at_timestamp(1_900_000_000))
+            .is_err());
+
+        // The free-text messages are only accepted if the binding allows the legacy messages
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let mut free_text_proof = signed_proof(&wallet, &message);
+        free_text_proof.message = "Summa proof of solvency for CryptoExchange"
+            .to_string()
+            .encode()
+            .into();
+        assert!(binding.check_proof(&free_text_proof).is_err());
+        assert_eq!(
+            binding
+                .allow_legacy_messages()
+                .check_proof(&free_text_proof),
+            Ok(())
+        );
+    }
+}
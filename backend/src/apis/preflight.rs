@@ -0,0 +1,349 @@
+//! Pre-flight check of the local circuit configuration against the Summa deployment.
+//!
+//! The Summa contract only validates its verifying key against its cryptocurrencies and balance byte range on
+//! deployment, so a local circuit with another number or order of cryptocurrencies, another balance byte range or
+//! another verifying key is only detected by a reverted `submitCommitment`. The pre-flight check compares them
+//! beforehand and reports every difference.
+use std::{error::Error, fmt};
+
+use ethers::utils::hex;
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, G1Affine},
+    plonk::VerifyingKey,
+    poly::kzg::commitment::ParamsKZG,
+};
+use halo2_solidity_verifier::{BatchOpenScheme, SolidityGenerator};
+use summa_solvency::cryptocurrency::Cryptocurrency;
+
+use crate::contracts::config::SummaConfig;
+
+/// The number of instances of the circuit, as given to the Solidity generator of the verifier contracts
+const N_INSTANCES: usize = 1;
+
+/// A difference between the local configuration and the Summa deployment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigMismatch {
+    /// The compared parameter, e.g., `cryptocurrency 0` or `verifying key k`
+    pub field: String,
+    pub local: String,
+    pub deployed: String,
+}
+
+impl fmt::Display for ConfigMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: local {}, deployed {}",
+            self.field, self.local, self.deployed
+        )
+    }
+}
+
+/// The code of the verifying key contract, i.e., the labelled 32-byte words it returns from its constructor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyingKeyCode {
+    words: Vec<(String, [u8; 32])>,
+}
+
+impl VerifyingKeyCode {
+    /// Renders the code of the verifying key contract of the local verifying key, as `gen_verifier` does.
+    ///
+    /// The verifying key contract does not depend on the batch open scheme, which only affects the verifier contract.
+    pub fn render(
+        params: &ParamsKZG<Bn256>,
+        verifying_key: &VerifyingKey<G1Affine>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let generator =
+            SolidityGenerator::new(params, verifying_key, BatchOpenScheme::Bdfg21, N_INSTANCES);
+        let (_, verifying_key_solidity) = generator.render_separately()?;
+        Self::parse(&verifying_key_solidity)
+    }
+
+    /// Parses the `mstore(offset, word) // label` statements of the Solidity source of a verifying key contract
+    pub fn parse(verifying_key_solidity: &str) -> Result<Self, Box<dyn Error>> {
+        let mut words = Vec::new();
+        for line in verifying_key_solidity.lines() {
+            let Some(arguments) = line.trim().strip_prefix("mstore(") else {
+                continue;
+            };
+            let (call, label) = arguments
+                .split_once("//")
+                .ok_or_else(|| format!("Unlabelled word in the verifying key: {}", line))?;
+            let (offset, word) = call
+                .trim()
+                .strip_suffix(')')
+                .and_then(|arguments| arguments.split_once(','))
+                .ok_or_else(|| format!("Invalid statement in the verifying key: {}", line))?;
+
+            let offset = usize::from_str_radix(offset.trim().trim_start_matches("0x"), 16)?;
+            if offset != words.len() * 32 {
+                return Err(format!("Unexpected offset {:#x} in the verifying key", offset).into());
+            }
+            let word: [u8; 32] = hex::decode(word.trim().trim_start_matches("0x"))?
+                .try_into()
+                .map_err(|_| format!("Invalid word in the verifying key: {}", line))?;
+            words.push((label.trim().to_string(), word));
+        }
+
+        if words.is_empty() {
+            return Err("No word in the verifying key".into());
+        }
+        Ok(VerifyingKeyCode { words })
+    }
+
+    /// Returns the code of the contract, as deployed
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|(_, word)| *word).collect()
+    }
+
+    /// Returns the number of permutation commitments, i.e., of the columns of the permutation argument
+    pub fn count_permutations(&self) -> usize {
+        self.words
+            .iter()
+            .filter(|(label, _)| label.starts_with("permutation_comms[") && label.ends_with(".x"))
+            .count()
+    }
+}
+
+fn describe_cryptocurrency(cryptocurrency: Option<&Cryptocurrency>) -> String {
+    match cryptocurrency {
+        Some(cryptocurrency) => format!("{} ({})", cryptocurrency.name, cryptocurrency.chain),
+        None => "none".to_string(),
+    }
+}
+
+/// Compares the local configuration of a circuit with the one of a Summa deployment
+///
+/// # Arguments
+///
+/// * `n_currencies` - the number of cryptocurrencies of the circuit
+/// * `cryptocurrencies` - the cryptocurrencies of the balance columns of the entries, in order
+/// * `verifying_key` - the verifying key code rendered from the local verifying key
+/// * `config` - the configuration of the Summa contract
+/// * `deployed_verifying_key` - the code of the verifying key contract of the Summa contract
+///
+/// # Returns
+///
+/// * `Vec<ConfigMismatch>` - the differences, empty if the configurations match
+pub fn compare_deployment(
+    n_currencies: usize,
+    cryptocurrencies: &[Cryptocurrency],
+    verifying_key: &VerifyingKeyCode,
+    config: &SummaConfig,
+    deployed_verifying_key: &[u8],
+) -> Vec<ConfigMismatch> {
+    let mut mismatches = Vec::new();
+
+    let deployed_cryptocurrencies: Vec<Cryptocurrency> = config
+        .cryptocurrency_names
+        .iter()
+        .zip(&config.cryptocurrency_chains)
+        .map(|(name, chain)| Cryptocurrency {
+            name: name.clone(),
+            chain: chain.clone(),
+        })
+        .collect();
+    if n_currencies != deployed_cryptocurrencies.len() {
+        mismatches.push(ConfigMismatch {
+            field: "number of cryptocurrencies".to_string(),
+            local: n_currencies.to_string(),
+            deployed: deployed_cryptocurrencies.len().to_string(),
+        });
+    }
+    for index in 0..cryptocurrencies.len().max(deployed_cryptocurrencies.len()) {
+        let local = cryptocurrencies.get(index);
+        let deployed = deployed_cryptocurrencies.get(index);
+        if local != deployed {
+            mismatches.push(ConfigMismatch {
+                field: format!("cryptocurrency {}", index),
+                local: describe_cryptocurrency(local),
+                deployed: describe_cryptocurrency(deployed),
+            });
+        }
+    }
+
+    // The Summa contract expects 2 + (balanceByteRange / 2) * numberOfCurrencies permutation columns
+    let n_permutations = verifying_key.count_permutations();
+    let local_balance_byte_range = match n_permutations.checked_sub(2) {
+        Some(n_range_check_columns) if n_currencies > 0 => {
+            (n_range_check_columns * 2 / n_currencies).to_string()
+        }
+        _ => "unknown".to_string(),
+    };
+    if local_balance_byte_range != config.balance_byte_range.to_string() {
+        mismatches.push(ConfigMismatch {
+            field: "balance byte range".to_string(),
+            local: local_balance_byte_range,
+            deployed: config.balance_byte_range.to_string(),
+        });
+    }
+
+    if deployed_verifying_key.is_empty() {
+        mismatches.push(ConfigMismatch {
+            field: "verifying key".to_string(),
+            local: format!("{} bytes", verifying_key.words.len() * 32),
+            deployed: "no code".to_string(),
+        });
+        return mismatches;
+    }
+    for (index, (label, word)) in verifying_key.words.iter().enumerate() {
+        let deployed_word = deployed_verifying_key.get(index * 32..(index + 1) * 32);
+        if deployed_word != Some(&word[..]) {
+            mismatches.push(ConfigMismatch {
+                field: format!("verifying key {}", label),
+                local: format!("0x{}", hex::encode(word)),
+                deployed: deployed_word
+                    .map(|deployed_word| format!("0x{}", hex::encode(deployed_word)))
+                    .unwrap_or_else(|| "none".to_string()),
+            });
+        }
+    }
+    if deployed_verifying_key.len() > verifying_key.words.len() * 32 {
+        mismatches.push(ConfigMismatch {
+            field: "verifying key length".to_string(),
+            local: format!("{} bytes", verifying_key.words.len() * 32),
+            deployed: format!("{} bytes", deployed_verifying_key.len()),
+        });
+    }
+
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders the Solidity source of a verifying key with the given k and number of permutation columns
+    fn verifying_key_solidity(k: u8, n_permutations: usize) -> String {
+        let mut labels = vec!["vk_digest".to_string(), "k".to_string()];
+        for index in 0..n_permutations {
+            labels.push(format!("permutation_comms[{}].x", index));
+            labels.push(format!("permutation_comms[{}].y", index));
+        }
+        labels
+            .iter()
+            .enumerate()
+            .map(|(index, label)| {
+                let value = if label == "k" { k } else { index as u8 + 1 };
+                format!(
+                    "            mstore({:#06x}, 0x{:064x}) // {}\n",
+                    index * 32,
+                    value,
+                    label
+                )
+            })
+            .collect()
+    }
+
+    fn cryptocurrency(name: &str) -> Cryptocurrency {
+        Cryptocurrency {
+            name: name.to_string(),
+            chain: "ETH".to_string(),
+        }
+    }
+
+    fn config(names: &[&str], balance_byte_range: u8) -> SummaConfig {
+        SummaConfig {
+            cryptocurrency_names: names.iter().map(|name| name.to_string()).collect(),
+            cryptocurrency_chains: vec!["ETH".to_string(); names.len()],
+            balance_byte_range,
+        }
+    }
+
+    #[test]
+    fn test_parse_verifying_key() {
+        let verifying_key = VerifyingKeyCode::parse(&verifying_key_solidity(17, 10)).unwrap();
+        assert_eq!(verifying_key.count_permutations(), 10);
+        let code = verifying_key.to_bytes();
+        assert_eq!(code.len(), 22 * 32);
+        assert_eq!(code[63], 17);
+
+        assert!(VerifyingKeyCode::parse("contract Empty {}").is_err());
+        assert!(VerifyingKeyCode::parse("mstore(0x0020, 0x01) // k").is_err());
+    }
+
+    #[test]
+    fn test_compare_deployment() {
+        // 2 currencies with a balance byte range of 8 need 2 + 4 * 2 permutation columns
+        let verifying_key = VerifyingKeyCode::parse(&verifying_key_solidity(17, 10)).unwrap();
+        let cryptocurrencies = vec![cryptocurrency("ETH"), cryptocurrency("USDT")];
+        let code = verifying_key.to_bytes();
+        assert!(compare_deployment(
+            2,
+            &cryptocurrencies,
+            &verifying_key,
+            &config(&["ETH", "USDT"], 8),
+            &code
+        )
+        .is_empty());
+
+        // The order of the cryptocurrencies matters
+        let mismatches = compare_deployment(
+            2,
+            &cryptocurrencies,
+            &verifying_key,
+            &config(&["USDT", "ETH"], 8),
+            &code,
+        );
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(
+            mismatches[0].to_string(),
+            "cryptocurrency 0: local ETH (ETH), deployed USDT (ETH)"
+        );
+
+        // A deployment for 3 currencies
+        let mismatches = compare_deployment(
+            2,
+            &cryptocurrencies,
+            &verifying_key,
+            &config(&["ETH", "USDT", "BTC"], 8),
+            &code,
+        );
+        assert_eq!(
+            mismatches
+                .iter()
+                .map(|mismatch| mismatch.field.as_str())
+                .collect::<Vec<_>>(),
+            vec!["number of cryptocurrencies", "cryptocurrency 2"]
+        );
+
+        // Another balance byte range
+        let mismatches = compare_deployment(
+            2,
+            &cryptocurrencies,
+            &verifying_key,
+            &config(&["ETH", "USDT"], 4),
+            &code,
+        );
+        assert_eq!(
+            mismatches,
+            vec![ConfigMismatch {
+                field: "balance byte range".to_string(),
+                local: "8".to_string(),
+                deployed: "4".to_string(),
+            }]
+        );
+
+        // Another verifying key, and no verifying key
+        let other_code = VerifyingKeyCode::parse(&verifying_key_solidity(18, 10))
+            .unwrap()
+            .to_bytes();
+        let mismatches = compare_deployment(
+            2,
+            &cryptocurrencies,
+            &verifying_key,
+            &config(&["ETH", "USDT"], 8),
+            &other_code,
+        );
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].field, "verifying key k");
+        let mismatches = compare_deployment(
+            2,
+            &cryptocurrencies,
+            &verifying_key,
+            &config(&["ETH", "USDT"], 8),
+            &[],
+        );
+        assert_eq!(mismatches[0].deployed, "no code");
+    }
+}
@@ -0,0 +1,419 @@
+//! Solvency report of a round, combining the liabilities committed on the Summa contract with the proven assets.
+//!
+//! The report is rendered as JSON, for the machines, and as Markdown or HTML, for the publication of the attestation.
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::{
+    types::{H256, U256},
+    utils::to_checksum,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use summa_solvency::cryptocurrency::Cryptocurrency;
+
+use super::{assets::AssetReport, bundle::RoundContext};
+use crate::contracts::reader::SummaReader;
+
+/// The amounts are serialized as decimal strings, to be readable in the published JSON
+mod decimal {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        U256::from_dec_str(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The status of the verification of the SNARK proof of the liabilities of the round
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnarkVerification {
+    /// The commitment of the round is on the Summa contract, which verified its SNARK and grand sum proofs,
+    /// and its total balances are the liabilities of the report
+    VerifiedOnChain,
+    /// The commitment of the round is on the Summa contract, but its total balances differ from the liabilities
+    TotalsMismatch,
+    /// No commitment of the round is on the Summa contract
+    NotCommitted,
+}
+
+impl SnarkVerification {
+    fn describe(&self) -> &'static str {
+        match self {
+            SnarkVerification::VerifiedOnChain => "verified on-chain by the Summa contract",
+            SnarkVerification::TotalsMismatch => {
+                "committed with other total balances than the liabilities"
+            }
+            SnarkVerification::NotCommitted => "not committed",
+        }
+    }
+}
+
+/// The assets and the liabilities of a cryptocurrency of the round
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurrencyReport {
+    pub cryptocurrency: Cryptocurrency,
+    #[serde(with = "decimal")]
+    pub liabilities: U256,
+    #[serde(with = "decimal")]
+    pub assets: U256,
+    /// The ratio of the assets to the liabilities, `None` without liabilities
+    pub coverage_ratio: Option<f64>,
+}
+
+impl CurrencyReport {
+    pub fn is_solvent(&self) -> bool {
+        self.assets >= self.liabilities
+    }
+
+    fn coverage(&self) -> String {
+        match self.coverage_ratio {
+            Some(ratio) => format!("{:.2}%", ratio * 100.0),
+            None => "n/a".to_string(),
+        }
+    }
+
+    fn status(&self) -> &'static str {
+        if self.is_solvent() {
+            "solvent"
+        } else {
+            "insolvent"
+        }
+    }
+}
+
+/// Returns the ratio of the assets to the liabilities, with a precision of a millionth
+fn coverage_ratio(assets: U256, liabilities: U256) -> Option<f64> {
+    if liabilities.is_zero() {
+        return None;
+    }
+    Some(match assets.checked_mul(U256::from(1_000_000)) {
+        Some(scaled_assets) if scaled_assets / liabilities <= U256::from(u128::MAX) => {
+            (scaled_assets / liabilities).as_u128() as f64 / 1_000_000.0
+        }
+        _ => f64::INFINITY,
+    })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The solvency report of a round
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolvencyReport {
+    pub context: RoundContext,
+    /// The UNIX timestamp the report was generated at
+    pub generated_at: u64,
+    pub currencies: Vec<CurrencyReport>,
+    /// The block the assets of each chain were read at
+    pub asset_blocks: BTreeMap<String, u64>,
+    pub snark_verification: SnarkVerification,
+    /// The transaction submitting the commitment of the round, if any
+    pub commitment_transaction: Option<H256>,
+    /// The transactions submitting the proofs of address ownership since the start block of the reader, in order.
+    /// The proofs are not tied to a round, so the transactions of all the rounds are listed.
+    pub ownership_transactions: Vec<H256>,
+}
+
+impl SolvencyReport {
+    /// Generates the report of a round from the Summa contract and the collected assets
+    ///
+    /// # Arguments
+    ///
+    /// * `summa_reader` - the reader of the Summa contract of the round, whose logs are queried from its start block in pages
+    /// * `context` - the context of the round
+    /// * `liabilities` - the grand sums of the balances of the users, as returned by `Round::get_total_balances`
+    /// * `assets` - the assets of the proven addresses, as collected by the `AssetCollector`
+    pub async fn generate(
+        summa_reader: &SummaReader,
+        context: RoundContext,
+        liabilities: &[U256],
+        assets: &AssetReport,
+    ) -> Result<Self, Box<dyn Error>> {
+        if context.summa_address != summa_reader.get_summa_address()
+            || context.chain_id != summa_reader.get_chain_id()
+        {
+            return Err("The round context is not the one of the Summa contract".into());
+        }
+
+        let currencies = assets
+            .compare(&context.cryptocurrencies, liabilities)?
+            .into_iter()
+            .map(|check| CurrencyReport {
+                coverage_ratio: coverage_ratio(check.assets, check.liabilities),
+                cryptocurrency: check.cryptocurrency,
+                liabilities: check.liabilities,
+                assets: check.assets,
+            })
+            .collect();
+
+        let commitment = summa_reader
+            .get_commitment_events_with_meta()
+            .await?
+            .into_iter()
+            .find(|(event, _)| event.timestamp == U256::from(context.timestamp));
+        let (snark_verification, commitment_transaction) = match commitment {
+            Some((event, meta)) if event.total_balances == liabilities => (
+                SnarkVerification::VerifiedOnChain,
+                Some(meta.transaction_hash),
+            ),
+            Some((_, meta)) => (
+                SnarkVerification::TotalsMismatch,
+                Some(meta.transaction_hash),
+            ),
+            None => (SnarkVerification::NotCommitted, None),
+        };
+
+        let mut ownership_transactions: Vec<H256> = Vec::new();
+        for (_, meta) in summa_reader
+            .get_address_ownership_events_with_meta()
+            .await?
+        {
+            if !ownership_transactions.contains(&meta.transaction_hash) {
+                ownership_transactions.push(meta.transaction_hash);
+            }
+        }
+
+        Ok(SolvencyReport {
+            context,
+            generated_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            currencies,
+            asset_blocks: assets.blocks.clone().into_iter().collect(),
+            snark_verification,
+            commitment_transaction,
+            ownership_transactions,
+        })
+    }
+
+    /// Returns whether the liabilities are verified on-chain and covered by the assets for every cryptocurrency
+    pub fn is_solvent(&self) -> bool {
+        self.snark_verification == SnarkVerification::VerifiedOnChain
+            && self.currencies.iter().all(|currency| currency.is_solvent())
+    }
+
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Returns the lines of the summary of the round, as label and value pairs
+    fn summary(&self) -> Vec<(&'static str, String)> {
+        let asset_blocks = self
+            .asset_blocks
+            .iter()
+            .map(|(chain, block)| format!("{} block {}", chain, block))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let ownership_transactions = self
+            .ownership_transactions
+            .iter()
+            .map(|hash| format!("{:?}", hash))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        vec![
+            (
+                "Summa contract",
+                format!(
+                    "{} on chain {}",
+                    to_checksum(&self.context.summa_address, None),
+                    self.context.chain_id
+                ),
+            ),
+            ("Generated at", format!("{} (UNIX time)", self.generated_at)),
+            (
+                "SNARK proof",
+                self.snark_verification.describe().to_string(),
+            ),
+            (
+                "Commitment transaction",
+                match self.commitment_transaction {
+                    Some(hash) => format!("{:?}", hash),
+                    None => "none".to_string(),
+                },
+            ),
+            (
+                "Ownership proof transactions (all rounds)",
+                if ownership_transactions.is_empty() {
+                    "none".to_string()
+                } else {
+                    ownership_transactions
+                },
+            ),
+            ("Assets read at", asset_blocks),
+            (
+                "Result",
+                if self.is_solvent() {
+                    "solvent".to_string()
+                } else {
+                    "not proven solvent".to_string()
+                },
+            ),
+        ]
+    }
+
+    /// Renders the report as a Markdown document
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!(
+            "# Summa solvency report of round {}\n\n",
+            self.context.timestamp
+        );
+        for (label, value) in self.summary() {
+            writeln!(markdown, "- {}: {}", label, value).unwrap();
+        }
+
+        markdown
+            .push_str("\n| Cryptocurrency | Chain | Liabilities | Assets | Coverage | Status |\n");
+        markdown.push_str("|---|---|---:|---:|---:|---|\n");
+        for currency in &self.currencies {
+            writeln!(
+                markdown,
+                "| {} | {} | {} | {} | {} | {} |",
+                currency.cryptocurrency.name,
+                currency.cryptocurrency.chain,
+                currency.liabilities,
+                currency.assets,
+                currency.coverage(),
+                currency.status()
+            )
+            .unwrap();
+        }
+        markdown
+    }
+
+    /// Renders the report as a standalone HTML document
+    pub fn to_html(&self) -> String {
+        let title = format!("Summa solvency report of round {}", self.context.timestamp);
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n<ul>\n",
+            title
+        );
+        for (label, value) in self.summary() {
+            writeln!(
+                html,
+                "<li>{}: {}</li>",
+                escape_html(label),
+                escape_html(&value)
+            )
+            .unwrap();
+        }
+
+        html.push_str("</ul>\n<table>\n<tr><th>Cryptocurrency</th><th>Chain</th><th>Liabilities</th><th>Assets</th><th>Coverage</th><th>Status</th></tr>\n");
+        for currency in &self.currencies {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&currency.cryptocurrency.name),
+                escape_html(&currency.cryptocurrency.chain),
+                currency.liabilities,
+                currency.assets,
+                currency.coverage(),
+                currency.status()
+            )
+            .unwrap();
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> SolvencyReport {
+        SolvencyReport {
+            context: RoundContext {
+                chain_id: 31337,
+                summa_address: "0xDc64a140Aa3E981100a9becA4E685f962f0cF6C9"
+                    .parse()
+                    .unwrap(),
+                timestamp: 1,
+                k: 17,
+                cryptocurrencies: vec![
+                    Cryptocurrency {
+                        name: "ETH".to_string(),
+                        chain: "ETH".to_string(),
+                    },
+                    Cryptocurrency {
+                        name: "<USDT>".to_string(),
+                        chain: "ETH".to_string(),
+                    },
+                ],
+            },
+            generated_at: 1_700_000_000,
+            currencies: vec![
+                CurrencyReport {
+                    cryptocurrency: Cryptocurrency {
+                        name: "ETH".to_string(),
+                        chain: "ETH".to_string(),
+                    },
+                    liabilities: U256::from(556862),
+                    assets: U256::from(556864),
+                    coverage_ratio: coverage_ratio(U256::from(556864), U256::from(556862)),
+                },
+                CurrencyReport {
+                    cryptocurrency: Cryptocurrency {
+                        name: "<USDT>".to_string(),
+                        chain: "ETH".to_string(),
+                    },
+                    liabilities: U256::from(400),
+                    assets: U256::from(100),
+                    coverage_ratio: coverage_ratio(U256::from(100), U256::from(400)),
+                },
+            ],
+            asset_blocks: BTreeMap::from([("ETH".to_string(), 42)]),
+            snark_verification: SnarkVerification::VerifiedOnChain,
+            commitment_transaction: Some(H256::repeat_byte(1)),
+            ownership_transactions: vec![H256::repeat_byte(2)],
+        }
+    }
+
+    #[test]
+    fn test_coverage_ratio() {
+        assert_eq!(coverage_ratio(U256::from(100), U256::from(400)), Some(0.25));
+        assert_eq!(coverage_ratio(U256::from(3), U256::from(2)), Some(1.5));
+        assert_eq!(coverage_ratio(U256::from(1), U256::zero()), None);
+        assert_eq!(coverage_ratio(U256::MAX, U256::one()), Some(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_render_report() {
+        let report = report();
+        assert!(!report.is_solvent());
+
+        // The amounts are decimal strings in the JSON, which reads back to the same report
+        let json = report.to_json().unwrap();
+        assert!(json.contains("\"liabilities\": \"556862\""));
+        assert!(json.contains("\"snark_verification\": \"verified_on_chain\""));
+        assert_eq!(SolvencyReport::from_json(&json).unwrap(), report);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.starts_with("# Summa solvency report of round 1\n"));
+        assert!(markdown.contains("| ETH | ETH | 556862 | 556864 | 100.00% | solvent |"));
+        assert!(markdown.contains("| <USDT> | ETH | 400 | 100 | 25.00% | insolvent |"));
+        assert!(markdown.contains("- SNARK proof: verified on-chain by the Summa contract"));
+        assert!(markdown.contains("- Assets read at: ETH block 42"));
+
+        // The names are escaped in the HTML
+        let html = report.to_html();
+        assert!(html.contains("<td>&lt;USDT&gt;</td>"));
+        assert!(!html.contains("<USDT>"));
+        assert!(html.contains(&format!("{:?}", H256::repeat_byte(1))));
+    }
+}
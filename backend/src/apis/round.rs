@@ -1,29 +1,54 @@
-use ethers::types::{Bytes, U256};
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{Bytes, U256},
+    utils::keccak256,
+};
 use halo2_proofs::{
     arithmetic::{best_fft, Field},
     halo2curves::{
         bn256::{Bn256, Fr as Fp, G1Affine, G2Affine},
+        ff::PrimeField,
         group::{cofactor::CofactorCurveAffine, Curve},
     },
-    plonk::{AdviceSingle, VerifyingKey},
+    plonk::{AdviceSingle, Circuit, VerifyingKey},
     poly::{
+        commitment::{Blind, Params},
         kzg::commitment::{KZGCommitmentScheme, ParamsKZG},
         Coeff,
     },
     transcript::TranscriptRead,
+    SerdeFormat,
 };
 use halo2_solidity_verifier::Keccak256Transcript;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
 
-use crate::contracts::signer::SummaSigner;
-use summa_solvency::utils::amortized_kzg::{create_naive_kzg_proof, verify_kzg_proof};
+use super::{
+    bundle::RoundContext,
+    preflight::{compare_deployment, ConfigMismatch, VerifyingKeyCode},
+};
+use crate::contracts::{signer::SummaSigner, transaction::CommitmentReceipt};
+use summa_solvency::{
+    cryptocurrency::Cryptocurrency,
+    entry::Entry,
+    utils::amortized_kzg::{create_naive_kzg_proof, verify_kzg_proof},
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct KZGProof {
     proof_calldata: Bytes,
     input_values: Vec<U256>,
     challenge_s_g2: Option<Vec<U256>>,
+    #[serde(default)]
+    user_index: Option<usize>,
+    #[serde(default)]
+    username: Option<String>,
 }
 
 impl KZGProof {
@@ -38,6 +63,16 @@ impl KZGProof {
     pub fn get_challenge(&self) -> &Option<Vec<U256>> {
         &self.challenge_s_g2
     }
+
+    /// Returns the row index of the user in the snapshot, for the proofs of inclusion
+    pub fn get_user_index(&self) -> Option<usize> {
+        self.user_index
+    }
+
+    /// Returns the username of the user, for the proofs of inclusion
+    pub fn get_username(&self) -> Option<&str> {
+        self.username.as_deref()
+    }
 }
 
 /// The `Round` struct represents a single operational cycle within the Summa Solvency protocol.
@@ -56,41 +91,110 @@ impl KZGProof {
 /// * `snapshot`: A `Snapshot` struct capturing the round's state, including user identities and balances.
 /// * `signer`: A reference to a `SummaSigner`, the entity responsible for signing transactions with the Summa contract in this round.
 ///
-pub struct Round<'a, const N_CURRENCIES: usize, const N_USERS: usize> {
+pub struct Round<'a, const N_CURRENCIES: usize, const N_USERS: usize, S: Signer = LocalWallet> {
     timestamp: u64,
     snapshot: Snapshot<N_CURRENCIES, N_USERS>,
-    signer: &'a SummaSigner,
+    signer: &'a SummaSigner<S>,
 }
 
-impl<const N_CURRENCIES: usize, const N_USERS: usize> Round<'_, N_CURRENCIES, N_USERS>
+impl<const N_CURRENCIES: usize, const N_USERS: usize, S: Signer + 'static>
+    Round<'_, N_CURRENCIES, N_USERS, S>
 where
     [usize; N_CURRENCIES + 1]: Sized,
 {
     pub fn new(
-        signer: &SummaSigner,
+        signer: &SummaSigner<S>,
         zk_snark_proof: Vec<u8>,
         advice_polys: AdviceSingle<G1Affine, Coeff>,
         params: ParamsKZG<Bn256>,
         verifying_key: VerifyingKey<G1Affine>,
+        entries: &[Entry<N_CURRENCIES>],
         timestamp: u64,
-    ) -> Round<'_, N_CURRENCIES, N_USERS> {
-        Round {
+    ) -> Result<Round<'_, N_CURRENCIES, N_USERS, S>, Box<dyn Error>> {
+        Ok(Round {
             timestamp,
             snapshot: Snapshot::<N_CURRENCIES, N_USERS>::new(
                 zk_snark_proof,
                 advice_polys,
                 params,
                 verifying_key,
-            ),
+                entries,
+            )?,
             signer,
-        }
+        })
     }
 
     pub fn get_timestamp(&self) -> u64 {
         self.timestamp
     }
 
-    pub async fn dispatch_commitment(&mut self) -> Result<(), Box<dyn Error>> {
+    /// Returns the context of the round, as carried by its proof bundles
+    ///
+    /// # Arguments
+    ///
+    /// * `cryptocurrencies` - the cryptocurrencies of the balance columns of the entries, in order
+    pub fn get_context(&self, cryptocurrencies: &[Cryptocurrency]) -> RoundContext {
+        RoundContext {
+            chain_id: self.signer.get_chain_id(),
+            summa_address: self.signer.get_summa_address(),
+            timestamp: self.timestamp,
+            k: self.snapshot.get_k(),
+            cryptocurrencies: cryptocurrencies.to_vec(),
+        }
+    }
+
+    /// Compares the configuration of the round with the one of the Summa contract of the signer
+    ///
+    /// # Arguments
+    ///
+    /// * `cryptocurrencies` - the cryptocurrencies of the balance columns of the entries, in order
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<ConfigMismatch>` - the differences in the cryptocurrencies, the balance byte range and the verifying key,
+    ///   empty if the round can be committed to the contract
+    pub async fn check_deployment(
+        &self,
+        cryptocurrencies: &[Cryptocurrency],
+    ) -> Result<Vec<ConfigMismatch>, Box<dyn Error>> {
+        let summa_reader = self.signer.get_reader();
+        let config = summa_reader.get_config().await?;
+        let deployed_verifying_key = summa_reader.get_verifying_key_code().await?;
+        let verifying_key =
+            VerifyingKeyCode::render(&self.snapshot.params, &self.snapshot.verifying_key)?;
+
+        Ok(compare_deployment(
+            N_CURRENCIES,
+            cryptocurrencies,
+            &verifying_key,
+            &config,
+            &deployed_verifying_key,
+        ))
+    }
+
+    /// Fails with the differences between the round and the Summa contract, if any, before the commitment is dispatched
+    pub async fn preflight_check(
+        &self,
+        cryptocurrencies: &[Cryptocurrency],
+    ) -> Result<(), Box<dyn Error>> {
+        let mismatches = self.check_deployment(cryptocurrencies).await?;
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+
+        let diff: Vec<String> = mismatches
+            .iter()
+            .map(|mismatch| format!("  {}", mismatch))
+            .collect();
+        Err(format!(
+            "The round does not match the Summa deployment:\n{}",
+            diff.join("\n")
+        )
+        .into())
+    }
+
+    /// Submits the commitment of the round to the Summa contract, returning the receipt of the transaction
+    pub async fn dispatch_commitment(&mut self) -> Result<CommitmentReceipt, Box<dyn Error>> {
         let grand_sum_proof = self.snapshot.generate_grand_sum_proof().unwrap();
         let submit_tx = self.signer.submit_commitment(
             Bytes::from(self.snapshot.zk_snark_proof.clone()),
@@ -102,9 +206,95 @@ where
         submit_tx.await
     }
 
+    /// Returns the grand sums of the balances of the users, i.e., the total liabilities of each cryptocurrency
+    pub fn get_total_balances(&self) -> Result<Vec<U256>, &'static str> {
+        Ok(self.snapshot.generate_grand_sum_proof()?.input_values)
+    }
+
     pub fn get_proof_of_inclusion(&self, user_index: usize) -> Result<KZGProof, &'static str> {
         self.snapshot.generate_proof_of_inclusion(user_index)
     }
+
+    /// Returns the proof of inclusion of the user with the given username, resolving its row index in the snapshot
+    pub fn get_proof_of_inclusion_for_user(
+        &self,
+        username: &str,
+    ) -> Result<KZGProof, Box<dyn Error>> {
+        self.snapshot.generate_proof_of_inclusion_for_user(username)
+    }
+
+    pub fn get_snapshot(&self) -> &Snapshot<N_CURRENCIES, N_USERS> {
+        &self.snapshot
+    }
+
+    /// Consumes the round and returns its snapshot, e.g., to hand it over to a long-running service once the commitment is submitted
+    pub fn into_snapshot(self) -> Snapshot<N_CURRENCIES, N_USERS> {
+        self.snapshot
+    }
+
+    /// Persists the round state to disk, so that the inclusion proofs can still be served after a restart.
+    /// See [`Snapshot::save`] for the file format.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        self.snapshot.save(self.timestamp, path)
+    }
+
+    /// Restores a round persisted with [`Round::save`], without re-proving.
+    ///
+    /// The circuit type `C` is needed to rebuild the verifying key, and the `params` must be the ones the round was proven with.
+    pub fn load<C: Circuit<Fp>, P: AsRef<Path>>(
+        signer: &SummaSigner<S>,
+        path: P,
+        params: ParamsKZG<Bn256>,
+    ) -> Result<Round<'_, N_CURRENCIES, N_USERS, S>, Box<dyn Error>> {
+        let (timestamp, snapshot) = Snapshot::<N_CURRENCIES, N_USERS>::load::<C, P>(path, params)?;
+
+        Ok(Round {
+            timestamp,
+            snapshot,
+            signer,
+        })
+    }
+}
+
+/// Magic bytes at the start of a persisted snapshot file
+const SNAPSHOT_FILE_MAGIC: [u8; 8] = *b"SUMMASNP";
+
+/// The version of the snapshot file format, to be bumped on any breaking change of `SnapshotFile`
+pub const SNAPSHOT_FILE_VERSION: u16 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotFileHeader {
+    magic: [u8; 8],
+    version: u16,
+}
+
+/// The on-disk representation of a `Snapshot` and of the timestamp of its round.
+///
+/// The KZG parameters are not embedded, they are referenced by the Keccak256 hash of their serialization instead.
+#[derive(Serialize, Deserialize)]
+struct SnapshotFile {
+    timestamp: u64,
+    n_currencies: usize,
+    n_users: usize,
+    k: u32,
+    params_hash: [u8; 32],
+    zk_snark_proof: Vec<u8>,
+    advice_polys: Vec<Vec<[u8; 32]>>,
+    advice_blinds: Vec<[u8; 32]>,
+    verifying_key: Vec<u8>,
+    usernames: Vec<String>,
+}
+
+/// Returns the Keccak256 hash of the serialized KZG parameters
+fn params_hash(params: &ParamsKZG<Bn256>) -> Result<[u8; 32], Box<dyn Error>> {
+    let mut params_bytes = Vec::new();
+    params.write(&mut params_bytes)?;
+    Ok(keccak256(params_bytes))
+}
+
+fn fp_from_bytes(bytes: [u8; 32]) -> Result<Fp, Box<dyn Error>> {
+    Option::from(Fp::from_repr(bytes))
+        .ok_or_else(|| "Invalid field element in snapshot file".into())
 }
 
 /// The `Snapshot` struct represents the state of database that contains users balance on holds by Custodians at a specific moment.
@@ -115,30 +305,216 @@ where
 /// * `advice_polys`: Composed of the unblinded advice polynomial, `advice_poly`, and the polynomials of blind factors, `advice_blind`.
 /// * `params`: The parameters for the KZG commitment scheme.
 /// * `verifying_key`: The verifying key for getting domains, which is used for generating inclusion proofs.
+/// * `usernames`: The usernames of the users, in the order of the rows of the snapshot.
+/// * `user_indices`: The index from the usernames to the row indices of the users.
+/// * `duplicate_usernames`: The usernames appearing in more than one row, which cannot be resolved to a single user.
 ///
 pub struct Snapshot<const N_CURRENCIES: usize, const N_USERS: usize> {
     zk_snark_proof: Vec<u8>,
     advice_polys: AdviceSingle<G1Affine, Coeff>,
     params: ParamsKZG<Bn256>,
     verifying_key: VerifyingKey<G1Affine>,
+    usernames: Vec<String>,
+    user_indices: HashMap<String, usize>,
+    duplicate_usernames: HashSet<String>,
 }
 
 impl<const N_CURRENCIES: usize, const N_USERS: usize> Snapshot<N_CURRENCIES, N_USERS>
 where
     [usize; N_CURRENCIES + 1]: Sized,
 {
+    /// Creates the snapshot of the entries the SNARK proof was generated from.
+    ///
+    /// The usernames are taken from the parsed entries, in the order of the rows of the circuit,
+    /// as they cannot be recovered from the username polynomial when longer than a field element.
     pub fn new(
         zk_snark_proof: Vec<u8>,
         advice_polys: AdviceSingle<G1Affine, Coeff>,
         params: ParamsKZG<Bn256>,
         verifying_key: VerifyingKey<G1Affine>,
-    ) -> Self {
-        Snapshot {
+        entries: &[Entry<N_CURRENCIES>],
+    ) -> Result<Self, Box<dyn Error>> {
+        Self::with_usernames(
             zk_snark_proof,
             advice_polys,
             params,
             verifying_key,
+            entries
+                .iter()
+                .map(|entry| entry.username().to_string())
+                .collect(),
+        )
+    }
+
+    fn with_usernames(
+        zk_snark_proof: Vec<u8>,
+        advice_polys: AdviceSingle<G1Affine, Coeff>,
+        params: ParamsKZG<Bn256>,
+        verifying_key: VerifyingKey<G1Affine>,
+        mut usernames: Vec<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        if usernames.len() > N_USERS {
+            return Err(format!(
+                "{} entries given for a snapshot of {} users",
+                usernames.len(),
+                N_USERS
+            )
+            .into());
+        }
+        // The rows after the entries are empty
+        usernames.resize(N_USERS, String::new());
+
+        let mut user_indices = HashMap::new();
+        let mut duplicate_usernames = HashSet::new();
+        for (user_index, username) in usernames.iter().enumerate() {
+            if username.is_empty() {
+                continue;
+            }
+            if user_indices.insert(username.clone(), user_index).is_some() {
+                duplicate_usernames.insert(username.clone());
+            }
         }
+
+        Ok(Snapshot {
+            zk_snark_proof,
+            advice_polys,
+            params,
+            verifying_key,
+            usernames,
+            user_indices,
+            duplicate_usernames,
+        })
+    }
+
+    /// Writes the snapshot along with the timestamp of its round to a versioned binary file.
+    ///
+    /// The file starts with the magic bytes and the format version, followed by the bincode-encoded snapshot:
+    /// the SNARK proof, the advice polynomials and blinds, the serialized verifying key, the usernames of the rows
+    /// and the hash of the KZG parameters.
+    pub fn save<P: AsRef<Path>>(&self, timestamp: u64, path: P) -> Result<(), Box<dyn Error>> {
+        let mut verifying_key = Vec::new();
+        self.verifying_key
+            .write(&mut verifying_key, SerdeFormat::RawBytes)?;
+
+        let snapshot_file = SnapshotFile {
+            timestamp,
+            n_currencies: N_CURRENCIES,
+            n_users: N_USERS,
+            k: self.params.k(),
+            params_hash: params_hash(&self.params)?,
+            zk_snark_proof: self.zk_snark_proof.clone(),
+            advice_polys: self
+                .advice_polys
+                .advice_polys
+                .iter()
+                .map(|poly| poly.iter().map(|coeff| coeff.to_repr()).collect())
+                .collect(),
+            advice_blinds: self
+                .advice_polys
+                .advice_blinds
+                .iter()
+                .map(|blind| blind.0.to_repr())
+                .collect(),
+            verifying_key,
+            usernames: self.usernames.clone(),
+        };
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(
+            &mut writer,
+            &SnapshotFileHeader {
+                magic: SNAPSHOT_FILE_MAGIC,
+                version: SNAPSHOT_FILE_VERSION,
+            },
+        )?;
+        bincode::serialize_into(&mut writer, &snapshot_file)?;
+
+        Ok(())
+    }
+
+    /// Reads a snapshot written with [`Snapshot::save`] and returns it along with the timestamp of its round.
+    ///
+    /// The circuit type `C` is needed to rebuild the verifying key.
+    /// The given `params` must hash to the reference stored in the file, i.e., be the parameters the snapshot was generated with.
+    pub fn load<C: Circuit<Fp>, P: AsRef<Path>>(
+        path: P,
+        params: ParamsKZG<Bn256>,
+    ) -> Result<(u64, Self), Box<dyn Error>> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let header: SnapshotFileHeader = bincode::deserialize_from(&mut reader)?;
+        if header.magic != SNAPSHOT_FILE_MAGIC {
+            return Err("Not a snapshot file".into());
+        }
+        if header.version != SNAPSHOT_FILE_VERSION {
+            return Err(format!(
+                "Unsupported snapshot file version {}, expected {}",
+                header.version, SNAPSHOT_FILE_VERSION
+            )
+            .into());
+        }
+
+        let snapshot_file: SnapshotFile = bincode::deserialize_from(&mut reader)?;
+        if snapshot_file.n_currencies != N_CURRENCIES || snapshot_file.n_users != N_USERS {
+            return Err(format!(
+                "The snapshot was generated for {} currencies and {} users",
+                snapshot_file.n_currencies, snapshot_file.n_users
+            )
+            .into());
+        }
+        if snapshot_file.k != params.k() || snapshot_file.params_hash != params_hash(&params)? {
+            return Err("The params do not match the ones the snapshot was generated with".into());
+        }
+
+        let verifying_key = VerifyingKey::<G1Affine>::read::<_, C>(
+            &mut snapshot_file.verifying_key.as_slice(),
+            SerdeFormat::RawBytes,
+        )?;
+
+        let domain = verifying_key.get_domain();
+        let advice_polys = snapshot_file
+            .advice_polys
+            .into_iter()
+            .map(|coeffs| -> Result<_, Box<dyn Error>> {
+                let mut poly = domain.empty_coeff();
+                if coeffs.len() != poly.len() {
+                    return Err("Invalid advice polynomial length in snapshot file".into());
+                }
+                for (coeff, bytes) in poly.iter_mut().zip(coeffs) {
+                    *coeff = fp_from_bytes(bytes)?;
+                }
+                Ok(poly)
+            })
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        let advice_blinds = snapshot_file
+            .advice_blinds
+            .into_iter()
+            .map(|bytes| fp_from_bytes(bytes).map(Blind))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((
+            snapshot_file.timestamp,
+            Snapshot::with_usernames(
+                snapshot_file.zk_snark_proof,
+                AdviceSingle {
+                    advice_polys,
+                    advice_blinds,
+                },
+                params,
+                verifying_key,
+                snapshot_file.usernames,
+            )?,
+        ))
+    }
+
+    /// Returns the zkSNARK proof, whose first points are the commitments submitted on-chain
+    pub fn get_zk_snark_proof(&self) -> &[u8] {
+        &self.zk_snark_proof
+    }
+
+    /// Returns the degree parameter of the KZG parameters the snapshot was generated with
+    pub fn get_k(&self) -> u32 {
+        self.params.k()
     }
 
     pub fn generate_grand_sum_proof(&self) -> Result<KZGProof, &'static str> {
@@ -149,10 +525,39 @@ where
             proof_calldata,
             input_values,
             challenge_s_g2: None,
+            user_index: None,
+            username: None,
         })
     }
 
+    /// Returns the row index of the user with the given username
+    pub fn get_user_index(&self, username: &str) -> Result<usize, Box<dyn Error>> {
+        if self.duplicate_usernames.contains(username) {
+            return Err(format!(
+                "Username '{}' appears more than once in the snapshot",
+                username
+            )
+            .into());
+        }
+        self.user_indices
+            .get(username)
+            .copied()
+            .ok_or_else(|| format!("Username '{}' not found in the snapshot", username).into())
+    }
+
+    pub fn generate_proof_of_inclusion_for_user(
+        &self,
+        username: &str,
+    ) -> Result<KZGProof, Box<dyn Error>> {
+        let user_index = self.get_user_index(username)?;
+        Ok(self.generate_proof_of_inclusion(user_index)?)
+    }
+
     pub fn generate_proof_of_inclusion(&self, user_index: usize) -> Result<KZGProof, &'static str> {
+        if user_index >= N_USERS {
+            return Err("User index out of range");
+        }
+
         let omega = self.verifying_key.get_domain().get_omega();
         let challenge = omega.pow_vartime([user_index as u64]);
         let (proof_calldata, input_values) = self
@@ -174,6 +579,8 @@ where
             proof_calldata,
             input_values,
             challenge_s_g2: Some(s_g2_point),
+            user_index: Some(user_index),
+            username: Some(self.usernames[user_index].clone()),
         })
     }
 
@@ -0,0 +1,514 @@
+//! Scheduled lifecycle of the rounds, resumable after a crash.
+//!
+//! At the start of each interval, the `RoundManager` runs a round through the following stages, in order:
+//!
+//! 1. `Proven`: the entries are fetched from the `EntrySource`, proven and the snapshot is saved.
+//! 2. `Verified`: the SNARK proof of the saved snapshot is verified locally.
+//! 3. `Committed`: the commitment of the snapshot is submitted to the Summa contract.
+//! 4. `Published`: the inclusion proofs of the users and the commitment are written as bundles.
+//!
+//! The stage of each round is recorded in the `state.json` file of its directory once completed, after the outputs
+//! of the stage are written. A round interrupted at any point is thus resumed from its last recorded stage,
+//! and the stages run again are idempotent: the snapshot is overwritten until it is recorded as proven,
+//! a commitment already on the Summa contract is not submitted again, and the bundles are overwritten.
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::H256,
+};
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr as Fp, G1Affine},
+    plonk::ProvingKey,
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+};
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use super::{
+    bundle::{CommitmentBundle, InclusionProofBundle, RoundContext},
+    round::{Round, Snapshot},
+};
+use crate::contracts::signer::SummaSigner;
+use summa_solvency::{
+    circuits::{
+        univariate_grand_sum::{UnivariateGrandSum, UnivariateGrandSumConfig},
+        utils::{full_prover, full_verifier, MultiOpenScheme},
+    },
+    cryptocurrency::Cryptocurrency,
+    entry::Entry,
+    utils::parse_csv_to_entries,
+};
+
+/// The name of the file recording the state of a round in its directory
+const STATE_FILE: &str = "state.json";
+
+const SNAPSHOT_FILE: &str = "snapshot.bin";
+
+const COMMITMENT_FILE: &str = "commitment.json";
+
+const PROOFS_DIR: &str = "proofs";
+
+type ManagedCircuit<const N_CURRENCIES: usize, const N_USERS: usize> =
+    UnivariateGrandSum<N_USERS, N_CURRENCIES, UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>>;
+
+/// The source of the entries of the users, e.g., the database of the exchange
+#[async_trait]
+pub trait EntrySource<const N_CURRENCIES: usize, const N_USERS: usize>: Send + Sync {
+    /// Returns the cryptocurrencies of the balances and the entries of the users at the time of the round,
+    /// padded with empty entries up to `N_USERS`
+    async fn fetch_entries(
+        &self,
+        timestamp: u64,
+    ) -> Result<(Vec<Cryptocurrency>, Vec<Entry<N_CURRENCIES>>), Box<dyn Error>>;
+}
+
+/// Entry source reading the entries from a CSV file, as exported from the database of the exchange
+pub struct CsvEntrySource {
+    path: PathBuf,
+}
+
+impl CsvEntrySource {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        CsvEntrySource {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+#[async_trait]
+impl<const N_CURRENCIES: usize, const N_USERS: usize> EntrySource<N_CURRENCIES, N_USERS>
+    for CsvEntrySource
+{
+    async fn fetch_entries(
+        &self,
+        _timestamp: u64,
+    ) -> Result<(Vec<Cryptocurrency>, Vec<Entry<N_CURRENCIES>>), Box<dyn Error>> {
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptocurrencies = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&Path, N_CURRENCIES>(
+            &self.path,
+            &mut entries,
+            &mut cryptocurrencies,
+        )?;
+        Ok((cryptocurrencies, entries))
+    }
+}
+
+/// The stages of a round, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundStage {
+    Scheduled,
+    Proven,
+    Verified,
+    Committed,
+    Published,
+}
+
+/// The recorded state of a round
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundState {
+    pub timestamp: u64,
+    /// The last completed stage of the round
+    pub stage: RoundStage,
+    /// The cryptocurrencies of the entries of the round, recorded once proven
+    pub cryptocurrencies: Vec<Cryptocurrency>,
+    /// The transaction submitting the commitment, unknown if the commitment was found on the Summa contract on resumption
+    pub commitment_transaction: Option<H256>,
+    /// The number of published inclusion proofs
+    pub n_published: usize,
+}
+
+impl RoundState {
+    fn new(timestamp: u64) -> Self {
+        RoundState {
+            timestamp,
+            stage: RoundStage::Scheduled,
+            cryptocurrencies: Vec::new(),
+            commitment_transaction: None,
+            n_published: 0,
+        }
+    }
+}
+
+/// Writes the file through a temporary file, so that a crash never leaves it partially written
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, contents)?;
+    fs::rename(temporary_path, path)?;
+    Ok(())
+}
+
+/// The `RoundManager` runs the rounds of a Summa contract on schedule, recording their state in a directory
+///
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of currencies of the rounds.
+/// * `N_USERS`: The number of users of the rounds.
+/// * `S`: The signer of the transactions of the exchange.
+pub struct RoundManager<
+    'a,
+    const N_CURRENCIES: usize,
+    const N_USERS: usize,
+    S: Signer = LocalWallet,
+> {
+    signer: &'a SummaSigner<S>,
+    source: Box<dyn EntrySource<N_CURRENCIES, N_USERS>>,
+    params: ParamsKZG<Bn256>,
+    pk: Arc<ProvingKey<G1Affine>>,
+    state_dir: PathBuf,
+    scheme: MultiOpenScheme,
+    interval: Duration,
+}
+
+impl<const N_CURRENCIES: usize, const N_USERS: usize, S: Signer + 'static>
+    RoundManager<'_, N_CURRENCIES, N_USERS, S>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    /// Creates a round manager proving with the Shplonk scheme and running a round every day,
+    /// which can be changed with `with_scheme` and `with_interval`
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - the signer of the Summa contract the commitments are submitted to
+    /// * `source` - the source of the entries of each round
+    /// * `params` - the KZG parameters the proving key was generated with
+    /// * `pk` - the proving key of the circuit
+    /// * `state_dir` - the directory of the state and the outputs of the rounds, one subdirectory per round
+    pub fn new<'a, E: EntrySource<N_CURRENCIES, N_USERS> + 'static, P: AsRef<Path>>(
+        signer: &'a SummaSigner<S>,
+        source: E,
+        params: ParamsKZG<Bn256>,
+        pk: ProvingKey<G1Affine>,
+        state_dir: P,
+    ) -> RoundManager<'a, N_CURRENCIES, N_USERS, S> {
+        RoundManager {
+            signer,
+            source: Box::new(source),
+            params,
+            pk: Arc::new(pk),
+            state_dir: state_dir.as_ref().to_path_buf(),
+            scheme: MultiOpenScheme::Shplonk,
+            interval: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// Sets the multi-open scheme of the proofs, which has to match the verifier contract
+    pub fn with_scheme(mut self, scheme: MultiOpenScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Sets the interval between the rounds, a round being run at the start of each interval since the UNIX epoch
+    pub fn with_interval(mut self, interval: Duration) -> Result<Self, Box<dyn Error>> {
+        if interval.as_secs() == 0 {
+            return Err("The interval between the rounds must be at least a second".into());
+        }
+        self.interval = interval;
+        Ok(self)
+    }
+
+    /// Returns the directory of the state and the outputs of the round
+    pub fn get_round_dir(&self, timestamp: u64) -> PathBuf {
+        self.state_dir.join(format!("round_{}", timestamp))
+    }
+
+    /// Returns the recorded state of the round, or `None` if the round was never run
+    pub fn get_state(&self, timestamp: u64) -> Result<Option<RoundState>, Box<dyn Error>> {
+        let state_path = self.get_round_dir(timestamp).join(STATE_FILE);
+        if !state_path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&fs::read(state_path)?)?))
+    }
+
+    /// Returns the recorded states of all the rounds, ordered by timestamp
+    pub fn get_states(&self) -> Result<Vec<RoundState>, Box<dyn Error>> {
+        if !self.state_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut states = Vec::new();
+        for dir_entry in fs::read_dir(&self.state_dir)? {
+            let timestamp = dir_entry?
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("round_"))
+                .and_then(|timestamp| timestamp.parse::<u64>().ok());
+            if let Some(state) = timestamp.map(|t| self.get_state(t)).transpose()?.flatten() {
+                states.push(state);
+            }
+        }
+        states.sort_by_key(|state| state.timestamp);
+        Ok(states)
+    }
+
+    fn record(&self, state: &RoundState) -> Result<(), Box<dyn Error>> {
+        write_atomically(
+            &self.get_round_dir(state.timestamp).join(STATE_FILE),
+            &serde_json::to_vec_pretty(state)?,
+        )
+    }
+
+    /// Fetches and proves the entries of the round, saving its snapshot
+    async fn prove(&self, timestamp: u64) -> Result<Vec<Cryptocurrency>, Box<dyn Error>> {
+        let (cryptocurrencies, entries) = self.source.fetch_entries(timestamp).await?;
+        if cryptocurrencies.len() != N_CURRENCIES || entries.len() != N_USERS {
+            return Err(format!(
+                "The entry source returned {} cryptocurrencies and {} entries, expected {} and {}",
+                cryptocurrencies.len(),
+                entries.len(),
+                N_CURRENCIES,
+                N_USERS
+            )
+            .into());
+        }
+
+        // The proving is CPU-bound, so it is moved off the async runtime
+        let params = self.params.clone();
+        let pk = Arc::clone(&self.pk);
+        let scheme = self.scheme;
+        let (zk_snark_proof, advice_polys) = tokio::task::spawn_blocking(move || {
+            // This instance is necessary to verify proof on solidity verifier.
+            let instances = vec![Fp::zero(); 1];
+            let (zk_snark_proof, advice_polys, _) = full_prover(
+                &params,
+                &pk,
+                ManagedCircuit::<N_CURRENCIES, N_USERS>::init(entries.clone()),
+                &[instances],
+                scheme,
+                None,
+            );
+            (zk_snark_proof, advice_polys)
+        })
+        .await?;
+
+        let snapshot_path = self.get_round_dir(timestamp).join(SNAPSHOT_FILE);
+        let temporary_path = snapshot_path.with_extension("tmp");
+        Snapshot::<N_CURRENCIES, N_USERS>::new(
+            zk_snark_proof,
+            advice_polys,
+            self.params.clone(),
+            self.pk.get_vk().clone(),
+            &entries,
+        )?
+        .save(timestamp, &temporary_path)?;
+        fs::rename(temporary_path, snapshot_path)?;
+
+        Ok(cryptocurrencies)
+    }
+
+    /// Loads the saved snapshot of the round
+    fn load_round(
+        &self,
+        timestamp: u64,
+    ) -> Result<Round<'_, N_CURRENCIES, N_USERS, S>, Box<dyn Error>> {
+        let round =
+            Round::<N_CURRENCIES, N_USERS, S>::load::<ManagedCircuit<N_CURRENCIES, N_USERS>, _>(
+                self.signer,
+                self.get_round_dir(timestamp).join(SNAPSHOT_FILE),
+                self.params.clone(),
+            )?;
+        if round.get_timestamp() != timestamp {
+            return Err(format!(
+                "The snapshot of the round {} is of another round",
+                timestamp
+            )
+            .into());
+        }
+        Ok(round)
+    }
+
+    /// Submits the commitment of the round, unless it is already on the Summa contract.
+    ///
+    /// The round is checked against the configuration and the verifying key of the Summa contract beforehand,
+    /// so that a mismatch is reported instead of a reverted transaction.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<H256>` - the transaction submitting the commitment, `None` if it was already submitted
+    async fn commit(
+        &self,
+        round: &mut Round<'_, N_CURRENCIES, N_USERS, S>,
+        cryptocurrencies: &[Cryptocurrency],
+    ) -> Result<Option<H256>, Box<dyn Error>> {
+        let timestamp = round.get_timestamp();
+        if let Some(commitment) = self.signer.get_reader().get_commitment(timestamp).await? {
+            // The Summa contract stores the commitments of the balances, i.e., the first points of the SNARK proof
+            let zk_snark_proof = round.get_snapshot().get_zk_snark_proof();
+            let commitment_length = 64 * (N_CURRENCIES + 1);
+            if zk_snark_proof.get(..commitment_length) != Some(&commitment[..]) {
+                return Err(format!(
+                    "Another commitment is already submitted for the round {}",
+                    timestamp
+                )
+                .into());
+            }
+            return Ok(None);
+        }
+
+        round.preflight_check(cryptocurrencies).await?;
+        Ok(Some(round.dispatch_commitment().await?.transaction_hash))
+    }
+
+    /// Writes the inclusion proofs of the users and the commitment of the round as bundles
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - the number of published inclusion proofs
+    fn publish(
+        &self,
+        round: &Round<'_, N_CURRENCIES, N_USERS, S>,
+        cryptocurrencies: &[Cryptocurrency],
+    ) -> Result<usize, Box<dyn Error>> {
+        let context = RoundContext {
+            chain_id: self.signer.get_chain_id(),
+            summa_address: self.signer.get_summa_address(),
+            timestamp: round.get_timestamp(),
+            k: self.params.k(),
+            cryptocurrencies: cryptocurrencies.to_vec(),
+        };
+
+        let round_dir = self.get_round_dir(round.get_timestamp());
+        let proofs_dir = round_dir.join(PROOFS_DIR);
+        fs::create_dir_all(&proofs_dir)?;
+
+        let mut n_published = 0;
+        for user_index in 0..N_USERS {
+            let inclusion_proof = round.get_proof_of_inclusion(user_index)?;
+            // Skip the empty rows padding the entries up to the number of users of the circuit
+            if inclusion_proof.get_username() == Some("") {
+                continue;
+            }
+            let bundle = InclusionProofBundle::new(context.clone(), inclusion_proof)?;
+            write_atomically(
+                &proofs_dir.join(format!("user_{}_proof.json", user_index)),
+                bundle.to_json()?.as_bytes(),
+            )?;
+            n_published += 1;
+        }
+
+        let commitment_bundle = CommitmentBundle::from_snapshot(context, round.get_snapshot())?;
+        write_atomically(
+            &round_dir.join(COMMITMENT_FILE),
+            commitment_bundle.to_json()?.as_bytes(),
+        )?;
+
+        Ok(n_published)
+    }
+
+    /// Runs the stages of the round up to the given one, resuming from its last recorded stage
+    pub async fn advance_round(
+        &self,
+        timestamp: u64,
+        until: RoundStage,
+    ) -> Result<RoundState, Box<dyn Error>> {
+        let mut state = match self.get_state(timestamp)? {
+            Some(state) => state,
+            None => {
+                fs::create_dir_all(self.get_round_dir(timestamp))?;
+                RoundState::new(timestamp)
+            }
+        };
+
+        if state.stage < RoundStage::Proven && until >= RoundStage::Proven {
+            state.cryptocurrencies = self.prove(timestamp).await?;
+            state.stage = RoundStage::Proven;
+            self.record(&state)?;
+        }
+        if state.stage >= until {
+            return Ok(state);
+        }
+
+        let mut round = self.load_round(timestamp)?;
+        if state.stage < RoundStage::Verified {
+            let instances = vec![Fp::zero(); 1];
+            if !full_verifier(
+                &self.params,
+                self.pk.get_vk(),
+                round.get_snapshot().get_zk_snark_proof(),
+                &[instances],
+                self.scheme,
+            ) {
+                return Err(
+                    format!("The SNARK proof of the round {} does not verify", timestamp).into(),
+                );
+            }
+            state.stage = RoundStage::Verified;
+            self.record(&state)?;
+        }
+        if state.stage < RoundStage::Committed && until >= RoundStage::Committed {
+            state.commitment_transaction = self.commit(&mut round, &state.cryptocurrencies).await?;
+            state.stage = RoundStage::Committed;
+            self.record(&state)?;
+        }
+        if state.stage < RoundStage::Published && until >= RoundStage::Published {
+            state.n_published = self.publish(&round, &state.cryptocurrencies)?;
+            state.stage = RoundStage::Published;
+            self.record(&state)?;
+        }
+
+        Ok(state)
+    }
+
+    /// Runs all the stages of the round, resuming from its last recorded stage
+    pub async fn run_round(&self, timestamp: u64) -> Result<RoundState, Box<dyn Error>> {
+        self.advance_round(timestamp, RoundStage::Published).await
+    }
+
+    /// Resumes the rounds which are not published yet, e.g., after a crash
+    pub async fn resume(&self) -> Result<Vec<RoundState>, Box<dyn Error>> {
+        let mut states = Vec::new();
+        for state in self.get_states()? {
+            if state.stage < RoundStage::Published {
+                states.push(self.run_round(state.timestamp).await?);
+            }
+        }
+        Ok(states)
+    }
+
+    /// Returns the timestamp of the round scheduled at the given time, i.e., the start of its interval
+    pub fn get_scheduled_timestamp(&self, time: u64) -> u64 {
+        time - time % self.interval.as_secs()
+    }
+
+    /// Runs the rounds on schedule, after resuming the unfinished ones.
+    ///
+    /// A round is run at the start of each interval, unless it is already published.
+    /// Returns the states of the rounds run on schedule once `max_rounds` of them are run, never if `None`,
+    /// or on the first failed round, which is resumed by the next run.
+    pub async fn run(&self, max_rounds: Option<usize>) -> Result<Vec<RoundState>, Box<dyn Error>> {
+        self.resume().await?;
+
+        let mut states = Vec::new();
+        if max_rounds == Some(0) {
+            return Ok(states);
+        }
+        loop {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let timestamp = self.get_scheduled_timestamp(now);
+            if self.get_state(timestamp)?.map(|state| state.stage) != Some(RoundStage::Published) {
+                states.push(self.run_round(timestamp).await?);
+                if Some(states.len()) == max_rounds {
+                    return Ok(states);
+                }
+            }
+
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let next_timestamp = timestamp + self.interval.as_secs();
+            if next_timestamp > now {
+                sleep(Duration::from_secs(next_timestamp - now)).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,725 @@
+//! Off-chain verification of the signatures of the proofs of address ownership.
+//!
+//! The Summa contract only checks that the fields of the proofs are not empty, leaving the verification of the
+//! signatures to the users and the auditors. Each proof is dispatched by its `chain` to a `ChainSignatureVerifier`:
+//!
+//! * `ETH`: `personal_sign` over the keccak256 hash of the ABI-encoded message, as submitted to the contract,
+//!   or the EIP-712 signature of the structured [`OwnershipMessage`].
+//! * `TRX`: TronWeb `signMessageV2` over the message string.
+//! * `BTC`: BIP-137 signatures of P2PKH, P2SH-P2WPKH and P2WPKH addresses, or BIP-322 simple signatures,
+//!   i.e., the serialized witness stack, of P2WPKH addresses, over the message string.
+//! * `SOL`: ed25519 signatures over the message string, with the base58-encoded public key as address.
+//!
+//! The structured messages are further checked against a [`MessageBinding`] with
+//! [`SignatureVerifiers::validate_with_binding`].
+use std::{collections::HashMap, fmt};
+
+use ethers::{
+    abi::AbiDecode,
+    core::k256::ecdsa::{
+        signature::hazmat::PrehashVerifier, RecoveryId, Signature as EcdsaSignature, VerifyingKey,
+    },
+    types::{transaction::eip712::Eip712, Address, Signature, H256},
+    utils::{keccak256, to_checksum},
+};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use super::ownership_message::{MessageBinding, OwnershipMessage};
+use crate::contracts::generated::summa_contract::AddressOwnershipProof;
+
+/// The outcome of the off-chain verification of a proof of address ownership
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureValidation {
+    /// The signature is made by the key of the address of the proof
+    Valid,
+    /// The signature is recovered to another address than the one of the proof
+    AddressMismatch { recovered: String },
+    /// The signature is not made by the key of the address of the proof
+    Mismatch,
+    /// The address or the signature of the proof is malformed
+    Invalid(String),
+    /// The message of the proof is not bound to the expected exchange, contract, chain or time
+    MessageRejected(String),
+    /// The signatures of the chain of the proof are not verified off-chain
+    Unsupported,
+}
+
+impl SignatureValidation {
+    /// Returns whether the proof has to be rejected, i.e., its signature is verified and does not match its address
+    pub fn is_rejected(&self) -> bool {
+        matches!(
+            self,
+            SignatureValidation::AddressMismatch { .. }
+                | SignatureValidation::Mismatch
+                | SignatureValidation::Invalid(_)
+                | SignatureValidation::MessageRejected(_)
+        )
+    }
+}
+
+impl fmt::Display for SignatureValidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureValidation::Valid => write!(f, "valid"),
+            SignatureValidation::AddressMismatch { recovered } => {
+                write!(f, "signature recovered to another address {}", recovered)
+            }
+            SignatureValidation::Mismatch => write!(f, "signature not made by the address"),
+            SignatureValidation::Invalid(reason) => write!(f, "invalid: {}", reason),
+            SignatureValidation::MessageRejected(reason) => {
+                write!(f, "message rejected: {}", reason)
+            }
+            SignatureValidation::Unsupported => write!(f, "not verified off-chain"),
+        }
+    }
+}
+
+/// The validation result of a proof of address ownership, in a signatures CSV or on the Summa contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowValidation {
+    /// The 1-based position of the proof, i.e., its row in the CSV excluding the header
+    pub row: usize,
+    pub chain: String,
+    pub cex_address: String,
+    pub result: SignatureValidation,
+}
+
+impl fmt::Display for RowValidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} ({} {}): {}",
+            self.row, self.chain, self.cex_address, self.result
+        )
+    }
+}
+
+/// Verifier of the signatures of the addresses of a chain
+pub trait ChainSignatureVerifier: Send + Sync {
+    /// Verifies that the signature of the proof is made by the key of its address over its message
+    fn verify(&self, proof: &AddressOwnershipProof) -> SignatureValidation;
+}
+
+/// The signature verifiers of the supported chains, keyed by the `chain` of the proofs
+pub struct SignatureVerifiers {
+    verifiers: HashMap<String, Box<dyn ChainSignatureVerifier>>,
+}
+
+impl Default for SignatureVerifiers {
+    /// Returns the verifiers of `ETH`, `TRX`, `BTC` and `SOL`
+    fn default() -> Self {
+        let mut verifiers = SignatureVerifiers::empty();
+        verifiers.register("ETH", Box::new(EvmSignatureVerifier));
+        verifiers.register("TRX", Box::new(TronSignatureVerifier));
+        verifiers.register("BTC", Box::new(BitcoinSignatureVerifier));
+        verifiers.register("SOL", Box::new(Ed25519SignatureVerifier));
+        verifiers
+    }
+}
+
+impl SignatureVerifiers {
+    /// Returns a set of verifiers without any chain
+    pub fn empty() -> Self {
+        SignatureVerifiers {
+            verifiers: HashMap::new(),
+        }
+    }
+
+    /// Registers the verifier of the given chain, replacing any previous one
+    pub fn register(&mut self, chain: &str, verifier: Box<dyn ChainSignatureVerifier>) {
+        self.verifiers.insert(chain.to_string(), verifier);
+    }
+
+    /// Verifies the signature of a proof of address ownership with the verifier of its chain
+    pub fn verify(&self, proof: &AddressOwnershipProof) -> SignatureValidation {
+        match self.verifiers.get(&proof.chain) {
+            Some(verifier) => verifier.verify(proof),
+            None => SignatureValidation::Unsupported,
+        }
+    }
+
+    /// Verifies the signatures of the proofs of address ownership, in order
+    pub fn validate(&self, proofs: &[AddressOwnershipProof]) -> Vec<RowValidation> {
+        proofs
+            .iter()
+            .enumerate()
+            .map(|(index, proof)| RowValidation {
+                row: index + 1,
+                chain: proof.chain.clone(),
+                cex_address: proof.cex_address.clone(),
+                result: self.verify(proof),
+            })
+            .collect()
+    }
+
+    /// Verifies the signatures of the proofs of address ownership, in order, rejecting the proofs whose signature is
+    /// not rejected but whose message does not match the binding
+    pub fn validate_with_binding(
+        &self,
+        proofs: &[AddressOwnershipProof],
+        binding: &MessageBinding,
+    ) -> Vec<RowValidation> {
+        let mut validations = self.validate(proofs);
+        for (validation, proof) in validations.iter_mut().zip(proofs) {
+            if validation.result.is_rejected() {
+                continue;
+            }
+            if let Err(reason) = binding.check_proof(proof) {
+                validation.result = SignatureValidation::MessageRejected(reason);
+            }
+        }
+        validations
+    }
+}
+
+/// Verifies the signature of a proof of address ownership according to its chain, with the default verifiers
+pub fn verify_signature(proof: &AddressOwnershipProof) -> SignatureValidation {
+    SignatureVerifiers::default().verify(proof)
+}
+
+/// Verifies the signatures of the proofs of address ownership, in the order of the rows of the signatures CSV,
+/// with the default verifiers
+pub fn validate_address_ownership_proofs(proofs: &[AddressOwnershipProof]) -> Vec<RowValidation> {
+    SignatureVerifiers::default().validate(proofs)
+}
+
+/// Returns the message string signed by the non-EVM addresses, which is ABI-encoded in the message of the proof
+fn message_text(proof: &AddressOwnershipProof) -> Vec<u8> {
+    match String::decode(proof.message.as_ref()) {
+        Ok(text) => text.into_bytes(),
+        Err(_) => proof.message.to_vec(),
+    }
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+fn double_sha256(data: &[u8]) -> [u8; 32] {
+    sha256(&sha256(data))
+}
+
+fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(sha256(data)).into()
+}
+
+fn tagged_hash(tag: &[u8], data: &[u8]) -> [u8; 32] {
+    let tag_hash = sha256(tag);
+    sha256(&[&tag_hash[..], &tag_hash[..], data].concat())
+}
+
+fn compact_size(n: usize) -> Vec<u8> {
+    match n {
+        0..=0xfc => vec![n as u8],
+        0xfd..=0xffff => [&[0xfd][..], &(n as u16).to_le_bytes()].concat(),
+        _ => [&[0xfe][..], &(n as u32).to_le_bytes()].concat(),
+    }
+}
+
+fn read_compact_size(data: &mut &[u8]) -> Option<usize> {
+    let (&first, rest) = data.split_first()?;
+    let (size, length) = match first {
+        0xfd => (
+            u16::from_le_bytes(rest.get(..2)?.try_into().ok()?) as usize,
+            2,
+        ),
+        0xfe => (
+            u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize,
+            4,
+        ),
+        0xff => return None,
+        _ => (first as usize, 0),
+    };
+    *data = &rest[length..];
+    Some(size)
+}
+
+fn decode_base58_check(encoded: &str) -> Option<Vec<u8>> {
+    let bytes = bs58::decode(encoded).into_vec().ok()?;
+    if bytes.len() < 4 {
+        return None;
+    }
+    let (payload, checksum) = bytes.split_at(bytes.len() - 4);
+    (double_sha256(payload)[..4] == *checksum).then(|| payload.to_vec())
+}
+
+fn encode_base58_check(payload: &[u8]) -> String {
+    bs58::encode([payload, &double_sha256(payload)[..4]].concat()).into_string()
+}
+
+/// Recovers the public key of a 65-byte `r || s || v` or `header || r || s` secp256k1 signature over a hash
+fn recover_secp256k1(
+    prehash: &[u8; 32],
+    signature: &[u8],
+    recovery_byte: u8,
+) -> Result<VerifyingKey, String> {
+    let recovery_id = RecoveryId::from_byte(recovery_byte).ok_or("invalid recovery ID")?;
+    let signature = EcdsaSignature::from_slice(signature).map_err(|e| e.to_string())?;
+    VerifyingKey::recover_from_prehash(prehash, &signature, recovery_id).map_err(|e| e.to_string())
+}
+
+/// Verifier of the `personal_sign` signatures of the EVM addresses over the keccak256 hash of the message,
+/// or of their EIP-712 signatures of the structured ownership messages
+pub struct EvmSignatureVerifier;
+
+impl ChainSignatureVerifier for EvmSignatureVerifier {
+    fn verify(&self, proof: &AddressOwnershipProof) -> SignatureValidation {
+        let address: Address = match proof.cex_address.parse() {
+            Ok(address) => address,
+            Err(_) => return SignatureValidation::Invalid("malformed EVM address".to_string()),
+        };
+        let signature = match Signature::try_from(proof.signature.as_ref()) {
+            Ok(signature) => signature,
+            Err(e) => return SignatureValidation::Invalid(e.to_string()),
+        };
+
+        let recovered = match OwnershipMessage::from_proof(proof) {
+            Some(message) => match message.encode_eip712() {
+                Ok(hash) => signature.recover(H256::from(hash)),
+                Err(e) => return SignatureValidation::Invalid(e.to_string()),
+            },
+            None => signature.recover(keccak256(&proof.message).to_vec()),
+        };
+        match recovered {
+            Ok(recovered) if recovered == address => SignatureValidation::Valid,
+            Ok(recovered) => SignatureValidation::AddressMismatch {
+                recovered: to_checksum(&recovered, None),
+            },
+            Err(e) => SignatureValidation::Invalid(e.to_string()),
+        }
+    }
+}
+
+/// Verifier of the TronWeb `signMessageV2` signatures of the Tron addresses
+pub struct TronSignatureVerifier;
+
+impl ChainSignatureVerifier for TronSignatureVerifier {
+    fn verify(&self, proof: &AddressOwnershipProof) -> SignatureValidation {
+        let address = match decode_base58_check(&proof.cex_address) {
+            Some(address) if address.len() == 21 && address[0] == 0x41 => address,
+            _ => return SignatureValidation::Invalid("malformed Tron address".to_string()),
+        };
+        if proof.signature.len() != 65 {
+            return SignatureValidation::Invalid("the signature is not 65 bytes long".to_string());
+        }
+
+        let message = message_text(proof);
+        let prehash = keccak256(
+            [
+                &b"\x19TRON Signed Message:\n"[..],
+                message.len().to_string().as_bytes(),
+                &message,
+            ]
+            .concat(),
+        );
+        let v = proof.signature[64];
+        let key = match recover_secp256k1(
+            &prehash,
+            &proof.signature[..64],
+            if v >= 27 { v - 27 } else { v },
+        ) {
+            Ok(key) => key,
+            Err(e) => return SignatureValidation::Invalid(e),
+        };
+
+        let recovered = &keccak256(&key.to_encoded_point(false).as_bytes()[1..])[12..];
+        if recovered == &address[1..] {
+            SignatureValidation::Valid
+        } else {
+            SignatureValidation::AddressMismatch {
+                recovered: encode_base58_check(&[&[0x41], recovered].concat()),
+            }
+        }
+    }
+}
+
+/// The kinds of Bitcoin addresses whose signatures are verified, with their hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BitcoinAddress {
+    P2pkh([u8; 20]),
+    P2sh([u8; 20]),
+    P2wpkh([u8; 20]),
+}
+
+/// Decodes a mainnet or testnet Bitcoin address
+fn decode_bitcoin_address(address: &str) -> Option<BitcoinAddress> {
+    let lowercase = address.to_lowercase();
+    if lowercase.starts_with("bc1") || lowercase.starts_with("tb1") {
+        use bech32::FromBase32;
+
+        let (_, data) = bech32::decode(address).ok()?;
+        let (version, program) = data.split_first()?;
+        let program = Vec::<u8>::from_base32(program).ok()?;
+        return match (version.to_u8(), program.len()) {
+            (0, 20) => Some(BitcoinAddress::P2wpkh(program.try_into().ok()?)),
+            _ => None,
+        };
+    }
+
+    let payload = decode_base58_check(address)?;
+    let (version, hash) = payload.split_first()?;
+    let hash: [u8; 20] = hash.try_into().ok()?;
+    match version {
+        0x00 | 0x6f => Some(BitcoinAddress::P2pkh(hash)),
+        0x05 | 0xc4 => Some(BitcoinAddress::P2sh(hash)),
+        _ => None,
+    }
+}
+
+/// Verifier of the BIP-137 and BIP-322 simple signatures of the Bitcoin addresses
+pub struct BitcoinSignatureVerifier;
+
+impl BitcoinSignatureVerifier {
+    fn verify_bip137(
+        address: &BitcoinAddress,
+        message: &[u8],
+        signature: &[u8],
+    ) -> SignatureValidation {
+        let header = signature[0];
+        let prehash = double_sha256(
+            &[
+                &b"\x18Bitcoin Signed Message:\n"[..],
+                &compact_size(message.len())[..],
+                message,
+            ]
+            .concat(),
+        );
+        let key = match recover_secp256k1(&prehash, &signature[1..], (header - 27) & 3) {
+            Ok(key) => key,
+            Err(e) => return SignatureValidation::Invalid(e),
+        };
+
+        // The headers 27 to 30 are for uncompressed keys, the others for compressed keys. As some wallets use the
+        // P2PKH headers for the segwit addresses as well, the address type is taken from the address itself.
+        let compressed = header >= 31;
+        let key_hash = hash160(key.to_encoded_point(compressed).as_bytes());
+        let matches = match address {
+            BitcoinAddress::P2pkh(hash) => *hash == key_hash,
+            BitcoinAddress::P2sh(hash) => {
+                compressed && *hash == hash160(&[&[0x00, 0x14][..], &key_hash[..]].concat())
+            }
+            BitcoinAddress::P2wpkh(hash) => compressed && *hash == key_hash,
+        };
+        if matches {
+            SignatureValidation::Valid
+        } else {
+            SignatureValidation::Mismatch
+        }
+    }
+
+    /// Returns the BIP-143 signature hash of the virtual `to_sign` transaction of BIP-322 for a P2WPKH address
+    fn bip322_sighash(key_hash: &[u8; 20], message: &[u8], sighash_type: u8) -> [u8; 32] {
+        let script_pubkey = [&[0x00, 0x14][..], key_hash].concat();
+        let message_hash = tagged_hash(b"BIP0322-signed-message", message);
+
+        // The virtual `to_spend` transaction, committing to the message and the address
+        let to_spend = [
+            &0u32.to_le_bytes()[..],
+            &[0x01],
+            &[0u8; 32],
+            &u32::MAX.to_le_bytes(),
+            &[0x22, 0x00, 0x20],
+            &message_hash,
+            &0u32.to_le_bytes(),
+            &[0x01],
+            &0u64.to_le_bytes(),
+            &compact_size(script_pubkey.len()),
+            &script_pubkey,
+            &0u32.to_le_bytes(),
+        ]
+        .concat();
+        let outpoint = [&double_sha256(&to_spend)[..], &0u32.to_le_bytes()].concat();
+
+        // The virtual `to_sign` transaction spends the `to_spend` output to a single `OP_RETURN` output
+        let script_code = [&[0x19, 0x76, 0xa9, 0x14][..], key_hash, &[0x88, 0xac]].concat();
+        let preimage = [
+            &0u32.to_le_bytes()[..],
+            &double_sha256(&outpoint),
+            &double_sha256(&0u32.to_le_bytes()),
+            &outpoint,
+            &script_code,
+            &0u64.to_le_bytes(),
+            &0u32.to_le_bytes(),
+            &double_sha256(&[&0u64.to_le_bytes()[..], &[0x01, 0x6a]].concat()),
+            &0u32.to_le_bytes(),
+            &(sighash_type as u32).to_le_bytes(),
+        ]
+        .concat();
+        double_sha256(&preimage)
+    }
+
+    fn verify_bip322_simple(
+        address: &BitcoinAddress,
+        message: &[u8],
+        witness: &[u8],
+    ) -> SignatureValidation {
+        let key_hash = match address {
+            BitcoinAddress::P2wpkh(hash) => hash,
+            _ => {
+                return SignatureValidation::Invalid(
+                    "BIP-322 signatures are only verified for P2WPKH addresses".to_string(),
+                )
+            }
+        };
+
+        // The simple signature is the serialized witness stack, i.e., the signature and the public key
+        let mut data = witness;
+        let mut stack = Vec::new();
+        for _ in 0..read_compact_size(&mut data).unwrap_or_default() {
+            match read_compact_size(&mut data).and_then(|length| data.get(..length)) {
+                Some(item) => {
+                    stack.push(item);
+                    data = &data[item.len()..];
+                }
+                None => break,
+            }
+        }
+        let (signature, public_key) = match stack[..] {
+            [signature, public_key] if !signature.is_empty() && data.is_empty() => {
+                (signature, public_key)
+            }
+            _ => return SignatureValidation::Invalid("malformed BIP-322 witness".to_string()),
+        };
+        if hash160(public_key) != *key_hash {
+            return SignatureValidation::Mismatch;
+        }
+
+        let (sighash_type, der_signature) = signature.split_last().unwrap();
+        let (key, signature) = match (
+            VerifyingKey::from_sec1_bytes(public_key),
+            EcdsaSignature::from_der(der_signature),
+        ) {
+            (Ok(key), Ok(signature)) => (key, signature.normalize_s().unwrap_or(signature)),
+            _ => return SignatureValidation::Invalid("malformed BIP-322 witness".to_string()),
+        };
+        let sighash = Self::bip322_sighash(key_hash, message, *sighash_type);
+        match key.verify_prehash(&sighash, &signature) {
+            Ok(()) => SignatureValidation::Valid,
+            Err(_) => SignatureValidation::Mismatch,
+        }
+    }
+}
+
+impl ChainSignatureVerifier for BitcoinSignatureVerifier {
+    fn verify(&self, proof: &AddressOwnershipProof) -> SignatureValidation {
+        let address = match decode_bitcoin_address(&proof.cex_address) {
+            Some(address) => address,
+            None => {
+                return SignatureValidation::Invalid(
+                    "malformed or unsupported Bitcoin address".to_string(),
+                )
+            }
+        };
+
+        let message = message_text(proof);
+        let signature = proof.signature.as_ref();
+        match signature.first() {
+            Some(27..=42) if signature.len() == 65 => {
+                Self::verify_bip137(&address, &message, signature)
+            }
+            _ => Self::verify_bip322_simple(&address, &message, signature),
+        }
+    }
+}
+
+/// Verifier of the ed25519 signatures of the addresses encoding their public key in base58, as on Solana
+pub struct Ed25519SignatureVerifier;
+
+impl ChainSignatureVerifier for Ed25519SignatureVerifier {
+    fn verify(&self, proof: &AddressOwnershipProof) -> SignatureValidation {
+        let public_key = match bs58::decode(&proof.cex_address)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .and_then(|bytes| ed25519_dalek::VerifyingKey::from_bytes(&bytes).ok())
+        {
+            Some(public_key) => public_key,
+            None => return SignatureValidation::Invalid("malformed ed25519 address".to_string()),
+        };
+        let signature = match ed25519_dalek::Signature::from_slice(&proof.signature) {
+            Ok(signature) => signature,
+            Err(e) => return SignatureValidation::Invalid(e.to_string()),
+        };
+
+        match public_key.verify_strict(&message_text(proof), &signature) {
+            Ok(()) => SignatureValidation::Valid,
+            Err(_) => SignatureValidation::Mismatch,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::csv_parser::parse_signature_csv;
+    use ethers::abi::AbiEncode;
+
+    fn proof(
+        chain: &str,
+        cex_address: &str,
+        signature: &str,
+        message: &str,
+    ) -> AddressOwnershipProof {
+        AddressOwnershipProof {
+            chain: chain.to_string(),
+            cex_address: cex_address.to_string(),
+            signature: signature.parse().unwrap(),
+            message: message.to_string().encode().into(),
+        }
+    }
+
+    const MESSAGE: &str = "Summa proof of solvency for CryptoExchange";
+
+    #[test]
+    fn test_verify_signatures_of_csv() {
+        let proofs = parse_signature_csv("../csv/signatures.csv").unwrap();
+        let validations = validate_address_ownership_proofs(&proofs);
+
+        assert_eq!(validations.len(), proofs.len());
+        for (row, validation) in validations.iter().enumerate() {
+            assert_eq!(validation.row, row + 1);
+            assert_eq!(validation.result, SignatureValidation::Valid);
+        }
+    }
+
+    #[test]
+    fn test_reject_mismatching_signatures() {
+        let proofs = parse_signature_csv("../csv/signatures.csv").unwrap();
+
+        // The signature of the second address does not prove the ownership of the first one
+        let mut swapped_proof = proofs[0].clone();
+        swapped_proof.signature = proofs[1].signature.clone();
+        assert_eq!(
+            verify_signature(&swapped_proof),
+            SignatureValidation::AddressMismatch {
+                recovered: proofs[1].cex_address.clone()
+            }
+        );
+
+        let mut truncated_proof = proofs[0].clone();
+        truncated_proof.signature = proofs[0].signature[..64].to_vec().into();
+        assert!(verify_signature(&truncated_proof).is_rejected());
+
+        let mut malformed_address_proof = proofs[0].clone();
+        malformed_address_proof.cex_address = "0x1234".to_string();
+        assert!(verify_signature(&malformed_address_proof).is_rejected());
+
+        // The proofs of the chains without verifier are left to the off-chain verification
+        let mut other_chain_proof = proofs[0].clone();
+        other_chain_proof.chain = "DOGE".to_string();
+        assert_eq!(
+            verify_signature(&other_chain_proof),
+            SignatureValidation::Unsupported
+        );
+        assert!(!SignatureValidation::Unsupported.is_rejected());
+    }
+
+    #[test]
+    fn test_verify_bitcoin_signatures() {
+        // BIP-137 signatures of the same compressed key, with the headers of the three address types
+        let p2pkh_proof = proof(
+            "BTC",
+            "1MXxss48W7CFF2KgdQzHZ2v5rcrdjZw9LS",
+            "0x202d20e27338eb006035f047b9ef7270ccfeb5e1ed641ea5d4838ba43cb7268a6e1127eaff2fd3681c4a9b94806a1db1517d4df09e4fa7e476f0d7e620b247604e",
+            MESSAGE,
+        );
+        let p2sh_p2wpkh_proof = proof(
+            "BTC",
+            "3Bx9NZa2EvDaTpVkp4Q2NJHredaLEpUrYU",
+            "0x242d20e27338eb006035f047b9ef7270ccfeb5e1ed641ea5d4838ba43cb7268a6e1127eaff2fd3681c4a9b94806a1db1517d4df09e4fa7e476f0d7e620b247604e",
+            MESSAGE,
+        );
+        let p2wpkh_proof = proof(
+            "BTC",
+            "bc1quy76xex2yug9vhyw0ccgtxsu03swdt9v5at0xl",
+            "0x282d20e27338eb006035f047b9ef7270ccfeb5e1ed641ea5d4838ba43cb7268a6e1127eaff2fd3681c4a9b94806a1db1517d4df09e4fa7e476f0d7e620b247604e",
+            MESSAGE,
+        );
+        for proof in [&p2pkh_proof, &p2sh_p2wpkh_proof, &p2wpkh_proof] {
+            assert_eq!(verify_signature(proof), SignatureValidation::Valid);
+        }
+
+        // The signature of another message or for another address is rejected
+        let mut other_message_proof = p2pkh_proof.clone();
+        other_message_proof.message = "Another message".to_string().encode().into();
+        assert_eq!(
+            verify_signature(&other_message_proof),
+            SignatureValidation::Mismatch
+        );
+        let mut other_address_proof = p2pkh_proof.clone();
+        other_address_proof.cex_address = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2".to_string();
+        assert_eq!(
+            verify_signature(&other_address_proof),
+            SignatureValidation::Mismatch
+        );
+
+        // The "Hello World" BIP-322 simple signature test vector
+        let bip322_proof = proof(
+            "BTC",
+            "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l",
+            "0x0247304402206517c8637a7bfc3a154edcba6196d64bbd5b73955cb7da7d1626bcdde466c364022022bf10d19fc0bb69b4596e306b362acaa835293cf693bb176f7324b531f5afec012102c7f12003196442943d8588e01aee840423cc54fc1521526a3b85c2b0cbd58872",
+            "Hello World",
+        );
+        assert_eq!(verify_signature(&bip322_proof), SignatureValidation::Valid);
+        let mut other_message_proof = bip322_proof.clone();
+        other_message_proof.message = MESSAGE.to_string().encode().into();
+        assert_eq!(
+            verify_signature(&other_message_proof),
+            SignatureValidation::Mismatch
+        );
+    }
+
+    #[test]
+    fn test_verify_tron_and_ed25519_signatures() {
+        let tron_proof = proof(
+            "TRX",
+            "TM6o53ixujcDRcA6VMT5dd3W9dmRDc21sh",
+            "0x5aadc7870ad62779ebedd55d9579b0dc24b7fcbc70950e2da4c6ba213962896657496a3faca7d6e687a1f8037617f9a5cd216bf7613cf6a5ddfcb29230ec481d1b",
+            MESSAGE,
+        );
+        assert_eq!(verify_signature(&tron_proof), SignatureValidation::Valid);
+        let mut other_message_proof = tron_proof.clone();
+        other_message_proof.message = "Another message".to_string().encode().into();
+        assert!(matches!(
+            verify_signature(&other_message_proof),
+            SignatureValidation::AddressMismatch { .. }
+        ));
+
+        let solana_proof = proof(
+            "SOL",
+            "5DCby79PUqvtRjdkzyyVV256ZbnxmuYgRSgvumiFDnpo",
+            "0xd7c6ac474e981a57a76b1170b7472aa2dd51707e122cb49b107226e57b7c207629c2f44eaa2a0466cd7fe110cc050810517c971db765498ec1134b8a0bf5a601",
+            MESSAGE,
+        );
+        assert_eq!(verify_signature(&solana_proof), SignatureValidation::Valid);
+        let mut other_message_proof = solana_proof.clone();
+        other_message_proof.message = "Another message".to_string().encode().into();
+        assert_eq!(
+            verify_signature(&other_message_proof),
+            SignatureValidation::Mismatch
+        );
+    }
+
+    #[test]
+    fn test_register_verifier() {
+        struct RejectAll;
+        impl ChainSignatureVerifier for RejectAll {
+            fn verify(&self, _proof: &AddressOwnershipProof) -> SignatureValidation {
+                SignatureValidation::Mismatch
+            }
+        }
+
+        let proofs = parse_signature_csv("../csv/signatures.csv").unwrap();
+        let mut verifiers = SignatureVerifiers::empty();
+        assert_eq!(
+            verifiers.verify(&proofs[0]),
+            SignatureValidation::Unsupported
+        );
+        verifiers.register("ETH", Box::new(RejectAll));
+        assert!(verifiers
+            .validate(&proofs)
+            .iter()
+            .all(|validation| validation.result == SignatureValidation::Mismatch));
+    }
+}
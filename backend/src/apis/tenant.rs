@@ -0,0 +1,493 @@
+//! Registry of the Summa deployments run by a custodian on behalf of several exchanges.
+//!
+//! Each tenant is an exchange with its own Summa contract, signer, circuit shape, currency list and keys.
+//! The `Tenant` trait erases the circuit shape, i.e., the `N_CURRENCIES` and `N_USERS` const generics,
+//! so that the `TenantRegistry` holds tenants of different shapes side by side.
+//! The rounds of a tenant run one at a time, while the rounds of different tenants run concurrently.
+//! The snapshots of the rounds are persisted in the directory of the tenant, only the most recently used ones
+//! being kept in memory to serve the proofs of inclusion.
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{Address, U256},
+};
+use futures::future::join_all;
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr as Fp, G1Affine},
+    plonk::ProvingKey,
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::round::{KZGProof, Round, Snapshot};
+use crate::contracts::{signer::SummaSigner, transaction::CommitmentReceipt};
+use summa_solvency::{
+    circuits::{
+        univariate_grand_sum::{UnivariateGrandSum, UnivariateGrandSumConfig},
+        utils::{full_prover, full_verifier, MultiOpenScheme},
+    },
+    cryptocurrency::Cryptocurrency,
+    entry::Entry,
+    utils::parse_csv_to_entries,
+};
+
+/// The number of snapshots a tenant keeps in memory by default
+pub const DEFAULT_SNAPSHOT_CACHE_SIZE: usize = 2;
+
+/// The shape of the circuit of a tenant, which has to match the verifier contracts of its Summa deployment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitShape {
+    pub n_currencies: usize,
+    pub n_users: usize,
+    pub k: u32,
+}
+
+/// A round run by a tenant
+#[derive(Debug, Clone, PartialEq)]
+pub struct TenantRound {
+    pub tenant: String,
+    pub timestamp: u64,
+    /// The grand sums of the balances of the users, as committed on the Summa contract
+    pub total_balances: Vec<U256>,
+    pub receipt: CommitmentReceipt,
+}
+
+/// An exchange whose rounds are run against its own Summa deployment
+#[async_trait]
+pub trait Tenant: Send + Sync {
+    /// Returns the name of the tenant, unique within a registry
+    fn get_name(&self) -> &str;
+
+    /// Returns the address of the Summa contract of the tenant
+    fn get_summa_address(&self) -> Address;
+
+    /// Returns the ID of the chain the Summa contract of the tenant is deployed on
+    fn get_chain_id(&self) -> u64;
+
+    fn get_shape(&self) -> CircuitShape;
+
+    /// Returns the cryptocurrencies of the balance columns of the tenant, in order
+    fn get_cryptocurrencies(&self) -> &[Cryptocurrency];
+
+    /// Returns the timestamps of the rounds run by the tenant, in increasing order
+    fn get_round_timestamps(&self) -> Vec<u64>;
+
+    /// Proves the current entries of the tenant and submits the commitment of the round to its Summa contract
+    async fn run_round(&self, timestamp: u64) -> Result<TenantRound, Box<dyn Error>>;
+
+    /// Returns the proof of inclusion of a user in a round run by the tenant
+    fn get_proof_of_inclusion(
+        &self,
+        timestamp: u64,
+        username: &str,
+    ) -> Result<KZGProof, Box<dyn Error>>;
+}
+
+type TenantCircuit<const N_CURRENCIES: usize, const N_USERS: usize> =
+    UnivariateGrandSum<N_USERS, N_CURRENCIES, UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>>;
+
+/// A tenant proving the entries of a CSV file with the `UnivariateGrandSum` circuit of the given shape
+///
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of currencies of the tenant.
+/// * `N_USERS`: The number of users of the tenant.
+/// * `S`: The signer of the transactions of the tenant.
+pub struct SummaTenant<const N_CURRENCIES: usize, const N_USERS: usize, S: Signer = LocalWallet> {
+    name: String,
+    signer: SummaSigner<S>,
+    params: ParamsKZG<Bn256>,
+    pk: Arc<ProvingKey<G1Affine>>,
+    cryptocurrencies: Vec<Cryptocurrency>,
+    entries_csv: PathBuf,
+    snapshots_dir: PathBuf,
+    scheme: MultiOpenScheme,
+    rounds: RwLock<BTreeSet<u64>>,
+    // The most recently used snapshots, from the least recently used one
+    snapshot_cache: std::sync::Mutex<VecDeque<(u64, Arc<Snapshot<N_CURRENCIES, N_USERS>>)>>,
+    snapshot_cache_size: usize,
+}
+
+impl<const N_CURRENCIES: usize, const N_USERS: usize, S: Signer + 'static>
+    SummaTenant<N_CURRENCIES, N_USERS, S>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    /// Creates a tenant proving with the Shplonk scheme, which can be changed with `with_scheme`.
+    ///
+    /// The rounds whose snapshots are found in the snapshots directory, e.g., before a restart, are restored.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name of the tenant
+    /// * `signer` - the signer of the Summa contract of the tenant
+    /// * `params` - the KZG parameters the proving key was generated with
+    /// * `pk` - the proving key of the circuit of the tenant
+    /// * `cryptocurrencies` - the cryptocurrencies of the balance columns, in the order of the Summa contract config
+    /// * `entries_csv` - the CSV file the entries of each round are read from
+    /// * `snapshots_dir` - the directory the snapshots of the rounds are saved to, not shared with other tenants
+    pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(
+        name: &str,
+        signer: SummaSigner<S>,
+        params: ParamsKZG<Bn256>,
+        pk: ProvingKey<G1Affine>,
+        cryptocurrencies: Vec<Cryptocurrency>,
+        entries_csv: P,
+        snapshots_dir: Q,
+    ) -> Result<Self, Box<dyn Error>> {
+        if cryptocurrencies.len() != N_CURRENCIES {
+            return Err(format!(
+                "{} cryptocurrencies given to the tenant {}, whose circuit has {}",
+                cryptocurrencies.len(),
+                name,
+                N_CURRENCIES
+            )
+            .into());
+        }
+
+        fs::create_dir_all(&snapshots_dir)?;
+        let mut rounds = BTreeSet::new();
+        for dir_entry in fs::read_dir(&snapshots_dir)? {
+            let file_name = dir_entry?.file_name();
+            if let Some(timestamp) = file_name
+                .to_str()
+                .and_then(|file_name| file_name.strip_prefix("round_"))
+                .and_then(|file_name| file_name.strip_suffix(".bin"))
+                .and_then(|timestamp| timestamp.parse().ok())
+            {
+                rounds.insert(timestamp);
+            }
+        }
+
+        Ok(SummaTenant {
+            name: name.to_string(),
+            signer,
+            params,
+            pk: Arc::new(pk),
+            cryptocurrencies,
+            entries_csv: entries_csv.as_ref().to_path_buf(),
+            snapshots_dir: snapshots_dir.as_ref().to_path_buf(),
+            scheme: MultiOpenScheme::Shplonk,
+            rounds: RwLock::new(rounds),
+            snapshot_cache: std::sync::Mutex::new(VecDeque::new()),
+            snapshot_cache_size: DEFAULT_SNAPSHOT_CACHE_SIZE,
+        })
+    }
+
+    /// Sets the multi-open scheme of the proofs, which has to match the verifier contract of the tenant
+    pub fn with_scheme(mut self, scheme: MultiOpenScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Sets the number of snapshots kept in memory, at least one
+    pub fn with_snapshot_cache_size(mut self, snapshot_cache_size: usize) -> Self {
+        self.snapshot_cache_size = snapshot_cache_size.max(1);
+        self
+    }
+
+    pub fn get_signer(&self) -> &SummaSigner<S> {
+        &self.signer
+    }
+
+    /// Returns the path of the saved snapshot of the round
+    pub fn get_snapshot_path(&self, timestamp: u64) -> PathBuf {
+        self.snapshots_dir.join(format!("round_{}.bin", timestamp))
+    }
+
+    /// Adds a snapshot to the cache as the most recently used one, evicting the least recently used ones beyond its size
+    fn cache_snapshot(&self, timestamp: u64, snapshot: Arc<Snapshot<N_CURRENCIES, N_USERS>>) {
+        let mut snapshot_cache = self.snapshot_cache.lock().unwrap();
+        snapshot_cache.retain(|(cached_timestamp, _)| *cached_timestamp != timestamp);
+        snapshot_cache.push_back((timestamp, snapshot));
+        while snapshot_cache.len() > self.snapshot_cache_size {
+            snapshot_cache.pop_front();
+        }
+    }
+
+    /// Returns the snapshot of the round from the cache, or loads it from its file otherwise
+    fn get_snapshot(
+        &self,
+        timestamp: u64,
+    ) -> Result<Arc<Snapshot<N_CURRENCIES, N_USERS>>, Box<dyn Error>> {
+        if !self.rounds.read().unwrap().contains(&timestamp) {
+            return Err(format!(
+                "The round {} of the tenant {} does not exist",
+                timestamp, self.name
+            )
+            .into());
+        }
+
+        let cached_snapshot = self
+            .snapshot_cache
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(cached_timestamp, _)| *cached_timestamp == timestamp)
+            .map(|(_, snapshot)| Arc::clone(snapshot));
+        let snapshot = match cached_snapshot {
+            Some(snapshot) => snapshot,
+            None => {
+                let (saved_timestamp, snapshot) =
+                    Snapshot::<N_CURRENCIES, N_USERS>::load::<
+                        TenantCircuit<N_CURRENCIES, N_USERS>,
+                        _,
+                    >(self.get_snapshot_path(timestamp), self.params.clone())?;
+                if saved_timestamp != timestamp {
+                    return Err(format!(
+                        "The snapshot of the round {} of the tenant {} is of another round",
+                        timestamp, self.name
+                    )
+                    .into());
+                }
+                Arc::new(snapshot)
+            }
+        };
+        self.cache_snapshot(timestamp, Arc::clone(&snapshot));
+        Ok(snapshot)
+    }
+}
+
+#[async_trait]
+impl<const N_CURRENCIES: usize, const N_USERS: usize, S: Signer + 'static> Tenant
+    for SummaTenant<N_CURRENCIES, N_USERS, S>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [(); N_CURRENCIES + 1]:,
+{
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_summa_address(&self) -> Address {
+        self.signer.get_summa_address()
+    }
+
+    fn get_chain_id(&self) -> u64 {
+        self.signer.get_chain_id()
+    }
+
+    fn get_shape(&self) -> CircuitShape {
+        CircuitShape {
+            n_currencies: N_CURRENCIES,
+            n_users: N_USERS,
+            k: self.params.k(),
+        }
+    }
+
+    fn get_cryptocurrencies(&self) -> &[Cryptocurrency] {
+        &self.cryptocurrencies
+    }
+
+    fn get_round_timestamps(&self) -> Vec<u64> {
+        self.rounds.read().unwrap().iter().copied().collect()
+    }
+
+    async fn run_round(&self, timestamp: u64) -> Result<TenantRound, Box<dyn Error>> {
+        if self.rounds.read().unwrap().contains(&timestamp) {
+            return Err(format!(
+                "The round {} of the tenant {} already exists",
+                timestamp, self.name
+            )
+            .into());
+        }
+
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptocurrencies = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&Path, N_CURRENCIES>(
+            &self.entries_csv,
+            &mut entries,
+            &mut cryptocurrencies,
+        )?;
+        if cryptocurrencies != self.cryptocurrencies {
+            return Err(format!(
+                "The entries of the tenant {} are in {:?}, expected {:?}",
+                self.name, cryptocurrencies, self.cryptocurrencies
+            )
+            .into());
+        }
+
+        // The proving is CPU-bound, so it is moved off the async runtime
+        let params = self.params.clone();
+        let pk = Arc::clone(&self.pk);
+        let scheme = self.scheme;
+        let (zk_snark_proof, advice_polys, verified) = tokio::task::spawn_blocking(move || {
+            // This instance is necessary to verify proof on solidity verifier.
+            let instances = vec![Fp::zero(); 1];
+            let (zk_snark_proof, advice_polys, _) = full_prover(
+                &params,
+                &pk,
+                TenantCircuit::<N_CURRENCIES, N_USERS>::init(entries.clone()),
+                &[instances.clone()],
+                scheme,
+                None,
+            );
+            let verified =
+                full_verifier(&params, pk.get_vk(), &zk_snark_proof, &[instances], scheme);
+            (zk_snark_proof, advice_polys, verified)
+        })
+        .await?;
+        if !verified {
+            return Err(format!(
+                "The SNARK proof of the round {} of the tenant {} does not verify",
+                timestamp, self.name
+            )
+            .into());
+        }
+
+        let mut round = Round::<N_CURRENCIES, N_USERS, S>::new(
+            &self.signer,
+            zk_snark_proof,
+            advice_polys,
+            self.params.clone(),
+            self.pk.get_vk().clone(),
+            &entries,
+            timestamp,
+        )?;
+        round.preflight_check(&self.cryptocurrencies).await?;
+        let total_balances = round.get_total_balances()?;
+
+        // The snapshot is written beforehand and only renamed once the commitment is submitted,
+        // so that the saved snapshots are the ones of the submitted rounds
+        let snapshot_path = self.get_snapshot_path(timestamp);
+        let temporary_path = snapshot_path.with_extension("tmp");
+        round.save(&temporary_path)?;
+        let receipt = match round.dispatch_commitment().await {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                let _ = fs::remove_file(&temporary_path);
+                return Err(e);
+            }
+        };
+        fs::rename(temporary_path, snapshot_path)?;
+
+        self.rounds.write().unwrap().insert(timestamp);
+        self.cache_snapshot(timestamp, Arc::new(round.into_snapshot()));
+
+        Ok(TenantRound {
+            tenant: self.name.clone(),
+            timestamp,
+            total_balances,
+            receipt,
+        })
+    }
+
+    fn get_proof_of_inclusion(
+        &self,
+        timestamp: u64,
+        username: &str,
+    ) -> Result<KZGProof, Box<dyn Error>> {
+        self.get_snapshot(timestamp)?
+            .generate_proof_of_inclusion_for_user(username)
+    }
+}
+
+/// A registered tenant, along with the timestamp of its last round, whose lock serializes the rounds of the tenant
+struct TenantSlot {
+    tenant: Arc<dyn Tenant>,
+    last_timestamp: Mutex<Option<u64>>,
+}
+
+/// The `TenantRegistry` runs the rounds of several tenants, each against its own Summa deployment
+#[derive(Default)]
+pub struct TenantRegistry {
+    tenants: BTreeMap<String, TenantSlot>,
+}
+
+impl TenantRegistry {
+    pub fn new() -> Self {
+        TenantRegistry::default()
+    }
+
+    /// Registers a tenant, whose name and Summa contract must not be the ones of another tenant
+    pub fn register<T: Tenant + 'static>(&mut self, tenant: T) -> Result<(), Box<dyn Error>> {
+        if self.tenants.contains_key(tenant.get_name()) {
+            return Err(format!("The tenant {} is already registered", tenant.get_name()).into());
+        }
+        if let Some(slot) = self.tenants.values().find(|slot| {
+            slot.tenant.get_summa_address() == tenant.get_summa_address()
+                && slot.tenant.get_chain_id() == tenant.get_chain_id()
+        }) {
+            return Err(format!(
+                "The Summa contract {:?} is already used by the tenant {}",
+                tenant.get_summa_address(),
+                slot.tenant.get_name()
+            )
+            .into());
+        }
+
+        // The rounds restored by the tenant, e.g., after a restart, are ordered before its next ones
+        let last_timestamp = tenant.get_round_timestamps().last().copied();
+        self.tenants.insert(
+            tenant.get_name().to_string(),
+            TenantSlot {
+                tenant: Arc::new(tenant),
+                last_timestamp: Mutex::new(last_timestamp),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn get_tenant(&self, name: &str) -> Option<Arc<dyn Tenant>> {
+        self.tenants.get(name).map(|slot| Arc::clone(&slot.tenant))
+    }
+
+    /// Returns the names of the registered tenants, in alphabetical order
+    pub fn get_tenant_names(&self) -> Vec<&str> {
+        self.tenants.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// Runs a round of a tenant.
+    ///
+    /// The round waits for the running round of the same tenant, if any, and its timestamp must be greater than
+    /// the one of the last round of the tenant. The rounds of the other tenants are not affected.
+    pub async fn run_round(
+        &self,
+        name: &str,
+        timestamp: u64,
+    ) -> Result<TenantRound, Box<dyn Error>> {
+        let slot = self
+            .tenants
+            .get(name)
+            .ok_or_else(|| format!("Unknown tenant {}", name))?;
+
+        let mut last_timestamp = slot.last_timestamp.lock().await;
+        if let Some(last_timestamp) = *last_timestamp {
+            if timestamp <= last_timestamp {
+                return Err(format!(
+                    "The round {} of the tenant {} is not after its last round {}",
+                    timestamp, name, last_timestamp
+                )
+                .into());
+            }
+        }
+        let round = slot.tenant.run_round(timestamp).await?;
+        *last_timestamp = Some(timestamp);
+        Ok(round)
+    }
+
+    /// Runs a round of every tenant concurrently, returning the outcome of each tenant by name.
+    ///
+    /// The failure of the round of a tenant does not stop the rounds of the other tenants.
+    pub async fn run_rounds(
+        &self,
+        timestamp: u64,
+    ) -> Vec<(String, Result<TenantRound, Box<dyn Error>>)> {
+        join_all(
+            self.tenants
+                .keys()
+                .map(|name| async move { (name.clone(), self.run_round(name, timestamp).await) }),
+        )
+        .await
+    }
+}
@@ -0,0 +1,230 @@
+use ethers::types::U256;
+use halo2_proofs::{
+    arithmetic::{CurveAffine, Field},
+    halo2curves::{
+        bn256::{Bn256, Fq, Fq2, Fr as Fp, G1Affine, G2Affine},
+        group::{prime::PrimeCurveAffine, Curve},
+        pairing::Engine,
+    },
+    plonk::VerifyingKey,
+    poly::kzg::commitment::ParamsKZG,
+    transcript::TranscriptRead,
+};
+use halo2_solidity_verifier::Keccak256Transcript;
+use std::error::Error;
+
+use super::round::KZGProof;
+use summa_solvency::utils::{amortized_kzg::verify_kzg_proof, fp_to_big_uint};
+
+/// Decodes a sequence of G1 points serialized as their 32-byte big-endian coordinates,
+/// as in the commitment bytes stored by `Summa.submitCommitment` and in the proof calldata of a `KZGProof`
+///
+/// # Arguments
+///
+/// * `bytes` - the serialized points
+///
+/// # Returns
+///
+/// * `Vec<G1Affine>` - the decoded points
+pub fn decode_points(bytes: &[u8]) -> Result<Vec<G1Affine>, Box<dyn Error>> {
+    if bytes.is_empty() || bytes.len() % 64 != 0 {
+        return Err("The serialized points should be a non-empty multiple of 64 bytes".into());
+    }
+
+    let mut transcript = Keccak256Transcript::new(bytes);
+    let mut points = Vec::with_capacity(bytes.len() / 64);
+    for _ in 0..bytes.len() / 64 {
+        let point: G1Affine = transcript.read_point()?;
+        points.push(point);
+    }
+    Ok(points)
+}
+
+/// Converts a field element encoded as a `U256` in the inputs of the Solidity verifiers back to a field element
+pub fn u256_to_fp(value: &U256) -> Result<Fp, Box<dyn Error>> {
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    Option::from(Fp::from_bytes(&bytes)).ok_or_else(|| "Value out of the scalar field".into())
+}
+
+/// Decodes a username encoded as a field element in the inputs of an inclusion proof
+pub fn decode_username(value: &U256) -> Result<String, Box<dyn Error>> {
+    let username = fp_to_big_uint(u256_to_fp(value)?);
+    Ok(String::from_utf8_lossy(&username.to_bytes_be()).into_owned())
+}
+
+/// Offset of the `omega` word in the code of the verifying key contract, as laid out by `halo2_solidity_verifier`
+const VERIFYING_KEY_OMEGA_OFFSET: usize = 0x60;
+
+/// Offset of the `neg_s_g2` words in the code of the verifying key contract, also read by the `InclusionVerifier`
+const VERIFYING_KEY_NEG_S_G2_OFFSET: usize = 0x220;
+
+fn read_word(code: &[u8], offset: usize) -> Result<[u8; 32], Box<dyn Error>> {
+    let mut word: [u8; 32] = code
+        .get(offset..offset + 32)
+        .ok_or("The verifying key code is too short")?
+        .try_into()?;
+    // The words are big-endian, while the fields are read from little-endian bytes
+    word.reverse();
+    Ok(word)
+}
+
+fn read_fq(code: &[u8], offset: usize) -> Result<Fq, Box<dyn Error>> {
+    Option::from(Fq::from_bytes(&read_word(code, offset)?))
+        .ok_or_else(|| "Value out of the base field".into())
+}
+
+/// The parameters of an inclusion proof check read from the verifying key contract deployed along with the Summa contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployedVerifyingKey {
+    omega: Fp,
+    s_g2: G2Affine,
+}
+
+impl DeployedVerifyingKey {
+    /// Reads the generator `omega` of the evaluation domain and the `[s]_2` of the KZG parameters
+    /// from the code of the verifying key contract, as returned by `SummaReader::get_verifying_key_code`
+    pub fn from_code(code: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let omega = Option::from(Fp::from_bytes(&read_word(
+            code,
+            VERIFYING_KEY_OMEGA_OFFSET,
+        )?))
+        .ok_or("Value out of the scalar field")?;
+
+        // The G2 coordinates are stored as `[x.c1, x.c0, y.c1, y.c0]`
+        let neg_s_g2 = G2Affine {
+            x: Fq2 {
+                c0: read_fq(code, VERIFYING_KEY_NEG_S_G2_OFFSET + 0x20)?,
+                c1: read_fq(code, VERIFYING_KEY_NEG_S_G2_OFFSET)?,
+            },
+            y: Fq2 {
+                c0: read_fq(code, VERIFYING_KEY_NEG_S_G2_OFFSET + 0x60)?,
+                c1: read_fq(code, VERIFYING_KEY_NEG_S_G2_OFFSET + 0x40)?,
+            },
+        };
+        if !bool::from(neg_s_g2.is_on_curve()) {
+            return Err("The neg_s_g2 of the verifying key is not a point of G2".into());
+        }
+
+        Ok(DeployedVerifyingKey {
+            omega,
+            s_g2: -neg_s_g2,
+        })
+    }
+
+    /// Returns the `[ω^i]_2 - [s]_2` point of the row `i` of the user, as the coordinates `[x.c1, x.c0, y.c1, y.c0]`
+    /// given to `Summa.verifyInclusionProof`
+    pub fn challenge_s_g2(&self, user_index: usize) -> Vec<U256> {
+        let challenge = self.omega.pow_vartime([user_index as u64]);
+        let challenge_s_g2 = (G2Affine::generator() * challenge - self.s_g2).to_affine();
+        vec![
+            U256::from_little_endian(challenge_s_g2.x.c1.to_bytes().as_slice()),
+            U256::from_little_endian(challenge_s_g2.x.c0.to_bytes().as_slice()),
+            U256::from_little_endian(challenge_s_g2.y.c1.to_bytes().as_slice()),
+            U256::from_little_endian(challenge_s_g2.y.c0.to_bytes().as_slice()),
+        ]
+    }
+}
+
+/// Checks the opening proofs of all the committed columns at the challenge, i.e.,
+/// `e(C - [v]_1, [1]_2) = e(π, [s]_2 - [challenge]_2)` for each column
+fn verify_openings(
+    commitment: &[u8],
+    inclusion_proof: &KZGProof,
+    challenge: Fp,
+    s_g2: G2Affine,
+) -> Result<bool, Box<dyn Error>> {
+    let commitments = decode_points(commitment)?;
+    let opening_proofs = decode_points(inclusion_proof.get_proof())?;
+    let values = inclusion_proof.get_input_values();
+    if opening_proofs.len() != commitments.len() || values.len() != commitments.len() {
+        return Err("The inclusion proof does not match the number of committed columns".into());
+    }
+
+    let s_minus_challenge_g2 = (s_g2 - G2Affine::generator() * challenge).to_affine();
+    for ((commitment, opening_proof), value) in commitments.iter().zip(opening_proofs).zip(values) {
+        let c_minus_v =
+            (commitment.to_curve() - G1Affine::generator() * u256_to_fp(value)?).to_affine();
+        let left_side = Bn256::pairing(&c_minus_v, &G2Affine::generator());
+        let right_side = Bn256::pairing(&opening_proof, &s_minus_challenge_g2);
+        if left_side != right_side {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn inclusion_challenge(omega: Fp, inclusion_proof: &KZGProof) -> Result<Fp, Box<dyn Error>> {
+    let user_index = inclusion_proof
+        .get_user_index()
+        .ok_or("The inclusion proof does not record the user index")?;
+    Ok(omega.pow_vartime([user_index as u64]))
+}
+
+/// Verifies an inclusion proof against the commitment bytes of its round and the verifying key contract of the
+/// Summa contract, e.g., for the users who only have access to the chain.
+///
+/// The challenge carried by the proof is not trusted: it is recomputed as `ω^i` from the `omega` of the verifying key
+/// and the user index `i` recorded in the proof, and the openings are checked against the `[s]_2` of the verifying key.
+///
+/// # Arguments
+///
+/// * `verifying_key` - the verifying key contract the Summa contract verifies the proofs against
+/// * `commitment` - the bytes returned by `Summa.commitments(timestamp)`
+/// * `inclusion_proof` - the inclusion proof of the user
+///
+/// # Returns
+///
+/// * `bool` - whether the opening proofs of all the columns are valid at the row of the user
+pub fn verify_inclusion_proof_with_verifying_key(
+    verifying_key: &DeployedVerifyingKey,
+    commitment: &[u8],
+    inclusion_proof: &KZGProof,
+) -> Result<bool, Box<dyn Error>> {
+    let challenge = inclusion_challenge(verifying_key.omega, inclusion_proof)?;
+    verify_openings(commitment, inclusion_proof, challenge, verifying_key.s_g2)
+}
+
+/// Verifies an inclusion proof against the commitment bytes of its round, entirely off-chain.
+///
+/// The challenge is not taken from the proof:
+/// it is recomputed as `ω^i` from the domain of the verifying key and the user index `i` recorded in the proof,
+/// and each column opening is checked with `verify_kzg_proof` against the `[s]_2` of the KZG parameters.
+///
+/// # Arguments
+///
+/// * `params` - the KZG parameters the round was proven with
+/// * `vk` - the verifying key of the circuit
+/// * `commitment` - the bytes returned by `Summa.commitments(timestamp)`
+/// * `inclusion_proof` - the inclusion proof of the user
+///
+/// # Returns
+///
+/// * `bool` - whether the opening proofs of all the columns are valid at the row of the user
+pub fn verify_inclusion_proof_offline(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    commitment: &[u8],
+    inclusion_proof: &KZGProof,
+) -> Result<bool, Box<dyn Error>> {
+    let commitments = decode_points(commitment)?;
+    let opening_proofs = decode_points(inclusion_proof.get_proof())?;
+    let values = inclusion_proof.get_input_values();
+    if opening_proofs.len() != commitments.len() || values.len() != commitments.len() {
+        return Err("The inclusion proof does not match the number of committed columns".into());
+    }
+    let challenge = inclusion_challenge(vk.get_domain().get_omega(), inclusion_proof)?;
+
+    for ((commitment, opening_proof), value) in commitments.iter().zip(opening_proofs).zip(values) {
+        if !verify_kzg_proof(
+            params,
+            commitment.to_curve(),
+            opening_proof.to_curve(),
+            &challenge,
+            &u256_to_fp(value)?,
+        ) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
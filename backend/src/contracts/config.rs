@@ -0,0 +1,135 @@
+use ethers::{
+    providers::Middleware,
+    types::{Address, H256, U256},
+    utils::keccak256,
+};
+use std::error::Error;
+
+// Storage layout of the Summa contract: the `_owner` of `Ownable` takes slot 0,
+// followed by the members of the `config` struct.
+const CRYPTOCURRENCY_NAMES_SLOT: u64 = 1;
+const CRYPTOCURRENCY_CHAINS_SLOT: u64 = 2;
+const BALANCE_BYTE_RANGE_SLOT: u64 = 3;
+
+/// The configuration of a deployed Summa contract.
+///
+/// The public `config()` getter generated by Solidity omits the dynamic arrays of the struct,
+/// so the configuration is read from the contract storage instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaConfig {
+    pub cryptocurrency_names: Vec<String>,
+    pub cryptocurrency_chains: Vec<String>,
+    pub balance_byte_range: u8,
+}
+
+async fn read_slot<M: Middleware>(
+    client: &M,
+    address: Address,
+    slot: U256,
+) -> Result<U256, Box<dyn Error>>
+where
+    M::Error: 'static,
+{
+    let mut key = [0u8; 32];
+    slot.to_big_endian(&mut key);
+    let word = client
+        .get_storage_at(address, H256::from(key), None)
+        .await?;
+    Ok(U256::from_big_endian(word.as_bytes()))
+}
+
+/// Returns the slot of the first element of the dynamic data stored at the given slot
+fn data_slot(slot: U256) -> U256 {
+    let mut key = [0u8; 32];
+    slot.to_big_endian(&mut key);
+    U256::from_big_endian(&keccak256(key))
+}
+
+async fn read_string<M: Middleware>(
+    client: &M,
+    address: Address,
+    slot: U256,
+) -> Result<String, Box<dyn Error>>
+where
+    M::Error: 'static,
+{
+    let word = read_slot(client, address, slot).await?;
+    let mut word_bytes = [0u8; 32];
+    word.to_big_endian(&mut word_bytes);
+
+    // Strings shorter than 32 bytes are stored in place along with twice their length in the lowest-order byte,
+    // longer strings store twice their length plus one and their data from the keccak256 hash of the slot
+    let bytes = if word_bytes[31] & 1 == 0 {
+        let length = (word_bytes[31] / 2) as usize;
+        word_bytes[..length].to_vec()
+    } else {
+        let length = ((word - 1) / 2).as_usize();
+        let start = data_slot(slot);
+        let mut bytes = Vec::with_capacity(length + 31);
+        for i in 0..(length + 31) / 32 {
+            let mut chunk = [0u8; 32];
+            read_slot(client, address, start + i)
+                .await?
+                .to_big_endian(&mut chunk);
+            bytes.extend_from_slice(&chunk);
+        }
+        bytes.truncate(length);
+        bytes
+    };
+
+    Ok(String::from_utf8(bytes)?)
+}
+
+async fn read_string_array<M: Middleware>(
+    client: &M,
+    address: Address,
+    slot: U256,
+) -> Result<Vec<String>, Box<dyn Error>>
+where
+    M::Error: 'static,
+{
+    let length = read_slot(client, address, slot).await?.as_usize();
+    let start = data_slot(slot);
+
+    let mut strings = Vec::with_capacity(length);
+    for i in 0..length {
+        strings.push(read_string(client, address, start + i).await?);
+    }
+    Ok(strings)
+}
+
+/// Reads the configuration of the Summa contract at the given address from its storage
+///
+/// # Arguments
+///
+/// * `client` - the client connected to the chain of the contract
+/// * `address` - the address of the Summa contract
+///
+/// # Returns
+///
+/// * `SummaConfig` - the cryptocurrency names and chains, in the order of the balance columns, and the balance byte range
+pub async fn read_summa_config<M: Middleware>(
+    client: &M,
+    address: Address,
+) -> Result<SummaConfig, Box<dyn Error>>
+where
+    M::Error: 'static,
+{
+    Ok(SummaConfig {
+        cryptocurrency_names: read_string_array(
+            client,
+            address,
+            U256::from(CRYPTOCURRENCY_NAMES_SLOT),
+        )
+        .await?,
+        cryptocurrency_chains: read_string_array(
+            client,
+            address,
+            U256::from(CRYPTOCURRENCY_CHAINS_SLOT),
+        )
+        .await?,
+        balance_byte_range: read_slot(client, address, U256::from(BALANCE_BYTE_RANGE_SLOT))
+            .await?
+            .low_u32() as u8,
+    })
+}
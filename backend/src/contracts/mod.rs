@@ -1,2 +1,6 @@
+pub mod config;
 pub mod generated;
+pub mod reader;
+pub mod remote_signer;
 pub mod signer;
+pub mod transaction;
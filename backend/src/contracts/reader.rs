@@ -0,0 +1,266 @@
+use ethers::{
+    contract::{ContractError, EthLogDecode, Event, LogMeta},
+    providers::{Http, Middleware, Provider},
+    types::{Address, Bytes, U256},
+    utils::keccak256,
+};
+use std::{error::Error, sync::Arc};
+
+use super::{
+    config::{read_summa_config, SummaConfig},
+    generated::summa_contract::{
+        AddressOwnershipProof, AddressOwnershipProofSubmittedFilter,
+        LiabilitiesCommitmentSubmittedFilter, Summa,
+    },
+    signer::{connect_provider, AddressInput},
+};
+use crate::apis::round::KZGProof;
+
+/// The number of blocks scanned per `eth_getLogs` request by default
+pub const DEFAULT_BATCH_SIZE: u64 = 1000;
+
+/// The `SummaReader` reads the state of a Summa contract for the users and the auditors.
+///
+/// Unlike the `SummaSigner`, it only needs the endpoint of a node and the address of the contract, no key.
+#[derive(Debug, Clone)]
+pub struct SummaReader {
+    chain_id: u64,
+    summa_contract: Summa<Provider<Http>>,
+    start_block: u64,
+    batch_size: u64,
+}
+
+impl SummaReader {
+    /// Creates a new SummaReader instance
+    /// # Arguments
+    /// * `url` -  The endpoint for connecting to the node
+    /// * `address` - The address of the Summa contract
+    pub async fn new(url: &str, address_input: AddressInput) -> Result<Self, Box<dyn Error>> {
+        let (provider, chain_id) = connect_provider(url).await?;
+        let address = address_input.resolve(chain_id)?;
+
+        Ok(Self::with_provider(provider, chain_id, address))
+    }
+
+    /// Creates a new SummaReader instance sharing the provider of another client, e.g., the `SummaSigner`
+    pub(crate) fn with_provider(
+        provider: Arc<Provider<Http>>,
+        chain_id: u64,
+        address: Address,
+    ) -> Self {
+        Self {
+            chain_id,
+            summa_contract: Summa::new(address, provider),
+            start_block: 0,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Sets the block the logs of the Summa contract are queried from, e.g., its deployment block
+    pub fn with_start_block(mut self, start_block: u64) -> Self {
+        self.start_block = start_block;
+        self
+    }
+
+    /// Sets the number of blocks queried per `eth_getLogs` request
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn get_summa_address(&self) -> Address {
+        self.summa_contract.address()
+    }
+
+    pub fn get_chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn get_start_block(&self) -> u64 {
+        self.start_block
+    }
+
+    /// Returns the bindings of the Summa contract, for the queries not covered by the reader
+    pub fn get_contract(&self) -> &Summa<Provider<Http>> {
+        &self.summa_contract
+    }
+
+    /// Returns the owner of the Summa contract, i.e., the account of the exchange
+    pub async fn get_owner(&self) -> Result<Address, Box<dyn Error>> {
+        Ok(self.summa_contract.owner().call().await?)
+    }
+
+    /// Returns the address of the verifying key contract the proofs are verified against
+    pub async fn get_verifying_key_address(&self) -> Result<Address, Box<dyn Error>> {
+        Ok(self.summa_contract.verifying_key().call().await?)
+    }
+
+    /// Returns the deployed code of the verifying key contract the proofs are verified against
+    pub async fn get_verifying_key_code(&self) -> Result<Bytes, Box<dyn Error>> {
+        let verifying_key_address = self.get_verifying_key_address().await?;
+        Ok(self
+            .summa_contract
+            .client()
+            .get_code(verifying_key_address, None)
+            .await?)
+    }
+
+    /// Returns the cryptocurrencies and the balance byte range the Summa contract is configured with
+    pub async fn get_config(&self) -> Result<SummaConfig, Box<dyn Error>> {
+        read_summa_config(
+            self.summa_contract.client().as_ref(),
+            self.get_summa_address(),
+        )
+        .await
+    }
+
+    /// Returns the proof of ownership of the address with the given hash, or `None` if the address is not verified
+    ///
+    /// # Arguments
+    ///
+    /// * `address_hash` - the keccak256 hash of the address string, as computed by [`hash_address`]
+    pub async fn get_address_ownership_proof(
+        &self,
+        address_hash: [u8; 32],
+    ) -> Result<Option<AddressOwnershipProof>, Box<dyn Error>> {
+        match self
+            .summa_contract
+            .get_address_ownership_proof(address_hash)
+            .call()
+            .await
+        {
+            Ok(proof) => Ok(Some(proof)),
+            // The contract reverts with "Address not verified" for the unknown addresses
+            Err(ContractError::Revert(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns all the proofs of address ownership submitted to the Summa contract since the start block, in order
+    pub async fn get_address_ownership_proofs(
+        &self,
+    ) -> Result<Vec<AddressOwnershipProof>, Box<dyn Error>> {
+        Ok(self
+            .get_address_ownership_events_with_meta()
+            .await?
+            .into_iter()
+            .flat_map(|(log, _)| log.address_ownership_proofs)
+            .collect())
+    }
+
+    /// Returns the events of all the batches of proofs of address ownership submitted since the start block, in order,
+    /// along with the block and the transaction of each event
+    pub async fn get_address_ownership_events_with_meta(
+        &self,
+    ) -> Result<Vec<(AddressOwnershipProofSubmittedFilter, LogMeta)>, Box<dyn Error>> {
+        self.query_logs(|| {
+            self.summa_contract
+                .address_ownership_proof_submitted_filter()
+        })
+        .await
+    }
+
+    /// Returns the commitment of the round with the given timestamp, i.e., the polynomial commitments of the SNARK proof,
+    /// or `None` if no commitment was submitted for the round
+    pub async fn get_commitment(&self, timestamp: u64) -> Result<Option<Bytes>, Box<dyn Error>> {
+        let commitment = self
+            .summa_contract
+            .commitments(U256::from(timestamp))
+            .call()
+            .await?;
+        Ok(if commitment.is_empty() {
+            None
+        } else {
+            Some(commitment)
+        })
+    }
+
+    /// Returns the events of all the commitments submitted to the Summa contract since the start block, in order
+    pub async fn get_commitment_events(
+        &self,
+    ) -> Result<Vec<LiabilitiesCommitmentSubmittedFilter>, Box<dyn Error>> {
+        Ok(self
+            .get_commitment_events_with_meta()
+            .await?
+            .into_iter()
+            .map(|(log, _)| log)
+            .collect())
+    }
+
+    /// Returns the events of all the commitments submitted since the start block, in order,
+    /// along with the block and the transaction of each event
+    pub async fn get_commitment_events_with_meta(
+        &self,
+    ) -> Result<Vec<(LiabilitiesCommitmentSubmittedFilter, LogMeta)>, Box<dyn Error>> {
+        self.query_logs(|| {
+            self.summa_contract
+                .liabilities_commitment_submitted_filter()
+        })
+        .await
+    }
+
+    /// Queries the logs of an event from the start block up to the latest block, in ranges of `batch_size` blocks
+    async fn query_logs<D: EthLogDecode>(
+        &self,
+        event: impl Fn() -> Event<Arc<Provider<Http>>, Provider<Http>, D>,
+    ) -> Result<Vec<(D, LogMeta)>, Box<dyn Error>> {
+        let latest_block = self
+            .summa_contract
+            .client()
+            .get_block_number()
+            .await?
+            .as_u64();
+
+        let mut logs = Vec::new();
+        let mut from_block = self.start_block;
+        while from_block <= latest_block {
+            let to_block = latest_block.min(from_block + self.batch_size - 1);
+            logs.extend(
+                event()
+                    .from_block(from_block)
+                    .to_block(to_block)
+                    .query_with_meta()
+                    .await?,
+            );
+            from_block = to_block + 1;
+        }
+        Ok(logs)
+    }
+
+    /// Verifies the inclusion proof of a user against the commitment of its round with `Summa.verifyInclusionProof`
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - the timestamp of the round the proof belongs to
+    /// * `inclusion_proof` - the inclusion proof of the user
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - whether the proof is valid
+    pub async fn verify_inclusion_proof(
+        &self,
+        timestamp: u64,
+        inclusion_proof: &KZGProof,
+    ) -> Result<bool, Box<dyn Error>> {
+        let challenge = inclusion_proof
+            .get_challenge()
+            .clone()
+            .ok_or("The proof is not an inclusion proof")?;
+
+        Ok(self
+            .summa_contract
+            .verify_inclusion_proof(
+                U256::from(timestamp),
+                inclusion_proof.get_proof().clone(),
+                challenge,
+                inclusion_proof.get_input_values().clone(),
+            )
+            .call()
+            .await?)
+    }
+}
+
+/// Returns the hash of an address the Summa contract indexes the proofs of address ownership by
+pub fn hash_address(address: &str) -> [u8; 32] {
+    keccak256(address.as_bytes())
+}
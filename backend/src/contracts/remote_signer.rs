@@ -0,0 +1,177 @@
+use async_trait::async_trait;
+use ethers::{
+    signers::Signer,
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712},
+        Address, Bytes, Signature,
+    },
+    utils::rlp::Rlp,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{fmt, str::FromStr};
+
+/// An error of the [`RemoteSigner`]
+#[derive(Debug)]
+pub enum RemoteSignerError {
+    /// The request to the remote signer failed
+    Http(reqwest::Error),
+    /// The remote signer returned a JSON-RPC error
+    Rpc { code: i64, message: String },
+    /// The remote signer returned an unexpected or invalid result
+    InvalidResponse(String),
+    /// The operation is not supported by the remote signer client
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for RemoteSignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemoteSignerError::Http(e) => write!(f, "Remote signer request failed: {}", e),
+            RemoteSignerError::Rpc { code, message } => {
+                write!(f, "Remote signer error {}: {}", code, message)
+            }
+            RemoteSignerError::InvalidResponse(message) => {
+                write!(f, "Invalid remote signer response: {}", message)
+            }
+            RemoteSignerError::Unsupported(operation) => {
+                write!(f, "{} is not supported by the remote signer", operation)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RemoteSignerError {}
+
+impl From<reqwest::Error> for RemoteSignerError {
+    fn from(e: reqwest::Error) -> Self {
+        RemoteSignerError::Http(e)
+    }
+}
+
+/// Client of a remote signer holding the key of the exchange, such as Web3Signer or Clef.
+///
+/// The signer is reached over HTTP with the JSON-RPC methods `eth_sign` and `eth_signTransaction`,
+/// so that the key never leaves the remote signer. The signatures returned by the remote signer are checked
+/// to be made by the expected account over the requested payload before being used.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: String,
+    address: Address,
+    chain_id: u64,
+}
+
+impl RemoteSigner {
+    /// Creates a new RemoteSigner instance
+    /// # Arguments
+    /// * `url` - The JSON-RPC endpoint of the remote signer
+    /// * `address` - The address of the account of the exchange on the remote signer
+    pub fn new(url: &str, address: Address) -> Self {
+        RemoteSigner {
+            client: reqwest::Client::new(),
+            url: url.to_owned(),
+            address,
+            chain_id: 1,
+        }
+    }
+
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, RemoteSignerError> {
+        let response: Value = self
+            .client
+            .post(&self.url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(RemoteSignerError::Rpc {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_owned(),
+            });
+        }
+        serde_json::from_value(response["result"].clone())
+            .map_err(|e| RemoteSignerError::InvalidResponse(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    type Error = RemoteSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        let message = message.as_ref().to_vec();
+        let result: String = self
+            .request(
+                "eth_sign",
+                json!([self.address, Bytes::from(message.clone())]),
+            )
+            .await?;
+
+        let signature = Signature::from_str(&result)
+            .map_err(|e| RemoteSignerError::InvalidResponse(e.to_string()))?;
+        signature
+            .verify(message, self.address)
+            .map_err(|e| RemoteSignerError::InvalidResponse(e.to_string()))?;
+        Ok(signature)
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let mut tx = tx.clone();
+        tx.set_from(self.address);
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+
+        let signed_tx: Bytes = self.request("eth_signTransaction", json!([tx])).await?;
+        let (decoded_tx, signature) = TypedTransaction::decode_signed(&Rlp::new(&signed_tx))
+            .map_err(|e| RemoteSignerError::InvalidResponse(e.to_string()))?;
+
+        // The remote signer must have signed the requested transaction with the expected account
+        let sighash = tx.sighash();
+        if decoded_tx.sighash() != sighash {
+            return Err(RemoteSignerError::InvalidResponse(
+                "The signed transaction differs from the requested one".to_owned(),
+            ));
+        }
+        signature
+            .verify(sighash, self.address)
+            .map_err(|e| RemoteSignerError::InvalidResponse(e.to_string()))?;
+        Ok(signature)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        _payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        Err(RemoteSignerError::Unsupported("Signing typed data"))
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
@@ -1,14 +1,21 @@
 use ethers::{
     prelude::SignerMiddleware,
     providers::{Http, Middleware, Provider},
-    signers::{LocalWallet, Signer},
+    signers::{coins_bip39::English, LocalWallet, MnemonicBuilder, Signer},
     types::{Address, Bytes, U256},
 };
 use serde_json::Value;
 use std::{error::Error, fs::File, io::BufReader, path::Path, str::FromStr, sync::Arc};
 use tokio::sync::Mutex;
 
-use super::generated::summa_contract::AddressOwnershipProof;
+use super::{
+    generated::summa_contract::AddressOwnershipProof,
+    reader::SummaReader,
+    transaction::{
+        send_transaction, AddressOwnershipReceipt, CommitmentReceipt, SubmissionReceipt,
+        TransactionConfig,
+    },
+};
 use crate::contracts::generated::summa_contract::Summa;
 
 pub enum AddressInput {
@@ -16,10 +23,41 @@ pub enum AddressInput {
     Path(String),
 }
 
+impl AddressInput {
+    /// Resolves the address of the Summa contract, looking it up in the deployments file for the given chain if needed
+    pub fn resolve(self, chain_id: u64) -> Result<Address, Box<dyn Error>> {
+        match self {
+            AddressInput::Address(address) => Ok(address),
+            AddressInput::Path(path) => SummaSigner::get_deployment_address(path, chain_id),
+        }
+    }
+}
+
+/// Connects to the node at the given endpoint, returning the provider along with the ID of its chain
+pub(crate) async fn connect_provider(
+    url: &str,
+) -> Result<(Arc<Provider<Http>>, u64), Box<dyn Error>> {
+    let provider = Arc::new(Provider::try_from(url)?);
+    let chain_id = provider.get_chainid().await?.as_u64();
+    Ok((provider, chain_id))
+}
+
+/// The `SummaSigner` submits the transactions of the exchange to the Summa contract.
+///
+/// It is generic over the ethers `Signer` holding the key of the exchange, a `LocalWallet` by default.
+/// Besides a raw private key, the wallet can be loaded from an encrypted JSON keystore or derived from a mnemonic,
+/// and the signing can be delegated to a remote signer with [`RemoteSigner`](super::remote_signer::RemoteSigner).
+///
+/// The transactions are sent according to its [`TransactionConfig`], which sets the fee caps, the confirmations
+/// to wait for and the replacement of the transactions that are not mined in time.
+///
+/// The state of the contract is read with the [`SummaReader`] it holds, sharing its provider.
 #[derive(Debug)]
-pub struct SummaSigner {
+pub struct SummaSigner<S: Signer = LocalWallet> {
     nonce_lock: Mutex<()>, // To prevent running `submit` methods concurrently
-    summa_contract: Summa<SignerMiddleware<Arc<Provider<Http>>, LocalWallet>>,
+    summa_contract: Summa<SignerMiddleware<Arc<Provider<Http>>, S>>,
+    summa_reader: SummaReader,
+    transaction_config: TransactionConfig,
 }
 
 impl SummaSigner {
@@ -33,34 +71,50 @@ impl SummaSigner {
         url: &str,
         address_input: AddressInput,
     ) -> Result<Self, Box<dyn Error>> {
-        let wallet: LocalWallet = LocalWallet::from_str(signer_key).unwrap();
+        let wallet: LocalWallet = LocalWallet::from_str(signer_key)?;
 
-        let provider = Arc::new(Provider::try_from(url)?);
-        let chain_id = provider.get_chainid().await?.as_u64();
-        let client = Arc::new(SignerMiddleware::new(
-            provider,
-            wallet.with_chain_id(chain_id),
-        ));
+        Self::with_signer(wallet, url, address_input).await
+    }
 
-        let address = match address_input {
-            AddressInput::Address(address) => address,
-            AddressInput::Path(path) => {
-                let address = Self::get_deployment_address(path, chain_id).unwrap();
-                address
-            }
-        };
+    /// Creates a new SummaSigner instance with the wallet decrypted from an encrypted JSON keystore
+    /// # Arguments
+    /// * `keystore_path` - The path to the keystore file
+    /// * `password` - The password the keystore is encrypted with
+    /// * `url` -  The endpoint for connecting to the node
+    /// * `address` - The address of the Summa contract
+    pub async fn from_keystore<P: AsRef<Path>>(
+        keystore_path: P,
+        password: &str,
+        url: &str,
+        address_input: AddressInput,
+    ) -> Result<Self, Box<dyn Error>> {
+        let wallet = LocalWallet::decrypt_keystore(keystore_path, password)?;
 
-        Ok(Self {
-            nonce_lock: Mutex::new(()),
-            summa_contract: Summa::new(address, client),
-        })
+        Self::with_signer(wallet, url, address_input).await
     }
 
-    pub fn get_summa_address(&self) -> Address {
-        self.summa_contract.address()
+    /// Creates a new SummaSigner instance with the wallet derived from a BIP-39 mnemonic
+    /// # Arguments
+    /// * `phrase` - The mnemonic phrase, in English
+    /// * `derivation_path` - The BIP-32 derivation path of the key, e.g., `m/44'/60'/0'/0/0`
+    /// * `url` -  The endpoint for connecting to the node
+    /// * `address` - The address of the Summa contract
+    pub async fn from_mnemonic(
+        phrase: &str,
+        derivation_path: &str,
+        url: &str,
+        address_input: AddressInput,
+    ) -> Result<Self, Box<dyn Error>> {
+        let wallet = MnemonicBuilder::<English>::default()
+            .phrase(phrase)
+            .derivation_path(derivation_path)?
+            .build()?;
+
+        Self::with_signer(wallet, url, address_input).await
     }
 
-    fn get_deployment_address<P: AsRef<Path>>(
+    /// Returns the address of the Summa contract deployed on the given chain, as recorded in the deployments file
+    pub fn get_deployment_address<P: AsRef<Path>>(
         path: P,
         chain_id: u64,
     ) -> Result<Address, Box<dyn Error>> {
@@ -83,25 +137,99 @@ impl SummaSigner {
 
         Ok(address)
     }
+}
+
+impl<S: Signer + 'static> SummaSigner<S> {
+    /// Creates a new SummaSigner instance with the given signer
+    /// # Arguments
+    /// * `signer` - The signer that will interact with the chain on behalf of the exchange
+    /// * `url` -  The endpoint for connecting to the node
+    /// * `address` - The address of the Summa contract
+    pub async fn with_signer(
+        signer: S,
+        url: &str,
+        address_input: AddressInput,
+    ) -> Result<Self, Box<dyn Error>> {
+        let (provider, chain_id) = connect_provider(url).await?;
+        let address = address_input.resolve(chain_id)?;
+        let summa_reader = SummaReader::with_provider(provider.clone(), chain_id, address);
+
+        let client = Arc::new(SignerMiddleware::new(
+            provider,
+            signer.with_chain_id(chain_id),
+        ));
+
+        Ok(Self {
+            nonce_lock: Mutex::new(()),
+            summa_contract: Summa::new(address, client),
+            summa_reader,
+            transaction_config: TransactionConfig::default(),
+        })
+    }
+
+    /// Sets the config of the transactions sent by the signer
+    pub fn with_transaction_config(mut self, transaction_config: TransactionConfig) -> Self {
+        self.transaction_config = transaction_config;
+        self
+    }
+
+    pub fn get_transaction_config(&self) -> &TransactionConfig {
+        &self.transaction_config
+    }
+
+    pub fn get_summa_address(&self) -> Address {
+        self.summa_contract.address()
+    }
+
+    /// Returns the reader of the Summa contract, for the state of the contract
+    pub fn get_reader(&self) -> &SummaReader {
+        &self.summa_reader
+    }
+
+    /// Returns the ID of the chain of the Summa contract
+    pub fn get_chain_id(&self) -> u64 {
+        self.summa_contract.client().signer().chain_id()
+    }
+
+    /// Returns the address of the account signing the transactions
+    pub fn get_signer_address(&self) -> Address {
+        self.summa_contract.client().address()
+    }
+
+    /// Returns the gas estimated for submitting the given proofs of address ownership in a single transaction
+    pub async fn estimate_proof_of_address_ownership_gas(
+        &self,
+        address_ownership_proofs: Vec<AddressOwnershipProof>,
+    ) -> Result<U256, Box<dyn Error>> {
+        Ok(self
+            .summa_contract
+            .submit_proof_of_address_ownership(address_ownership_proofs)
+            .estimate_gas()
+            .await?)
+    }
 
     pub async fn submit_proof_of_address_ownership(
         &self,
         address_ownership_proofs: Vec<AddressOwnershipProof>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<AddressOwnershipReceipt, Box<dyn std::error::Error>> {
         let lock_guard = self.nonce_lock.lock().await;
 
         let submit_proof_of_address_ownership = &self
             .summa_contract
             .submit_proof_of_address_ownership(address_ownership_proofs);
 
-        // To prevent nonce collision, we lock the nonce before sending the transaction
-        let tx = submit_proof_of_address_ownership.send().await?;
-
-        // Wait for the pending transaction to be mined
-        tx.await?;
+        // To prevent nonce collision, we lock the nonce before sending the transaction,
+        // and wait for the transaction to be confirmed
+        let (receipt, replacements) = send_transaction(
+            self.summa_contract.client().as_ref(),
+            self.get_signer_address(),
+            &submit_proof_of_address_ownership.tx,
+            &self.transaction_config,
+        )
+        .await?;
 
         drop(lock_guard);
-        Ok(())
+        SubmissionReceipt::from_receipt(receipt, self.get_summa_address(), replacements)
     }
 
     pub async fn submit_commitment(
@@ -110,7 +238,7 @@ impl SummaSigner {
         grand_sum_proof: Bytes,
         total_balances: Vec<U256>,
         timestamp: U256,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<CommitmentReceipt, Box<dyn std::error::Error>> {
         let lock_guard = self.nonce_lock.lock().await;
 
         let submit_liability_commitment = &self.summa_contract.submit_commitment(
@@ -120,14 +248,18 @@ impl SummaSigner {
             timestamp,
         );
 
-        // To prevent nonce collision, we lock the nonce before sending the transaction
-        let tx = submit_liability_commitment.send().await?;
-
-        // Wait for the pending transaction to be mined
-        tx.await?;
+        // To prevent nonce collision, we lock the nonce before sending the transaction,
+        // and wait for the transaction to be confirmed
+        let (receipt, replacements) = send_transaction(
+            self.summa_contract.client().as_ref(),
+            self.get_signer_address(),
+            &submit_liability_commitment.tx,
+            &self.transaction_config,
+        )
+        .await?;
 
         drop(lock_guard);
 
-        Ok(())
+        SubmissionReceipt::from_receipt(receipt, self.get_summa_address(), replacements)
     }
 }
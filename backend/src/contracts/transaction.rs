@@ -0,0 +1,235 @@
+use ethers::{
+    contract::{parse_log, EthLogDecode},
+    providers::Middleware,
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockNumber, Eip1559TransactionRequest,
+        TransactionReceipt, H256, U256, U64,
+    },
+};
+use std::{error::Error, time::Duration};
+use tokio::time::{sleep, Instant};
+
+use super::generated::summa_contract::{
+    AddressOwnershipProofSubmittedFilter, LiabilitiesCommitmentSubmittedFilter,
+};
+
+/// The settings of the transactions sent by the `SummaSigner`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionConfig {
+    /// The upper bound of the max fee per gas, in wei
+    pub max_fee_per_gas_cap: Option<U256>,
+    /// The upper bound of the max priority fee per gas, in wei
+    pub max_priority_fee_per_gas_cap: Option<U256>,
+    /// The number of blocks to wait for, including the one containing the transaction
+    pub confirmations: u64,
+    /// The time to wait for the transaction to be mined before replacing it with higher fees
+    pub replacement_timeout: Duration,
+    /// The percentage by which the fees are increased on each replacement
+    pub fee_bump_percent: u64,
+    /// The number of replacements after which the transaction is given up
+    pub max_replacements: usize,
+    /// The interval between two polls of the node
+    pub poll_interval: Duration,
+}
+
+impl Default for TransactionConfig {
+    fn default() -> Self {
+        TransactionConfig {
+            max_fee_per_gas_cap: None,
+            max_priority_fee_per_gas_cap: None,
+            confirmations: 1,
+            replacement_timeout: Duration::from_secs(60),
+            fee_bump_percent: 15,
+            max_replacements: 3,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// The receipt of a transaction sent to the Summa contract, along with the event it emitted
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmissionReceipt<E> {
+    pub transaction_hash: H256,
+    pub block_number: u64,
+    pub gas_used: U256,
+    pub effective_gas_price: U256,
+    /// The number of times the transaction was replaced with higher fees before being mined
+    pub replacements: usize,
+    pub event: E,
+}
+
+pub type AddressOwnershipReceipt = SubmissionReceipt<AddressOwnershipProofSubmittedFilter>;
+
+pub type CommitmentReceipt = SubmissionReceipt<LiabilitiesCommitmentSubmittedFilter>;
+
+impl<E: EthLogDecode> SubmissionReceipt<E> {
+    /// Builds the typed receipt from the receipt of a transaction, decoding the event emitted by the given contract
+    pub fn from_receipt(
+        receipt: TransactionReceipt,
+        contract_address: Address,
+        replacements: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        let event = receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == contract_address)
+            .find_map(|log| parse_log::<E>(log.clone()).ok())
+            .ok_or("The transaction did not emit the expected event")?;
+
+        Ok(SubmissionReceipt {
+            transaction_hash: receipt.transaction_hash,
+            block_number: receipt
+                .block_number
+                .ok_or("The transaction is not mined")?
+                .as_u64(),
+            gas_used: receipt.gas_used.unwrap_or_default(),
+            effective_gas_price: receipt.effective_gas_price.unwrap_or_default(),
+            replacements,
+            event,
+        })
+    }
+}
+
+fn apply_cap(fee: U256, cap: Option<U256>) -> U256 {
+    match cap {
+        Some(cap) => fee.min(cap),
+        None => fee,
+    }
+}
+
+fn bump(fee: U256, percent: u64) -> U256 {
+    fee + (fee * percent / 100).max(U256::one())
+}
+
+/// Returns the receipt of the first mined transaction among the sent ones, which all share the same nonce
+async fn find_receipt<M: Middleware>(
+    client: &M,
+    sent_hashes: &[H256],
+) -> Result<Option<TransactionReceipt>, Box<dyn Error>>
+where
+    M::Error: 'static,
+{
+    for hash in sent_hashes {
+        if let Some(receipt) = client.get_transaction_receipt(*hash).await? {
+            return Ok(Some(receipt));
+        }
+    }
+    Ok(None)
+}
+
+/// Sends the call as an EIP-1559 transaction and waits for its confirmations.
+///
+/// The fees are estimated from the fee history of the node and capped according to the config.
+/// Whenever the transaction is not mined within the replacement timeout, it is replaced by the same transaction,
+/// with the same nonce, paying fees increased by the configured percentage.
+/// Every replacement counts against the configured maximum, including the ones the node rejects,
+/// and the error of the last replacement is returned if it failed when the maximum is reached.
+///
+/// # Arguments
+///
+/// * `client` - the signing client sending the transaction
+/// * `from` - the address of the signer of the client
+/// * `call` - the transaction of the contract call, only its recipient and data are used
+/// * `config` - the fee caps, confirmations and replacement settings
+///
+/// # Returns
+///
+/// * `TransactionReceipt` - the receipt of the confirmed transaction
+/// * `usize` - the number of replacements sent
+pub async fn send_transaction<M: Middleware>(
+    client: &M,
+    from: Address,
+    call: &TypedTransaction,
+    config: &TransactionConfig,
+) -> Result<(TransactionReceipt, usize), Box<dyn Error>>
+where
+    M::Error: 'static,
+{
+    let mut tx = Eip1559TransactionRequest::new()
+        .from(from)
+        .data(call.data().cloned().unwrap_or_default());
+    if let Some(to) = call.to() {
+        tx = tx.to(to.clone());
+    }
+
+    // The nonce and the gas limit are fixed so that the replacements override the original transaction
+    let nonce = client
+        .get_transaction_count(from, Some(BlockNumber::Pending.into()))
+        .await?;
+    let gas = client
+        .estimate_gas(&tx.clone().nonce(nonce).into(), None)
+        .await?;
+    tx = tx.nonce(nonce).gas(gas);
+
+    let (estimated_max_fee, estimated_priority_fee) = client.estimate_eip1559_fees(None).await?;
+    let mut max_fee = apply_cap(estimated_max_fee, config.max_fee_per_gas_cap);
+    let mut max_priority_fee =
+        apply_cap(estimated_priority_fee, config.max_priority_fee_per_gas_cap).min(max_fee);
+
+    let mut sent_hashes: Vec<H256> = Vec::new();
+    let mut n_attempts = 0;
+    let mut last_error: Option<Box<dyn Error>> = None;
+    loop {
+        tx = tx
+            .max_fee_per_gas(max_fee)
+            .max_priority_fee_per_gas(max_priority_fee);
+        n_attempts += 1;
+        match client.send_transaction(tx.clone(), None).await {
+            Ok(pending) => {
+                sent_hashes.push(pending.tx_hash());
+                last_error = None;
+            }
+            // A previous transaction may have been mined in the meantime, which is checked below
+            Err(e) if !sent_hashes.is_empty() => last_error = Some(e.into()),
+            Err(e) => return Err(e.into()),
+        }
+
+        let deadline = Instant::now() + config.replacement_timeout;
+        loop {
+            // The receipt is fetched again on each poll, so that a reorganized transaction is waited for again
+            if let Some(receipt) = find_receipt(client, &sent_hashes).await? {
+                if receipt.status != Some(U64::one()) {
+                    return Err(
+                        format!("Transaction {:?} reverted", receipt.transaction_hash).into(),
+                    );
+                }
+                let block_number = receipt.block_number.ok_or("The transaction is not mined")?;
+                let current_block_number = client.get_block_number().await?;
+                if current_block_number + 1 >= block_number + config.confirmations {
+                    return Ok((receipt, sent_hashes.len().saturating_sub(1)));
+                }
+            } else if Instant::now() >= deadline {
+                break;
+            }
+            sleep(config.poll_interval).await;
+        }
+
+        if n_attempts > config.max_replacements {
+            return Err(last_error.unwrap_or_else(|| {
+                format!(
+                    "The transaction is not mined after {} replacements",
+                    config.max_replacements
+                )
+                .into()
+            }));
+        }
+
+        // The replacement pays at least the current estimate, and the nodes only accept it if both fees are increased
+        let (estimated_max_fee, estimated_priority_fee) =
+            client.estimate_eip1559_fees(None).await?;
+        let bumped_max_fee = apply_cap(
+            bump(max_fee, config.fee_bump_percent).max(estimated_max_fee),
+            config.max_fee_per_gas_cap,
+        );
+        let bumped_priority_fee = apply_cap(
+            bump(max_priority_fee, config.fee_bump_percent).max(estimated_priority_fee),
+            config.max_priority_fee_per_gas_cap,
+        )
+        .min(bumped_max_fee);
+        if bumped_max_fee <= max_fee || bumped_priority_fee <= max_priority_fee {
+            return Err("The transaction is not mined and its fees reached the caps".into());
+        }
+        max_fee = bumped_max_fee;
+        max_priority_fee = bumped_priority_fee;
+    }
+}
@@ -0,0 +1,416 @@
+//! Indexer of the history of a Summa contract.
+//!
+//! The `LiabilitiesCommitmentSubmitted` and `AddressOwnershipProofSubmitted` logs are scanned from a given block
+//! and stored in an embedded `sled` database, so that the past rounds can be listed without knowing their timestamps.
+//! The hashes of the indexed blocks are stored along with the events, and checked against the chain on each sync
+//! to roll back the events of the blocks removed by a reorganization.
+use ethers::{
+    contract::LogMeta,
+    providers::{Http, Middleware, Provider},
+    types::{Address, Bytes, H256, U256},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{error::Error, path::Path, sync::Arc};
+
+use crate::contracts::{
+    generated::summa_contract::{AddressOwnershipProof, Summa},
+    reader::{hash_address, SummaReader},
+};
+
+pub use crate::contracts::reader::DEFAULT_BATCH_SIZE;
+
+// All the entries are stored in a single tree, with prefixed keys, so that each sync is applied atomically
+const COMMITMENT_PREFIX: &[u8] = b"commitment/";
+const OWNERSHIP_PROOF_PREFIX: &[u8] = b"ownership_proof/";
+const BLOCK_PREFIX: &[u8] = b"block/";
+const NEXT_BLOCK_KEY: &[u8] = b"next_block";
+const START_BLOCK_KEY: &[u8] = b"start_block";
+const SUMMA_ADDRESS_KEY: &[u8] = b"summa_address";
+
+/// The block and the transaction an event was emitted in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventLocation {
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub transaction_hash: H256,
+}
+
+impl From<&LogMeta> for EventLocation {
+    fn from(meta: &LogMeta) -> Self {
+        EventLocation {
+            block_number: meta.block_number.as_u64(),
+            block_hash: meta.block_hash,
+            transaction_hash: meta.transaction_hash,
+        }
+    }
+}
+
+/// A commitment submitted to the Summa contract, as emitted by `LiabilitiesCommitmentSubmitted`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedCommitment {
+    pub timestamp: u64,
+    pub total_balances: Vec<U256>,
+    pub snark_proof: Bytes,
+    pub grand_sum_proof: Bytes,
+    pub location: EventLocation,
+}
+
+/// A proof of address ownership submitted to the Summa contract, as emitted by `AddressOwnershipProofSubmitted`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedOwnershipProof {
+    pub cex_address: String,
+    pub chain: String,
+    pub signature: Bytes,
+    pub message: Bytes,
+    pub location: EventLocation,
+}
+
+impl IndexedOwnershipProof {
+    pub fn to_address_ownership_proof(&self) -> AddressOwnershipProof {
+        AddressOwnershipProof {
+            cex_address: self.cex_address.clone(),
+            chain: self.chain.clone(),
+            signature: self.signature.clone(),
+            message: self.message.clone(),
+        }
+    }
+}
+
+/// The outcome of a sync of the indexer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    /// The first block whose events were rolled back because of a reorganization, if any
+    pub rolled_back_from: Option<u64>,
+    pub new_commitments: usize,
+    pub new_ownership_proofs: usize,
+    /// The last block scanned, if any
+    pub last_block: Option<u64>,
+}
+
+/// Indexes the commitments and the proofs of address ownership submitted to a Summa contract.
+pub struct SummaIndexer {
+    db: sled::Db,
+    provider: Arc<Provider<Http>>,
+    summa_contract: Summa<Provider<Http>>,
+    batch_size: u64,
+}
+
+fn commitment_key(timestamp: u64) -> Vec<u8> {
+    [COMMITMENT_PREFIX, &timestamp.to_be_bytes()[..]].concat()
+}
+
+fn ownership_proof_key(cex_address: &str) -> Vec<u8> {
+    [OWNERSHIP_PROOF_PREFIX, &hash_address(cex_address)[..]].concat()
+}
+
+fn block_key(block_number: u64) -> Vec<u8> {
+    [BLOCK_PREFIX, &block_number.to_be_bytes()[..]].concat()
+}
+
+fn decode<T: DeserializeOwned>(value: &[u8]) -> Result<T, Box<dyn Error>> {
+    Ok(bincode::deserialize(value)?)
+}
+
+fn decode_u64(value: &[u8]) -> Result<u64, Box<dyn Error>> {
+    Ok(u64::from_be_bytes(
+        value.try_into().map_err(|_| "Invalid block number")?,
+    ))
+}
+
+impl SummaIndexer {
+    /// Opens the indexer database at the given path, creating it if needed
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - the directory of the database
+    /// * `reader` - the client of the indexed Summa contract
+    /// * `start_block` - the block to start scanning from, e.g., the deployment block of the contract.
+    ///   It is ignored when the database already exists.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        reader: &SummaReader,
+        start_block: u64,
+    ) -> Result<Self, Box<dyn Error>> {
+        let db = sled::open(path)?;
+        let summa_address = reader.get_summa_address();
+
+        match db.get(SUMMA_ADDRESS_KEY)? {
+            Some(stored_address) if stored_address.as_ref() != summa_address.as_bytes() => {
+                return Err(format!(
+                    "The database indexes another Summa contract than {:?}",
+                    summa_address
+                )
+                .into());
+            }
+            Some(_) => {}
+            None => {
+                let mut batch = sled::Batch::default();
+                batch.insert(SUMMA_ADDRESS_KEY, summa_address.as_bytes());
+                batch.insert(START_BLOCK_KEY, &start_block.to_be_bytes());
+                batch.insert(NEXT_BLOCK_KEY, &start_block.to_be_bytes());
+                db.apply_batch(batch)?;
+            }
+        }
+
+        Ok(SummaIndexer {
+            db,
+            provider: reader.get_contract().client(),
+            summa_contract: reader.get_contract().clone(),
+            batch_size: DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    /// Sets the number of blocks scanned per `eth_getLogs` request
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn get_summa_address(&self) -> Address {
+        self.summa_contract.address()
+    }
+
+    /// Returns the block the scan started from
+    pub fn get_start_block(&self) -> Result<u64, Box<dyn Error>> {
+        decode_u64(
+            &self
+                .db
+                .get(START_BLOCK_KEY)?
+                .ok_or("The database is not initialized")?,
+        )
+    }
+
+    /// Returns the next block to scan
+    pub fn get_next_block(&self) -> Result<u64, Box<dyn Error>> {
+        decode_u64(
+            &self
+                .db
+                .get(NEXT_BLOCK_KEY)?
+                .ok_or("The database is not initialized")?,
+        )
+    }
+
+    /// Rolls back the blocks removed by a reorganization, then scans the new blocks up to the latest one
+    pub async fn sync(&self) -> Result<SyncSummary, Box<dyn Error>> {
+        let mut summary = SyncSummary {
+            rolled_back_from: self.rollback_reorganized_blocks().await?,
+            ..SyncSummary::default()
+        };
+
+        let latest_block = self.provider.get_block_number().await?.as_u64();
+        let mut from_block = self.get_next_block()?;
+        while from_block <= latest_block {
+            let to_block = latest_block.min(from_block + self.batch_size - 1);
+            let (new_commitments, new_ownership_proofs) = self.scan(from_block, to_block).await?;
+            summary.new_commitments += new_commitments;
+            summary.new_ownership_proofs += new_ownership_proofs;
+            summary.last_block = Some(to_block);
+            from_block = to_block + 1;
+        }
+
+        Ok(summary)
+    }
+
+    async fn canonical_block_hash(
+        &self,
+        block_number: u64,
+    ) -> Result<Option<H256>, Box<dyn Error>> {
+        Ok(self
+            .provider
+            .get_block(block_number)
+            .await?
+            .and_then(|block| block.hash))
+    }
+
+    /// Checks the stored block hashes against the chain, from the latest one, and rolls back the events of the
+    /// blocks following the last matching one. Since each block hash commits to the whole chain before it,
+    /// the check stops at the first matching block, while the blocks between it and the first mismatching one,
+    /// whose hashes are not stored, may have been reorganized as well.
+    async fn rollback_reorganized_blocks(&self) -> Result<Option<u64>, Box<dyn Error>> {
+        let mut reorganized = false;
+        let mut last_matching_block = None;
+        for entry in self.db.scan_prefix(BLOCK_PREFIX).rev() {
+            let (key, stored_hash) = entry?;
+            let block_number = decode_u64(&key[BLOCK_PREFIX.len()..])?;
+            if self.canonical_block_hash(block_number).await?
+                == Some(H256::from_slice(&stored_hash))
+            {
+                last_matching_block = Some(block_number);
+                break;
+            }
+            reorganized = true;
+        }
+
+        if !reorganized {
+            return Ok(None);
+        }
+        // Without any matching block, everything is scanned again from the start block
+        let rollback_from = match last_matching_block {
+            Some(block_number) => block_number + 1,
+            None => self.get_start_block()?,
+        };
+        self.rollback(rollback_from)?;
+        Ok(Some(rollback_from))
+    }
+
+    /// Removes the events of the given block and the following ones, which will be scanned again
+    fn rollback(&self, from_block: u64) -> Result<(), Box<dyn Error>> {
+        let mut batch = sled::Batch::default();
+        for entry in self.db.scan_prefix(COMMITMENT_PREFIX) {
+            let (key, value) = entry?;
+            if decode::<IndexedCommitment>(&value)?.location.block_number >= from_block {
+                batch.remove(key);
+            }
+        }
+        for entry in self.db.scan_prefix(OWNERSHIP_PROOF_PREFIX) {
+            let (key, value) = entry?;
+            if decode::<IndexedOwnershipProof>(&value)?
+                .location
+                .block_number
+                >= from_block
+            {
+                batch.remove(key);
+            }
+        }
+        for entry in self.db.scan_prefix(BLOCK_PREFIX) {
+            let (key, _) = entry?;
+            if decode_u64(&key[BLOCK_PREFIX.len()..])? >= from_block {
+                batch.remove(key);
+            }
+        }
+        batch.insert(NEXT_BLOCK_KEY, &from_block.to_be_bytes());
+        self.db.apply_batch(batch)?;
+        Ok(())
+    }
+
+    /// Scans the events of the given range of blocks and stores them along with the hashes of their blocks
+    async fn scan(&self, from_block: u64, to_block: u64) -> Result<(usize, usize), Box<dyn Error>> {
+        let last_block_hash = self
+            .canonical_block_hash(to_block)
+            .await?
+            .ok_or("The block to scan is not found")?;
+
+        let commitment_logs = self
+            .summa_contract
+            .liabilities_commitment_submitted_filter()
+            .from_block(from_block)
+            .to_block(to_block)
+            .query_with_meta()
+            .await?;
+        let ownership_proof_logs = self
+            .summa_contract
+            .address_ownership_proof_submitted_filter()
+            .from_block(from_block)
+            .to_block(to_block)
+            .query_with_meta()
+            .await?;
+
+        let mut batch = sled::Batch::default();
+        let mut block_hashes = vec![(to_block, last_block_hash)];
+        for (log, meta) in &commitment_logs {
+            let indexed_commitment = IndexedCommitment {
+                timestamp: log.timestamp.as_u64(),
+                total_balances: log.total_balances.clone(),
+                snark_proof: log.snark_proof.clone(),
+                grand_sum_proof: log.grand_sum_proof.clone(),
+                location: meta.into(),
+            };
+            batch.insert(
+                commitment_key(indexed_commitment.timestamp),
+                bincode::serialize(&indexed_commitment)?,
+            );
+            block_hashes.push((meta.block_number.as_u64(), meta.block_hash));
+        }
+        let mut new_ownership_proofs = 0;
+        for (log, meta) in &ownership_proof_logs {
+            for proof in &log.address_ownership_proofs {
+                let indexed_proof = IndexedOwnershipProof {
+                    cex_address: proof.cex_address.clone(),
+                    chain: proof.chain.clone(),
+                    signature: proof.signature.clone(),
+                    message: proof.message.clone(),
+                    location: meta.into(),
+                };
+                batch.insert(
+                    ownership_proof_key(&proof.cex_address),
+                    bincode::serialize(&indexed_proof)?,
+                );
+                new_ownership_proofs += 1;
+            }
+            block_hashes.push((meta.block_number.as_u64(), meta.block_hash));
+        }
+
+        // The logs must come from the chain the last block belongs to, otherwise the range is scanned again later
+        for (block_number, block_hash) in &block_hashes {
+            if self.canonical_block_hash(*block_number).await? != Some(*block_hash) {
+                return Err(format!(
+                    "The chain was reorganized while scanning block {}",
+                    block_number
+                )
+                .into());
+            }
+            batch.insert(block_key(*block_number), block_hash.as_bytes());
+        }
+        batch.insert(NEXT_BLOCK_KEY, &(to_block + 1).to_be_bytes());
+        self.db.apply_batch(batch)?;
+
+        Ok((commitment_logs.len(), new_ownership_proofs))
+    }
+
+    /// Returns the commitment of the round with the given timestamp
+    pub fn get_round(&self, timestamp: u64) -> Result<Option<IndexedCommitment>, Box<dyn Error>> {
+        self.db
+            .get(commitment_key(timestamp))?
+            .map(|value| decode(&value))
+            .transpose()
+    }
+
+    /// Returns the commitment of the round with the latest timestamp
+    pub fn get_latest_round(&self) -> Result<Option<IndexedCommitment>, Box<dyn Error>> {
+        self.db
+            .scan_prefix(COMMITMENT_PREFIX)
+            .next_back()
+            .transpose()?
+            .map(|(_, value)| decode(&value))
+            .transpose()
+    }
+
+    /// Returns the commitments of all the rounds, ordered by timestamp
+    pub fn get_rounds(&self) -> Result<Vec<IndexedCommitment>, Box<dyn Error>> {
+        self.db
+            .scan_prefix(COMMITMENT_PREFIX)
+            .map(|entry| decode(&entry?.1))
+            .collect()
+    }
+
+    /// Returns the total balances of all the rounds, ordered by timestamp
+    pub fn get_totals_over_time(&self) -> Result<Vec<(u64, Vec<U256>)>, Box<dyn Error>> {
+        Ok(self
+            .get_rounds()?
+            .into_iter()
+            .map(|commitment| (commitment.timestamp, commitment.total_balances))
+            .collect())
+    }
+
+    /// Returns the proof of ownership of the given address
+    pub fn get_ownership_proof(
+        &self,
+        cex_address: &str,
+    ) -> Result<Option<IndexedOwnershipProof>, Box<dyn Error>> {
+        self.db
+            .get(ownership_proof_key(cex_address))?
+            .map(|value| decode(&value))
+            .transpose()
+    }
+
+    /// Returns the proofs of ownership of all the addresses, ordered by block
+    pub fn get_ownership_proofs(&self) -> Result<Vec<IndexedOwnershipProof>, Box<dyn Error>> {
+        let mut proofs = self
+            .db
+            .scan_prefix(OWNERSHIP_PROOF_PREFIX)
+            .map(|entry| decode(&entry?.1))
+            .collect::<Result<Vec<IndexedOwnershipProof>, _>>()?;
+        proofs.sort_by_key(|proof| proof.location.block_number);
+        Ok(proofs)
+    }
+}
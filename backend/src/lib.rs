@@ -1,4 +1,8 @@
 #![feature(generic_const_exprs)]
 pub mod apis;
 pub mod contracts;
+#[cfg(feature = "indexer")]
+pub mod indexer;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod tests;
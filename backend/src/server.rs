@@ -0,0 +1,433 @@
+//! HTTP service serving the inclusion proofs of several rounds to the users.
+//!
+//! The service exposes the following endpoints:
+//!
+//! * `GET /rounds` - the metadata of all the loaded rounds
+//! * `GET /rounds/:timestamp` - the metadata of a round
+//! * `GET /rounds/:timestamp/commitment` - the `CommitmentBundle` of a round, as submitted to the Summa contract
+//! * `GET /rounds/:timestamp/inclusion_proof` - the `InclusionProofBundle` of the authenticated user in a round
+//!
+//! The inclusion proof endpoint requires an `Authorization: Bearer <token>` header.
+//! The token is resolved to a user ID, i.e., the username in the entries, by an [`Authenticator`],
+//! and the requests of each user are rate limited.
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Path, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use ethers::signers::Signer;
+use serde::{Deserialize, Serialize};
+use summa_solvency::cryptocurrency::Cryptocurrency;
+
+use crate::apis::{
+    bundle::{CommitmentBundle, InclusionProofBundle, RoundContext},
+    round::{Round, Snapshot},
+};
+
+/// Resolves the bearer tokens of the requests to user IDs
+pub trait Authenticator: Send + Sync {
+    /// Returns the user ID, i.e., the username in the entries, authenticated by the token, or `None` if the token is invalid
+    fn authenticate(&self, token: &str) -> Option<String>;
+}
+
+/// Authenticator backed by a fixed map from the tokens to the user IDs
+#[derive(Debug, Clone, Default)]
+pub struct StaticTokenAuthenticator {
+    tokens: HashMap<String, String>,
+}
+
+impl StaticTokenAuthenticator {
+    pub fn new(tokens: HashMap<String, String>) -> Self {
+        StaticTokenAuthenticator { tokens }
+    }
+}
+
+impl Authenticator for StaticTokenAuthenticator {
+    fn authenticate(&self, token: &str) -> Option<String> {
+        self.tokens.get(token).cloned()
+    }
+}
+
+/// Per-user rate limit, as a maximum number of requests within a fixed time window
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub max_requests: u32,
+    pub window: Duration,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            max_requests: 10,
+            window: Duration::from_secs(60),
+        }
+    }
+}
+
+/// The request windows of the users, along with the last time the expired ones were evicted
+struct RateWindows {
+    users: HashMap<String, (Instant, u32)>,
+    last_eviction: Instant,
+}
+
+struct RateLimiter {
+    limit: RateLimit,
+    windows: Mutex<RateWindows>,
+}
+
+impl RateLimiter {
+    fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            limit,
+            windows: Mutex::new(RateWindows {
+                users: HashMap::new(),
+                last_eviction: Instant::now(),
+            }),
+        }
+    }
+
+    /// Records a request of the user and returns whether it is within the limit
+    ///
+    /// The windows older than `limit.window` are evicted at most once per window, so that the memory is bounded
+    /// by the number of users seen within the last two windows.
+    fn check(&self, user_id: &str) -> bool {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+
+        if now.duration_since(windows.last_eviction) >= self.limit.window {
+            let window = self.limit.window;
+            windows
+                .users
+                .retain(|_, (window_start, _)| now.duration_since(*window_start) < window);
+            windows.last_eviction = now;
+        }
+
+        let (window_start, count) = windows.users.entry(user_id.to_owned()).or_insert((now, 0));
+        if now.duration_since(*window_start) >= self.limit.window {
+            *window_start = now;
+            *count = 0;
+        }
+        if *count >= self.limit.max_requests {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+/// The metadata of a loaded round
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundMetadata {
+    pub timestamp: u64,
+    pub n_currencies: usize,
+    pub n_users: usize,
+    pub k: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// An error returned by the service, rendered as a JSON body with the matching HTTP status
+#[derive(Debug)]
+pub struct ServerError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ServerError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ServerError {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(ErrorBody {
+                error: self.message,
+            }),
+        )
+            .into_response()
+    }
+}
+
+/// A served round, along with its commitment computed once when the round is added
+struct ServedRound<const N_CURRENCIES: usize, const N_USERS: usize> {
+    snapshot: Arc<Snapshot<N_CURRENCIES, N_USERS>>,
+    commitment: CommitmentBundle,
+}
+
+struct ServerState<const N_CURRENCIES: usize, const N_USERS: usize> {
+    rounds: BTreeMap<u64, ServedRound<N_CURRENCIES, N_USERS>>,
+    authenticator: Box<dyn Authenticator>,
+    rate_limiter: RateLimiter,
+}
+
+/// The `InclusionProofServer` serves the inclusion proofs of several rounds, keyed by their timestamps.
+///
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of currencies of the rounds.
+/// * `N_USERS`: The number of users of the rounds.
+///
+pub struct InclusionProofServer<const N_CURRENCIES: usize, const N_USERS: usize> {
+    rounds: BTreeMap<u64, ServedRound<N_CURRENCIES, N_USERS>>,
+    authenticator: Box<dyn Authenticator>,
+    rate_limit: RateLimit,
+}
+
+impl<const N_CURRENCIES: usize, const N_USERS: usize> InclusionProofServer<N_CURRENCIES, N_USERS>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+{
+    pub fn new<A: Authenticator + 'static>(authenticator: A, rate_limit: RateLimit) -> Self {
+        InclusionProofServer {
+            rounds: BTreeMap::new(),
+            authenticator: Box::new(authenticator),
+            rate_limit,
+        }
+    }
+
+    /// Adds a round to the served rounds, replacing any round with the same timestamp
+    ///
+    /// # Arguments
+    ///
+    /// * `round` - the round, whose commitment is submitted to the Summa contract
+    /// * `cryptocurrencies` - the cryptocurrencies of the balance columns of the entries, in order
+    pub fn add_round<S: Signer + 'static>(
+        &mut self,
+        round: Round<'_, N_CURRENCIES, N_USERS, S>,
+        cryptocurrencies: &[Cryptocurrency],
+    ) -> Result<(), Box<dyn Error>> {
+        let context = round.get_context(cryptocurrencies);
+        self.add_snapshot(context, round.into_snapshot())
+    }
+
+    /// Adds a snapshot to the served rounds under the timestamp of its context, e.g., one restored with [`Snapshot::load`]
+    ///
+    /// The grand sum proof of the round is generated here, once, and served from memory afterwards.
+    pub fn add_snapshot(
+        &mut self,
+        context: RoundContext,
+        snapshot: Snapshot<N_CURRENCIES, N_USERS>,
+    ) -> Result<(), Box<dyn Error>> {
+        if context.k != snapshot.get_k() {
+            return Err(format!(
+                "The context is for k = {}, the snapshot has k = {}",
+                context.k,
+                snapshot.get_k()
+            )
+            .into());
+        }
+        let timestamp = context.timestamp;
+        let commitment = CommitmentBundle::from_snapshot(context, &snapshot)?;
+
+        self.rounds.insert(
+            timestamp,
+            ServedRound {
+                snapshot: Arc::new(snapshot),
+                commitment,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns the router of the service, to be served or nested in an existing application
+    pub fn router(self) -> Router {
+        let state = Arc::new(ServerState {
+            rounds: self.rounds,
+            authenticator: self.authenticator,
+            rate_limiter: RateLimiter::new(self.rate_limit),
+        });
+
+        Router::new()
+            .route("/rounds", get(list_rounds::<N_CURRENCIES, N_USERS>))
+            .route(
+                "/rounds/:timestamp",
+                get(get_round_metadata::<N_CURRENCIES, N_USERS>),
+            )
+            .route(
+                "/rounds/:timestamp/commitment",
+                get(get_commitment::<N_CURRENCIES, N_USERS>),
+            )
+            .route(
+                "/rounds/:timestamp/inclusion_proof",
+                get(get_inclusion_proof::<N_CURRENCIES, N_USERS>),
+            )
+            .with_state(state)
+    }
+
+    /// Serves the rounds on the given address until the task is cancelled
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), Box<dyn Error + Send + Sync>> {
+        axum::Server::bind(&addr)
+            .serve(self.router().into_make_service())
+            .await?;
+        Ok(())
+    }
+
+    /// Serves the rounds on an already bound listener until the task is cancelled
+    pub async fn serve_on(self, listener: TcpListener) -> Result<(), Box<dyn Error + Send + Sync>> {
+        axum::Server::from_tcp(listener)?
+            .serve(self.router().into_make_service())
+            .await?;
+        Ok(())
+    }
+}
+
+type SharedState<const N_CURRENCIES: usize, const N_USERS: usize> =
+    Arc<ServerState<N_CURRENCIES, N_USERS>>;
+
+fn round_metadata<const N_CURRENCIES: usize, const N_USERS: usize>(
+    timestamp: u64,
+    snapshot: &Snapshot<N_CURRENCIES, N_USERS>,
+) -> RoundMetadata
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+{
+    RoundMetadata {
+        timestamp,
+        n_currencies: N_CURRENCIES,
+        n_users: N_USERS,
+        k: snapshot.get_k(),
+    }
+}
+
+fn find_round<const N_CURRENCIES: usize, const N_USERS: usize>(
+    state: &ServerState<N_CURRENCIES, N_USERS>,
+    timestamp: u64,
+) -> Result<&ServedRound<N_CURRENCIES, N_USERS>, ServerError> {
+    state.rounds.get(&timestamp).ok_or_else(|| {
+        ServerError::new(
+            StatusCode::NOT_FOUND,
+            format!("Round {} not found", timestamp),
+        )
+    })
+}
+
+fn authenticate<const N_CURRENCIES: usize, const N_USERS: usize>(
+    state: &ServerState<N_CURRENCIES, N_USERS>,
+    headers: &HeaderMap,
+) -> Result<String, ServerError> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| ServerError::new(StatusCode::UNAUTHORIZED, "Missing bearer token"))?;
+
+    state
+        .authenticator
+        .authenticate(token)
+        .ok_or_else(|| ServerError::new(StatusCode::UNAUTHORIZED, "Invalid bearer token"))
+}
+
+async fn list_rounds<const N_CURRENCIES: usize, const N_USERS: usize>(
+    State(state): State<SharedState<N_CURRENCIES, N_USERS>>,
+) -> Json<Vec<RoundMetadata>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+{
+    Json(
+        state
+            .rounds
+            .iter()
+            .map(|(timestamp, round)| round_metadata(*timestamp, &round.snapshot))
+            .collect(),
+    )
+}
+
+async fn get_round_metadata<const N_CURRENCIES: usize, const N_USERS: usize>(
+    State(state): State<SharedState<N_CURRENCIES, N_USERS>>,
+    Path(timestamp): Path<u64>,
+) -> Result<Json<RoundMetadata>, ServerError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+{
+    let round = find_round(&state, timestamp)?;
+    Ok(Json(round_metadata(timestamp, &round.snapshot)))
+}
+
+async fn get_commitment<const N_CURRENCIES: usize, const N_USERS: usize>(
+    State(state): State<SharedState<N_CURRENCIES, N_USERS>>,
+    Path(timestamp): Path<u64>,
+) -> Result<Json<CommitmentBundle>, ServerError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+{
+    let round = find_round(&state, timestamp)?;
+    Ok(Json(round.commitment.clone()))
+}
+
+async fn get_inclusion_proof<const N_CURRENCIES: usize, const N_USERS: usize>(
+    State(state): State<SharedState<N_CURRENCIES, N_USERS>>,
+    Path(timestamp): Path<u64>,
+    headers: HeaderMap,
+) -> Result<Json<InclusionProofBundle>, ServerError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+{
+    let user_id = authenticate(&state, &headers)?;
+    if !state.rate_limiter.check(&user_id) {
+        return Err(ServerError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "Rate limit exceeded",
+        ));
+    }
+    let round = find_round(&state, timestamp)?;
+    let snapshot = round.snapshot.clone();
+    let context = round.commitment.context.clone();
+
+    // Generating the KZG proofs is CPU-bound, so it is kept off the async runtime
+    let inclusion_proof = tokio::task::spawn_blocking(move || {
+        snapshot
+            .generate_proof_of_inclusion_for_user(&user_id)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| ServerError::new(StatusCode::NOT_FOUND, e))?;
+
+    let bundle = InclusionProofBundle::new(context, inclusion_proof)
+        .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(bundle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_evicts_expired_windows() {
+        let rate_limiter = RateLimiter::new(RateLimit {
+            max_requests: 1,
+            window: Duration::from_millis(50),
+        });
+
+        assert!(rate_limiter.check("alice"));
+        assert!(!rate_limiter.check("alice"));
+        assert!(rate_limiter.check("bob"));
+        assert_eq!(rate_limiter.windows.lock().unwrap().users.len(), 2);
+
+        // Once the window has elapsed, the next request evicts the expired windows of the other users
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(rate_limiter.check("alice"));
+        let windows = rate_limiter.windows.lock().unwrap();
+        assert_eq!(windows.users.len(), 1);
+        assert!(windows.users.contains_key("alice"));
+    }
+}
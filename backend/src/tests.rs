@@ -5,10 +5,15 @@ use ethers::{
     prelude::SignerMiddleware,
     providers::{Http, Middleware, Provider},
     signers::{LocalWallet, Signer},
-    types::{H160, U256},
+    types::{transaction::eip2718::TypedTransaction, Bytes, H160, U256},
     utils::{Anvil, AnvilInstance},
 };
-use tokio::time;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    time,
+};
 
 use crate::contracts::generated::{
     grandsum_verifier::GrandSumVerifier, inclusion_verifier::InclusionVerifier,
@@ -61,11 +66,25 @@ pub async fn initialize_test_env(
             .await;
     }
 
-    // Deploy a Verifying Key and Verifier contracts.
+    // Deploy the Summa contract along with its Verifying Key and Verifier contracts.
     if block_time.is_some() {
         time::sleep(Duration::from_secs(block_time.unwrap())).await;
     };
 
+    let summa_contract =
+        deploy_summa_contract(Arc::clone(&client), &[("ETH", "ETH"), ("USDT", "ETH")]).await;
+
+    time::sleep(Duration::from_secs(3)).await;
+
+    (anvil, cex_addr_1, cex_addr_2, client, summa_contract)
+}
+
+// Deploy a Summa contract, owned by the signer of the client, along with its Verifying Key and Verifier contracts.
+// The cryptocurrencies are given as (name, chain) pairs.
+pub async fn deploy_summa_contract(
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    cryptocurrencies: &[(&str, &str)],
+) -> Summa<SignerMiddleware<Provider<Http>, LocalWallet>> {
     let verifying_key_contract = VerifyingKey::deploy(Arc::clone(&client), ())
         .unwrap()
         .send()
@@ -98,50 +117,144 @@ pub async fn initialize_test_env(
         Token::Address(snark_verifier_contract.address()),
         Token::Address(grand_sum_verifier.address()),
         Token::Address(inclusion_verifier_contract.address()),
-        // TOCO: check cryptocurrency names
-        Token::Array([Token::String("ETH".into()), Token::String("USDT".into())].to_vec()),
-        Token::Array([Token::String("ETH".into()), Token::String("ETH".into())].to_vec()),
+        Token::Array(
+            cryptocurrencies
+                .iter()
+                .map(|(name, _)| Token::String(name.to_string()))
+                .collect(),
+        ),
+        Token::Array(
+            cryptocurrencies
+                .iter()
+                .map(|(_, chain)| Token::String(chain.to_string()))
+                .collect(),
+        ),
         Token::Uint(balance_byte_range.into()),
     ];
 
     // Deploy Summa contract.
-    let summa_contract = Summa::deploy(Arc::clone(&client), args)
-        .unwrap()
-        .send()
-        .await
-        .unwrap();
+    Summa::deploy(client, args).unwrap().send().await.unwrap()
+}
 
-    time::sleep(Duration::from_secs(3)).await;
+// Spawn a mock remote signer serving `eth_sign` and `eth_signTransaction` over HTTP with the given wallet.
+pub async fn spawn_mock_remote_signer(wallet: LocalWallet) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
 
-    (anvil, cex_addr_1, cex_addr_2, client, summa_contract)
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let wallet = wallet.clone();
+            tokio::spawn(async move {
+                // Read the request headers, then the body of the announced length.
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                let body_start = loop {
+                    let n = stream.read(&mut buffer).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buffer[..n]);
+                    if let Some(position) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break position + 4;
+                    }
+                };
+                let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+                let content_length: usize = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map(|value| value.trim().parse().unwrap())
+                    .unwrap_or(0);
+                while request.len() < body_start + content_length {
+                    let n = stream.read(&mut buffer).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buffer[..n]);
+                }
+
+                let rpc_request: Value =
+                    serde_json::from_slice(&request[body_start..body_start + content_length])
+                        .unwrap();
+                let result = match rpc_request["method"].as_str() {
+                    Some("eth_sign") => {
+                        let message: Bytes =
+                            serde_json::from_value(rpc_request["params"][1].clone()).unwrap();
+                        let signature = wallet.sign_message(message.as_ref()).await.unwrap();
+                        json!(format!("0x{}", signature))
+                    }
+                    Some("eth_signTransaction") => {
+                        let tx: TypedTransaction =
+                            serde_json::from_value(rpc_request["params"][0].clone()).unwrap();
+                        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+                        json!(tx.rlp_signed(&signature))
+                    }
+                    _ => Value::Null,
+                };
+
+                let body = json!({ "jsonrpc": "2.0", "id": rpc_request["id"], "result": result })
+                    .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+
+    url
 }
 
 #[cfg(test)]
 mod test {
+    use async_trait::async_trait;
     use ethers::{
         abi::AbiEncode,
+        middleware::SignerMiddleware,
         providers::{Http, Middleware, Provider},
-        types::{U256, U64},
-        utils::to_checksum,
+        signers::{LocalWallet, Signer},
+        types::{Bytes, TransactionRequest, H160, H256, U256, U64},
+        utils::{keccak256, to_checksum},
     };
-    use halo2_proofs::halo2curves::bn256::Fr as Fp;
+    use halo2_proofs::{halo2curves::bn256::Fr as Fp, poly::commitment::Params};
     use rand::{rngs::OsRng, Rng};
-    use std::{convert::TryFrom, error::Error};
+    use std::{convert::TryFrom, error::Error, sync::Arc};
     use tokio::{
         join,
         time::{sleep, Duration},
     };
 
-    use crate::apis::{address_ownership::AddressOwnership, round::Round};
+    use crate::apis::{
+        address_ownership::{AddressOwnership, SkipReason, SkippedProof},
+        assets::{AddressBalance, AssetCollector, CurrencyAsset, CurrencyManifest},
+        bundle::{CommitmentBundle, InclusionProofBundle, RoundContext, BUNDLE_VERSION},
+        csv_parser::{parse_signature_csv, SignatureRecord},
+        ownership_message::{MessageBinding, OwnershipMessage},
+        preflight::VerifyingKeyCode,
+        report::{SnarkVerification, SolvencyReport},
+        round::{KZGProof, Round},
+        round_manager::{CsvEntrySource, EntrySource, RoundManager, RoundStage},
+        signature_verification::SignatureValidation,
+        tenant::{CircuitShape, SummaTenant, Tenant, TenantRegistry},
+        verification::{
+            decode_username, verify_inclusion_proof_offline,
+            verify_inclusion_proof_with_verifying_key, DeployedVerifyingKey,
+        },
+    };
     use crate::contracts::{
+        config::{read_summa_config, SummaConfig},
         generated::summa_contract::{AddressOwnershipProof, AddressOwnershipProofSubmittedFilter},
+        reader::{hash_address, SummaReader},
+        remote_signer::RemoteSigner,
         signer::{AddressInput, SummaSigner},
+        transaction::{send_transaction, TransactionConfig},
     };
-    use crate::tests::initialize_test_env;
+    use crate::tests::{deploy_summa_contract, initialize_test_env, spawn_mock_remote_signer};
     use summa_solvency::{
         circuits::{
             univariate_grand_sum::{UnivariateGrandSum, UnivariateGrandSumConfig},
-            utils::{full_prover, full_verifier, generate_setup_artifacts},
+            utils::{full_prover, full_verifier, generate_setup_artifacts, MultiOpenScheme},
         },
         cryptocurrency::Cryptocurrency,
         entry::Entry,
@@ -174,87 +287,234 @@ mod test {
     }
 
     #[tokio::test]
-    async fn test_concurrent_sumbit_commitments() -> Result<(), Box<dyn Error>> {
-        let (anvil, _, _, _, summa_contract) = initialize_test_env(Some(1)).await;
+    async fn test_signer_backends() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+        let exchange_address = anvil.addresses()[0];
 
-        // This test ensures that dispatching two commitments concurrently does not result in nonce collisions.
-        // It checks that both commitments are processed and mined, indicating there is no interference when they are submitted simultaneously.
-        let signer = SummaSigner::new(
-            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        // The wallet derived from the Anvil mnemonic is the first Anvil account.
+        let mnemonic_signer = SummaSigner::from_mnemonic(
+            "test test test test test test test test test test test junk",
+            "m/44'/60'/0'/0/0",
             anvil.endpoint().as_str(),
             AddressInput::Address(summa_contract.address()),
         )
         .await?;
+        assert_eq!(mnemonic_signer.get_signer_address(), exchange_address);
 
-        let entry_csv = "../csv/entry_16.csv";
-        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
-        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
-        parse_csv_to_entries::<&str, N_CURRENCIES>(entry_csv, &mut entries, &mut cryptos).unwrap();
+        // The wallet decrypted from a keystore is the one encrypted in it.
+        let keystore_dir =
+            std::env::temp_dir().join(format!("summa_keystore_{}", OsRng.gen::<u64>()));
+        std::fs::create_dir_all(&keystore_dir)?;
+        let (keystore_wallet, keystore_name) =
+            LocalWallet::new_keystore(&keystore_dir, &mut OsRng, "password", None)?;
+        let keystore_signer = SummaSigner::from_keystore(
+            keystore_dir.join(&keystore_name),
+            "password",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+        assert_eq!(
+            keystore_signer.get_signer_address(),
+            keystore_wallet.address()
+        );
+        assert!(SummaSigner::from_keystore(
+            keystore_dir.join(&keystore_name),
+            "wrong password",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await
+        .is_err());
+        std::fs::remove_dir_all(&keystore_dir)?;
 
-        let univariate_grand_sum_circuit = UnivariateGrandSum::<
-            N_USERS,
-            N_CURRENCIES,
-            UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
-        >::init(entries.to_vec());
+        // The transactions signed by the remote signer are accepted by the chain.
+        let wallet: LocalWallet = anvil.keys()[0].clone().into();
+        let remote_signer_url =
+            spawn_mock_remote_signer(wallet.with_chain_id(anvil.chain_id())).await;
+        let remote_signer = SummaSigner::with_signer(
+            RemoteSigner::new(&remote_signer_url, exchange_address),
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+        assert_eq!(remote_signer.get_signer_address(), exchange_address);
 
-        let (params, pk, vk) =
-            generate_setup_artifacts(K, Some(PARAMS_PATH), &univariate_grand_sum_circuit).unwrap();
+        let mut address_ownership_client =
+            AddressOwnership::new(&remote_signer, "../csv/signatures.csv")?.allow_legacy_messages();
+        address_ownership_client
+            .dispatch_proof_of_address_ownership()
+            .await?;
+        let ownership_proof_logs = summa_contract
+            .address_ownership_proof_submitted_filter()
+            .query()
+            .await?;
+        assert_eq!(ownership_proof_logs.len(), 1);
 
-        // Create a proof
-        let instances = vec![Fp::zero(); 1]; // This instance is necessary to verify proof on solidity verifier.
-        let (zk_snark_proof, advice_polys, _omega) = full_prover(
-            &params,
-            &pk,
-            univariate_grand_sum_circuit.clone(),
-            &[instances.clone()],
+        // The signatures of another account than the expected one are rejected.
+        let message = "Summa proof of solvency for CryptoExchange";
+        let signature = RemoteSigner::new(&remote_signer_url, exchange_address)
+            .sign_message(message)
+            .await?;
+        assert!(signature.verify(message, exchange_address).is_ok());
+        assert!(RemoteSigner::new(&remote_signer_url, anvil.addresses()[1])
+            .sign_message(message)
+            .await
+            .is_err());
+
+        drop(anvil);
+        Ok(())
+    }
+
+    #[test]
+    fn test_proof_bundles() -> Result<(), Box<dyn Error>> {
+        let context = RoundContext {
+            chain_id: 31337,
+            summa_address: "0xDc64a140Aa3E981100a9becA4E685f962f0cF6C9".parse()?,
+            timestamp: 1,
+            k: K,
+            cryptocurrencies: vec![
+                Cryptocurrency {
+                    name: "ETH".to_string(),
+                    chain: "ETH".to_string(),
+                },
+                Cryptocurrency {
+                    name: "USDT".to_string(),
+                    chain: "ETH".to_string(),
+                },
+            ],
+        };
+
+        // A bare `KZGProof` JSON, as exported before the bundles, is migrated by attaching its context.
+        let legacy_proof_json = r#"{
+            "proof_calldata": "0x0102",
+            "input_values": ["0x64784761", "0x2e70", "0xa0cb"],
+            "challenge_s_g2": ["0x1", "0x2", "0x3", "0x4"]
+        }"#;
+        assert!(InclusionProofBundle::from_json(legacy_proof_json).is_err());
+        let inclusion_proof_bundle =
+            InclusionProofBundle::from_kzg_proof_json(legacy_proof_json, context.clone())?;
+        assert_eq!(inclusion_proof_bundle.version, BUNDLE_VERSION);
+        assert_eq!(inclusion_proof_bundle.proof.get_user_index(), None);
+        assert_eq!(
+            inclusion_proof_bundle.balances()[1],
+            (&context.cryptocurrencies[1], &U256::from(0xa0cb))
         );
 
-        let mut round_one = Round::<N_CURRENCIES, N_USERS>::new(
-            &signer,
-            zk_snark_proof.clone(),
-            advice_polys.clone(),
-            params.clone(),
-            vk.clone(),
-            1,
+        // Both encodings round trip.
+        assert_eq!(
+            InclusionProofBundle::from_json(&inclusion_proof_bundle.to_json()?)?,
+            inclusion_proof_bundle
         );
-        let mut round_two = Round::<N_CURRENCIES, N_USERS>::new(
-            &signer,
-            zk_snark_proof,
-            advice_polys,
-            params,
-            vk,
-            2,
+        let inclusion_proof_bundle_bytes = inclusion_proof_bundle.to_bytes()?;
+        assert_eq!(
+            InclusionProofBundle::from_bytes(&inclusion_proof_bundle_bytes)?,
+            inclusion_proof_bundle
         );
 
-        // Check the block number before sending the transaction for liability commitment.
-        let outer_provider: Provider<Http> = Provider::try_from(anvil.endpoint().as_str())?;
-        let start_block_number = outer_provider.get_block_number().await?;
+        let commitment_bundle = CommitmentBundle::new(
+            context.clone(),
+            Bytes::from(vec![1u8; 192]),
+            Bytes::from(vec![2u8; 128]),
+            vec![U256::from(556862), U256::from(556862)],
+        )?;
+        assert_eq!(
+            CommitmentBundle::from_json(&commitment_bundle.to_json()?)?,
+            commitment_bundle
+        );
+        assert_eq!(
+            CommitmentBundle::from_bytes(&commitment_bundle.to_bytes()?)?,
+            commitment_bundle
+        );
 
-        // Send two commitments simultaneously.
-        let (round_one_result, round_two_result) = join!(
-            round_one.dispatch_commitment(),
-            round_two.dispatch_commitment()
+        // A bundle of another kind or of another version is rejected.
+        assert!(CommitmentBundle::from_bytes(&inclusion_proof_bundle_bytes).is_err());
+        let mut future_bundle = serde_json::to_value(&commitment_bundle)?;
+        future_bundle["version"] = (BUNDLE_VERSION + 1).into();
+        assert!(CommitmentBundle::from_json(&future_bundle.to_string()).is_err());
+
+        // The context has to match the proofs.
+        assert!(CommitmentBundle::new(
+            context.clone(),
+            Bytes::from(vec![1u8; 192]),
+            Bytes::from(vec![2u8; 128]),
+            vec![U256::from(556862)],
+        )
+        .is_err());
+
+        // The decoded bundles are checked like the constructed ones.
+        let mut mismatched_commitment_bundle = commitment_bundle.clone();
+        mismatched_commitment_bundle.total_balances.pop();
+        assert!(CommitmentBundle::from_json(&mismatched_commitment_bundle.to_json()?).is_err());
+        assert!(CommitmentBundle::from_bytes(&mismatched_commitment_bundle.to_bytes()?).is_err());
+
+        let mut mismatched_inclusion_proof_bundle = inclusion_proof_bundle.clone();
+        mismatched_inclusion_proof_bundle
+            .context
+            .cryptocurrencies
+            .pop();
+        assert!(
+            InclusionProofBundle::from_json(&mismatched_inclusion_proof_bundle.to_json()?).is_err()
+        );
+        assert!(
+            InclusionProofBundle::from_bytes(&mismatched_inclusion_proof_bundle.to_bytes()?)
+                .is_err()
         );
 
-        // Check that two blocks have been mined.
-        for _ in 0..5 {
-            sleep(Duration::from_millis(500)).await;
-            let updated_block_number = outer_provider.get_block_number().await?;
-            if (updated_block_number - start_block_number) > U64::from(2) {
-                break;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_summa_config() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, client, summa_contract) = initialize_test_env(None).await;
+
+        let config = read_summa_config(client.as_ref(), summa_contract.address()).await?;
+        assert_eq!(
+            config,
+            SummaConfig {
+                cryptocurrency_names: vec!["ETH".to_string(), "USDT".to_string()],
+                cryptocurrency_chains: vec!["ETH".to_string(), "ETH".to_string()],
+                balance_byte_range: 8,
             }
-        }
+        );
 
-        // Check that the results of both commitments are correctly submitted.
-        assert!(round_one_result.is_ok());
-        assert!(round_two_result.is_ok());
+        // The balance byte range read from the storage matches the public getter.
+        assert_eq!(
+            config.balance_byte_range,
+            summa_contract.config().call().await?
+        );
 
+        drop(anvil);
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_round_features() -> Result<(), Box<dyn Error>> {
-        let (anvil, cex_addr_1, cex_addr_2, _, summa_contract) = initialize_test_env(None).await;
+    async fn test_summa_reader() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let summa_reader = SummaReader::new(
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+        assert_eq!(summa_reader.get_chain_id(), anvil.chain_id());
+        assert_eq!(summa_reader.get_owner().await?, anvil.addresses()[0]);
+        assert_eq!(
+            summa_reader.get_config().await?.cryptocurrency_names,
+            vec!["ETH".to_string(), "USDT".to_string()]
+        );
+        assert_eq!(
+            summa_reader.get_verifying_key_address().await?,
+            summa_contract.verifying_key().call().await?
+        );
+
+        // Nothing is submitted yet.
+        assert!(summa_reader
+            .get_address_ownership_proofs()
+            .await?
+            .is_empty());
+        assert!(summa_reader.get_commitment_events().await?.is_empty());
+        assert_eq!(summa_reader.get_commitment(1).await?, None);
 
         let signer = SummaSigner::new(
             "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
@@ -262,119 +522,1491 @@ mod test {
             AddressInput::Address(summa_contract.address()),
         )
         .await?;
-
         let mut address_ownership_client =
-            AddressOwnership::new(&signer, "../csv/signatures.csv").unwrap();
-
-        // Dispatch proof of address ownership.
+            AddressOwnership::new(&signer, "../csv/signatures.csv")?.allow_legacy_messages();
         address_ownership_client
             .dispatch_proof_of_address_ownership()
             .await?;
 
-        let ownership_proof_logs = summa_contract
-            .address_ownership_proof_submitted_filter()
-            .query()
-            .await?;
+        // The submitted proofs are found by the hash of their address.
+        let ownership_proofs = address_ownership_client.get_ownership_proofs();
+        assert_eq!(
+            &summa_reader.get_address_ownership_proofs().await?,
+            ownership_proofs
+        );
+        for proof in ownership_proofs {
+            assert_eq!(
+                summa_reader
+                    .get_address_ownership_proof(hash_address(&proof.cex_address))
+                    .await?,
+                Some(proof.clone())
+            );
+        }
+        assert_eq!(
+            summa_reader
+                .get_address_ownership_proof(hash_address(
+                    "0x0000000000000000000000000000000000000000"
+                ))
+                .await?,
+            None
+        );
 
-        assert_eq!(ownership_proof_logs.len(), 1);
+        // The logs are the same when queried one block at a time.
+        let paged_reader = summa_reader.clone().with_batch_size(1);
         assert_eq!(
-        ownership_proof_logs[0],
-        AddressOwnershipProofSubmittedFilter {
-                address_ownership_proofs: vec![AddressOwnershipProof {
-        chain: "ETH".to_string(),
-        cex_address: to_checksum(&cex_addr_1, None),
-        signature:
-            ("0x089b32327d332c295dc3b8873c205b72153211de6dc1c51235782b091cefb9d06d6df2661b86a7d441cd322f125b84901486b150e684221a7b7636eb8182af551b").parse().unwrap(),
-            message:  "Summa proof of solvency for CryptoExchange".encode().into(),
-        },AddressOwnershipProof {
-        chain: "ETH".to_string(),
-        cex_address:to_checksum(&cex_addr_2, None),
-        signature:
-            ("0xb17a9e25265d3b88de7bfad81e7accad6e3d5612308ff83cc0fef76a34152b0444309e8fc3dea5139e49b6fc83a8553071a7af3d0cfd3fb8c1aea2a4c171729c1c").parse().unwrap(),
-            message:  "Summa proof of solvency for CryptoExchange".encode().into(),
-        },
-                ],
-            }
+            &paged_reader.get_address_ownership_proofs().await?,
+            ownership_proofs
         );
 
-        // Initialize Round.
-        let entry_csv = "../csv/entry_16.csv";
-        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
-        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
-        parse_csv_to_entries::<&str, N_CURRENCIES>(entry_csv, &mut entries, &mut cryptos).unwrap();
+        // No log is found after the start block.
+        let latest_block = summa_reader
+            .get_contract()
+            .client()
+            .get_block_number()
+            .await?
+            .as_u64();
+        let late_reader = summa_reader.with_start_block(latest_block + 1);
+        assert!(late_reader.get_address_ownership_proofs().await?.is_empty());
 
-        let univariate_grand_sum_circuit = UnivariateGrandSum::<
-            N_USERS,
-            N_CURRENCIES,
-            UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
-        >::init(entries.to_vec());
+        drop(anvil);
+        Ok(())
+    }
 
-        let (params, pk, vk) =
-            generate_setup_artifacts(K, Some(PARAMS_PATH), &univariate_grand_sum_circuit).unwrap();
+    #[tokio::test]
+    async fn test_reject_invalid_ownership_signatures() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
 
-        // Create a SNARK proof
-        let instances = vec![Fp::zero(); 1]; // This instance is necessary to verify proof on solidity verifier.
-        let (zk_snark_proof, advice_polys, _omega) = full_prover(
-            &params,
-            &pk,
-            univariate_grand_sum_circuit.clone(),
-            &[instances.clone()],
-        );
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
 
-        // Verify the SNARK proof to ensure its validity.
-        assert!(full_verifier(
-            &params,
-            pk.get_vk(),
-            &zk_snark_proof,
-            &[instances]
-        ));
+        // Swap the addresses of the two rows, so that none of the signatures matches its address.
+        let csv = std::fs::read_to_string("../csv/signatures.csv")?;
+        let rows: Vec<Vec<&str>> = csv
+            .lines()
+            .skip(1)
+            .map(|line| line.split(';').collect())
+            .collect();
+        let swapped_csv = format!(
+            "chain;address;signature;message\n{};{};{};{}\n{};{};{};{}\n",
+            rows[0][0],
+            rows[1][1],
+            rows[0][2],
+            rows[0][3],
+            rows[1][0],
+            rows[0][1],
+            rows[1][2],
+            rows[1][3]
+        );
+        let csv_path =
+            std::env::temp_dir().join(format!("summa_signatures_{}.csv", OsRng.gen::<u64>()));
+        std::fs::write(&csv_path, swapped_csv)?;
 
-        let snapshot_time = 1u64;
-        let mut round = Round::<N_CURRENCIES, N_USERS>::new(
-            &signer,
-            zk_snark_proof,
-            advice_polys,
-            params,
-            vk,
-            snapshot_time,
+        let mut address_ownership_client =
+            AddressOwnership::new(&signer, csv_path.to_str().unwrap())?;
+        let report = address_ownership_client.get_validation_report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].row, 1);
+        assert_eq!(
+            report[0].result,
+            SignatureValidation::AddressMismatch {
+                recovered: rows[0][1].to_string()
+            }
         );
+        assert!(report[1].result.is_rejected());
 
-        let mut liability_commitment_logs = summa_contract
-            .liabilities_commitment_submitted_filter()
+        // Nothing is sent on-chain.
+        assert!(address_ownership_client
+            .dispatch_proof_of_address_ownership()
+            .await
+            .is_err());
+        let ownership_proof_logs = summa_contract
+            .address_ownership_proof_submitted_filter()
             .query()
             .await?;
-        assert_eq!(liability_commitment_logs.len(), 0);
+        assert!(ownership_proof_logs.is_empty());
 
-        // Dispatch the liability commitment transaction to Summa contract.
-        round.dispatch_commitment().await?;
+        std::fs::remove_file(&csv_path)?;
+        drop(anvil);
+        Ok(())
+    }
 
-        // Check for updated logs to confirm the liability commitment transaction.
-        liability_commitment_logs = summa_contract
-            .liabilities_commitment_submitted_filter()
-            .query()
-            .await?;
+    #[tokio::test]
+    async fn test_structured_ownership_messages() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
 
-        assert_eq!(liability_commitment_logs.len(), 1);
-        assert_eq!(liability_commitment_logs[0].timestamp, U256::from(1));
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        // One address signs a message bound to the Summa contract, the other one a message bound to another contract.
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let message = OwnershipMessage {
+            exchange: "CryptoExchange".to_string(),
+            summa_address: summa_contract.address(),
+            chain_id: anvil.chain_id(),
+            nonce: 1,
+            valid_from: now - 60,
+            valid_until: now + 3600,
+        };
+        let other_contract_message = OwnershipMessage {
+            summa_address: H160::random(),
+            ..message.clone()
+        };
+
+        let csv_path =
+            std::env::temp_dir().join(format!("summa_signatures_{}.csv", OsRng.gen::<u64>()));
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(b';')
+            .from_path(&csv_path)?;
+        for (key, message) in anvil.keys()[3..5]
+            .iter()
+            .zip([&message, &other_contract_message])
+        {
+            let wallet: LocalWallet = key.clone().into();
+            let signature = wallet.sign_typed_data(message).await?;
+            writer.serialize(SignatureRecord::new(
+                "ETH".to_string(),
+                to_checksum(&wallet.address(), None),
+                Bytes::from(signature.to_vec()).to_string(),
+                message.to_message_string()?,
+            ))?;
+        }
+        writer.flush()?;
+
+        // Both signatures are valid, but the second message is rejected by the binding to the Summa contract.
+        let address_ownership_client = AddressOwnership::new(&signer, csv_path.to_str().unwrap())?;
+        let report = address_ownership_client.get_validation_report();
+        assert_eq!(report[0].result, SignatureValidation::Valid);
         assert_eq!(
-            liability_commitment_logs[0].total_balances,
-            vec![U256::from(556862), U256::from(556862)]
+            report[1].result,
+            SignatureValidation::MessageRejected(format!(
+                "signed for the Summa contract {}",
+                to_checksum(&other_contract_message.summa_address, None)
+            ))
         );
-        assert_eq!(liability_commitment_logs[0].grand_sum_proof.len(), 128);
-        assert_eq!(liability_commitment_logs[0].snark_proof.len(), 192);
 
-        // Generate and verify an inclusion proof for a random user.
-        let user_range: std::ops::Range<usize> = 0..N_USERS;
+        // The messages are also rejected when signed for another exchange or when expired.
+        let binding = MessageBinding::new(summa_contract.address(), anvil.chain_id());
+        let address_ownership_client = address_ownership_client
+            .with_message_binding(&binding.clone().with_exchange("OtherExchange"));
+        assert!(address_ownership_client.get_validation_report()[0]
+            .result
+            .is_rejected());
+        let mut address_ownership_client =
+            address_ownership_client.with_message_binding(&binding.at_timestamp(now + 7200));
+        assert!(address_ownership_client.get_validation_report()[0]
+            .result
+            .is_rejected());
+        assert!(address_ownership_client
+            .dispatch_proof_of_address_ownership()
+            .await
+            .is_err());
+
+        // The free-text messages are rejected unless the legacy messages are allowed.
+        let legacy_client = AddressOwnership::new(&signer, "../csv/signatures.csv")?;
+        assert!(legacy_client
+            .get_validation_report()
+            .iter()
+            .all(|validation| validation.result
+                == SignatureValidation::MessageRejected(
+                    "not a structured ownership message".to_string()
+                )));
+        assert!(legacy_client
+            .allow_legacy_messages()
+            .get_validation_report()
+            .iter()
+            .all(|validation| validation.result == SignatureValidation::Valid));
+
+        std::fs::remove_file(&csv_path)?;
+        drop(anvil);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_asset_collection() -> Result<(), Box<dyn Error>> {
+        let (anvil, cex_addr_1, cex_addr_2, _, summa_contract) = initialize_test_env(None).await;
+        let provider: Provider<Http> = Provider::try_from(anvil.endpoint().as_str())?;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+        AddressOwnership::new(&signer, "../csv/signatures.csv")?
+            .allow_legacy_messages()
+            .dispatch_proof_of_address_ownership()
+            .await?;
+
+        // A mock ERC-20 token, whose `balanceOf(owner)` returns the storage slot of the owner address.
+        let token = H160::random();
+        provider
+            .request::<_, ()>(
+                "anvil_setCode",
+                (token, "0x6004355460005260206000f3".parse::<Bytes>()?),
+            )
+            .await?;
+        for (owner, balance) in [(cex_addr_1, 200000u64), (cex_addr_2, 300000u64)] {
+            provider
+                .request::<_, ()>(
+                    "anvil_setStorageAt",
+                    (token, H256::from(owner), H256::from_low_u64_be(balance)),
+                )
+                .await?;
+        }
+        provider.request::<_, ()>("anvil_mine", [1u64]).await?;
+        let block = provider.get_block_number().await?.as_u64();
+
+        // The balances changed after the block are not collected.
+        provider
+            .request::<_, ()>("anvil_setBalance", (cex_addr_1, U256::from(1)))
+            .await?;
+        provider.request::<_, ()>("anvil_mine", [1u64]).await?;
+
+        let example_manifest = CurrencyManifest::from_file("currencies.example.json")?;
+        assert_eq!(example_manifest.currencies.len(), 2);
+        let manifest = CurrencyManifest {
+            currencies: vec![
+                CurrencyAsset {
+                    name: "ETH".to_string(),
+                    chain: "ETH".to_string(),
+                    token: None,
+                },
+                CurrencyAsset {
+                    name: "USDT".to_string(),
+                    chain: "ETH".to_string(),
+                    token: Some(token),
+                },
+            ],
+        };
+        let eth = manifest.currencies[0].get_cryptocurrency();
+        let usdt = manifest.currencies[1].get_cryptocurrency();
+
+        // The addresses of the chains without endpoint are skipped.
+        let summa_reader = SummaReader::new(
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+        let mut proofs = summa_reader.get_address_ownership_proofs().await?;
+        proofs.push(AddressOwnershipProof {
+            chain: "BTC".to_string(),
+            cex_address: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+            signature: Bytes::default(),
+            message: Bytes::default(),
+        });
+
+        let report = AssetCollector::new(manifest.clone())
+            .with_chain("ETH", anvil.endpoint().as_str(), Some(block))?
+            .collect(&proofs)
+            .await?;
+        assert_eq!(report.blocks["ETH"], block);
+        assert_eq!(report.balances.len(), 4);
+        assert_eq!(
+            report.balances[1],
+            AddressBalance {
+                cex_address: cex_addr_1,
+                cryptocurrency: usdt.clone(),
+                balance: U256::from(200000),
+            }
+        );
+        assert_eq!(report.get_total(&eth), U256::from(278432 * 2));
+        assert_eq!(report.get_total(&usdt), U256::from(500000));
+        assert_eq!(report.skipped, vec![proofs[2].clone()]);
+
+        // The assets are compared with the grand sums of the liabilities of `entry_16.csv`.
+        let checks = report.compare(
+            &[eth.clone(), usdt.clone()],
+            &[U256::from(556862), U256::from(556862)],
+        )?;
+        assert!(checks[0].is_solvent());
+        assert!(!checks[1].is_solvent());
+        assert!(report.compare(&[eth.clone()], &[]).is_err());
+
+        // Without block, the balances are read at the latest block.
+        let latest_report = AssetCollector::new(manifest)
+            .with_chain("ETH", anvil.endpoint().as_str(), None)?
+            .collect(&proofs)
+            .await?;
+        assert_eq!(report.blocks["ETH"] + 1, latest_report.blocks["ETH"]);
+        assert_eq!(latest_report.get_total(&eth), U256::from(278432 + 1));
+
+        drop(anvil);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_ownership_batches() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        // Register the first address of the signatures CSV beforehand.
+        let csv = std::fs::read_to_string("../csv/signatures.csv")?;
+        let registered_row = csv.lines().nth(1).unwrap();
+        let registered_proof = parse_signature_csv("../csv/signatures.csv")?.remove(0);
+        signer
+            .submit_proof_of_address_ownership(vec![registered_proof.clone()])
+            .await?;
+
+        // Sign the message with four new addresses.
+        let message = "Summa proof of solvency for CryptoExchange";
+        let mut new_rows = Vec::new();
+        for key in &anvil.keys()[3..7] {
+            let wallet: LocalWallet = key.clone().into();
+            let signature = wallet
+                .sign_message(keccak256(message.to_string().encode()))
+                .await?;
+            new_rows.push(format!(
+                "ETH;{};{};{}",
+                to_checksum(&wallet.address(), None),
+                Bytes::from(signature.to_vec()),
+                message
+            ));
+        }
+
+        // The CSV starts with the registered address and ends with a repetition of the first new address.
+        let csv_path =
+            std::env::temp_dir().join(format!("summa_signatures_{}.csv", OsRng.gen::<u64>()));
+        std::fs::write(
+            &csv_path,
+            format!(
+                "chain;address;signature;message\n{}\n{}\n{}\n",
+                registered_row,
+                new_rows.join("\n"),
+                new_rows[0]
+            ),
+        )?;
+        let csv_proofs = parse_signature_csv(&csv_path)?;
+        let new_proofs = csv_proofs[1..5].to_vec();
+
+        // The max batch gas fits two proofs but not three.
+        let two_proofs_gas = signer
+            .estimate_proof_of_address_ownership_gas(new_proofs[..2].to_vec())
+            .await?;
+        let three_proofs_gas = signer
+            .estimate_proof_of_address_ownership_gas(new_proofs[..3].to_vec())
+            .await?;
+        let max_batch_gas = (two_proofs_gas + (three_proofs_gas - two_proofs_gas) / 2).as_u64();
+
+        let mut address_ownership_client =
+            AddressOwnership::new(&signer, csv_path.to_str().unwrap())?
+                .allow_legacy_messages()
+                .with_max_batch_gas(max_batch_gas);
+        let report = address_ownership_client
+            .dispatch_proof_of_address_ownership()
+            .await?;
+
+        assert_eq!(report.submitted, new_proofs);
+        assert_eq!(
+            report.skipped,
+            vec![
+                SkippedProof {
+                    row: 1,
+                    chain: "ETH".to_string(),
+                    cex_address: registered_proof.cex_address.clone(),
+                    reason: SkipReason::AlreadyRegistered,
+                },
+                SkippedProof {
+                    row: 6,
+                    chain: "ETH".to_string(),
+                    cex_address: new_proofs[0].cex_address.clone(),
+                    reason: SkipReason::Duplicate { first_row: 2 },
+                },
+            ]
+        );
+        assert_eq!(report.receipts.len(), 2);
+        for (receipt, batch) in report.receipts.iter().zip(new_proofs.chunks(2)) {
+            assert_eq!(receipt.event.address_ownership_proofs, batch);
+            assert!(receipt.gas_used <= U256::from(max_batch_gas));
+        }
+
+        // Dispatching again submits nothing, as all the addresses are registered.
+        let report = address_ownership_client
+            .dispatch_proof_of_address_ownership()
+            .await?;
+        assert!(report.submitted.is_empty());
+        assert!(report.receipts.is_empty());
+        assert_eq!(report.skipped.len(), 6);
+        assert!(report.skipped[..5]
+            .iter()
+            .all(|skipped| skipped.reason == SkipReason::AlreadyRegistered));
+
+        std::fs::remove_file(&csv_path)?;
+        drop(anvil);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction_management() -> Result<(), Box<dyn Error>> {
+        let (anvil, cex_addr_1, _, client, summa_contract) = initialize_test_env(Some(1)).await;
+        let provider: Provider<Http> = Provider::try_from(anvil.endpoint().as_str())?;
+        let exchange_address = anvil.addresses()[0];
+        let max_fee_per_gas_cap = U256::from(100_000_000_000u64);
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?
+        .with_transaction_config(TransactionConfig {
+            max_fee_per_gas_cap: Some(max_fee_per_gas_cap),
+            confirmations: 2,
+            poll_interval: Duration::from_millis(100),
+            ..TransactionConfig::default()
+        });
+
+        // The receipt carries the emitted event, once the transaction is confirmed by the requested number of blocks
+        let mut address_ownership_client =
+            AddressOwnership::new(&signer, "../csv/signatures.csv")?.allow_legacy_messages();
+        let report = address_ownership_client
+            .dispatch_proof_of_address_ownership()
+            .await?;
+        assert_eq!(report.receipts.len(), 1);
+        let receipt = &report.receipts[0];
+        assert_eq!(
+            &receipt.event.address_ownership_proofs,
+            address_ownership_client.get_ownership_proofs()
+        );
+        assert_eq!(receipt.replacements, 0);
+        assert!(receipt.gas_used > U256::zero());
+        assert!(receipt.effective_gas_price <= max_fee_per_gas_cap);
+        assert!(provider.get_block_number().await?.as_u64() > receipt.block_number);
+
+        let transaction = provider
+            .get_transaction(receipt.transaction_hash)
+            .await?
+            .unwrap();
+        assert_eq!(transaction.transaction_type, Some(U64::from(2)));
+        assert!(transaction.max_fee_per_gas.unwrap() <= max_fee_per_gas_cap);
+
+        // The transactions reverting on the contract are rejected before being sent
+        assert!(signer
+            .submit_proof_of_address_ownership(
+                address_ownership_client.get_ownership_proofs().clone()
+            )
+            .await
+            .is_err());
+
+        // Stop mining, so that the transactions get stuck in the mempool
+        provider
+            .request::<_, ()>("evm_setIntervalMining", [0u64])
+            .await?;
+        let start_nonce = provider
+            .get_transaction_count(exchange_address, None)
+            .await?;
+        let transfer = TransactionRequest::new().to(cex_addr_1).into();
+
+        // The transaction is given up once the replacements are exhausted
+        let no_replacement_config = TransactionConfig {
+            replacement_timeout: Duration::from_millis(500),
+            max_replacements: 0,
+            poll_interval: Duration::from_millis(100),
+            ..TransactionConfig::default()
+        };
+        assert!(send_transaction(
+            client.as_ref(),
+            exchange_address,
+            &transfer,
+            &no_replacement_config
+        )
+        .await
+        .is_err());
+
+        // The stuck transaction is replaced with higher fees until the mining resumes
+        let mining_provider = provider.clone();
+        let resume_mining = tokio::spawn(async move {
+            sleep(Duration::from_millis(2500)).await;
+            mining_provider
+                .request::<_, ()>("evm_setIntervalMining", [1u64])
+                .await
+        });
+        let replacement_config = TransactionConfig {
+            replacement_timeout: Duration::from_secs(1),
+            poll_interval: Duration::from_millis(100),
+            ..TransactionConfig::default()
+        };
+        let (receipt, replacements) = send_transaction(
+            client.as_ref(),
+            exchange_address,
+            &transfer,
+            &replacement_config,
+        )
+        .await?;
+        resume_mining.await??;
+        assert!(replacements >= 1);
+        assert_eq!(receipt.status, Some(U64::one()));
+
+        // Only one transaction per nonce is mined, the given up one along with the replaced one
+        let end_nonce = provider
+            .get_transaction_count(exchange_address, None)
+            .await?;
+        assert_eq!(end_nonce, start_nonce + 2);
+
+        // The replacements rejected by the node count against the maximum, and the last rejection is returned.
+        // The balance of the exchange is drained after the first transaction is sent, so that its replacements fail.
+        provider
+            .request::<_, ()>("evm_setIntervalMining", [0u64])
+            .await?;
+        let balance = provider.get_balance(exchange_address, None).await?;
+        let drain_provider = provider.clone();
+        let drain_balance = tokio::spawn(async move {
+            sleep(Duration::from_millis(250)).await;
+            drain_provider
+                .request::<(H160, U256), ()>("anvil_setBalance", (exchange_address, U256::zero()))
+                .await
+        });
+        let failing_replacement_config = TransactionConfig {
+            replacement_timeout: Duration::from_millis(500),
+            max_replacements: 2,
+            poll_interval: Duration::from_millis(100),
+            ..TransactionConfig::default()
+        };
+        let result = tokio::time::timeout(
+            Duration::from_secs(30),
+            send_transaction(
+                client.as_ref(),
+                exchange_address,
+                &transfer,
+                &failing_replacement_config,
+            ),
+        )
+        .await?;
+        drain_balance.await??;
+        let error = result.expect_err("the replacements should fail");
+        assert!(!error.to_string().contains("not mined after"));
+        provider
+            .request::<(H160, U256), ()>("anvil_setBalance", (exchange_address, balance))
+            .await?;
+
+        drop(anvil);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_sumbit_commitments() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(Some(1)).await;
+
+        // This test ensures that dispatching two commitments concurrently does not result in nonce collisions.
+        // It checks that both commitments are processed and mined, indicating there is no interference when they are submitted simultaneously.
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let entry_csv = "../csv/entry_16.csv";
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&str, N_CURRENCIES>(entry_csv, &mut entries, &mut cryptos).unwrap();
+
+        let univariate_grand_sum_circuit = UnivariateGrandSum::<
+            N_USERS,
+            N_CURRENCIES,
+            UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
+        >::init(entries.to_vec());
+
+        let (params, pk, vk) =
+            generate_setup_artifacts(K, Some(PARAMS_PATH), &univariate_grand_sum_circuit).unwrap();
+
+        // Create a proof
+        let instances = vec![Fp::zero(); 1]; // This instance is necessary to verify proof on solidity verifier.
+        let (zk_snark_proof, advice_polys, _omega) = full_prover(
+            &params,
+            &pk,
+            univariate_grand_sum_circuit.clone(),
+            &[instances.clone()],
+            MultiOpenScheme::Shplonk,
+            None,
+        );
+
+        let mut round_one = Round::<N_CURRENCIES, N_USERS>::new(
+            &signer,
+            zk_snark_proof.clone(),
+            advice_polys.clone(),
+            params.clone(),
+            vk.clone(),
+            &entries,
+            1,
+        )?;
+        let mut round_two = Round::<N_CURRENCIES, N_USERS>::new(
+            &signer,
+            zk_snark_proof,
+            advice_polys,
+            params,
+            vk,
+            &entries,
+            2,
+        )?;
+
+        // Check the block number before sending the transaction for liability commitment.
+        let outer_provider: Provider<Http> = Provider::try_from(anvil.endpoint().as_str())?;
+        let start_block_number = outer_provider.get_block_number().await?;
+
+        // Send two commitments simultaneously.
+        let (round_one_result, round_two_result) = join!(
+            round_one.dispatch_commitment(),
+            round_two.dispatch_commitment()
+        );
+
+        // Check that two blocks have been mined.
+        for _ in 0..5 {
+            sleep(Duration::from_millis(500)).await;
+            let updated_block_number = outer_provider.get_block_number().await?;
+            if (updated_block_number - start_block_number) > U64::from(2) {
+                break;
+            }
+        }
+
+        // Check that the results of both commitments are correctly submitted.
+        assert!(round_one_result.is_ok());
+        assert!(round_two_result.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_round_features() -> Result<(), Box<dyn Error>> {
+        let (anvil, cex_addr_1, cex_addr_2, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let mut address_ownership_client = AddressOwnership::new(&signer, "../csv/signatures.csv")
+            .unwrap()
+            .allow_legacy_messages();
+
+        // Dispatch proof of address ownership.
+        let ownership_report = address_ownership_client
+            .dispatch_proof_of_address_ownership()
+            .await?;
+
+        let ownership_proof_logs = summa_contract
+            .address_ownership_proof_submitted_filter()
+            .query()
+            .await?;
+
+        assert_eq!(ownership_proof_logs.len(), 1);
+        assert_eq!(
+        ownership_proof_logs[0],
+        AddressOwnershipProofSubmittedFilter {
+                address_ownership_proofs: vec![AddressOwnershipProof {
+        chain: "ETH".to_string(),
+        cex_address: to_checksum(&cex_addr_1, None),
+        signature:
+            ("0x089b32327d332c295dc3b8873c205b72153211de6dc1c51235782b091cefb9d06d6df2661b86a7d441cd322f125b84901486b150e684221a7b7636eb8182af551b").parse().unwrap(),
+            message:  "Summa proof of solvency for CryptoExchange".encode().into(),
+        },AddressOwnershipProof {
+        chain: "ETH".to_string(),
+        cex_address:to_checksum(&cex_addr_2, None),
+        signature:
+            ("0xb17a9e25265d3b88de7bfad81e7accad6e3d5612308ff83cc0fef76a34152b0444309e8fc3dea5139e49b6fc83a8553071a7af3d0cfd3fb8c1aea2a4c171729c1c").parse().unwrap(),
+            message:  "Summa proof of solvency for CryptoExchange".encode().into(),
+        },
+                ],
+            }
+        );
+
+        // Initialize Round.
+        let entry_csv = "../csv/entry_16.csv";
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&str, N_CURRENCIES>(entry_csv, &mut entries, &mut cryptos).unwrap();
+
+        let univariate_grand_sum_circuit = UnivariateGrandSum::<
+            N_USERS,
+            N_CURRENCIES,
+            UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
+        >::init(entries.to_vec());
+
+        let (params, pk, vk) =
+            generate_setup_artifacts(K, Some(PARAMS_PATH), &univariate_grand_sum_circuit).unwrap();
+
+        // Create a SNARK proof
+        let instances = vec![Fp::zero(); 1]; // This instance is necessary to verify proof on solidity verifier.
+        let (zk_snark_proof, advice_polys, _omega) = full_prover(
+            &params,
+            &pk,
+            univariate_grand_sum_circuit.clone(),
+            &[instances.clone()],
+            MultiOpenScheme::Shplonk,
+            None,
+        );
+
+        // Verify the SNARK proof to ensure its validity.
+        assert!(full_verifier(
+            &params,
+            pk.get_vk(),
+            &zk_snark_proof,
+            &[instances],
+            MultiOpenScheme::Shplonk
+        ));
+
+        let snapshot_time = 1u64;
+        let mut round = Round::<N_CURRENCIES, N_USERS>::new(
+            &signer,
+            zk_snark_proof,
+            advice_polys,
+            params.clone(),
+            vk.clone(),
+            &entries,
+            snapshot_time,
+        )?;
+
+        let mut liability_commitment_logs = summa_contract
+            .liabilities_commitment_submitted_filter()
+            .query()
+            .await?;
+        assert_eq!(liability_commitment_logs.len(), 0);
+
+        assert_eq!(
+            round.get_total_balances()?,
+            vec![U256::from(556862), U256::from(556862)]
+        );
+
+        // The round matches the configuration and the verifying key of the Summa contract.
+        assert!(round.check_deployment(&cryptos).await?.is_empty());
+        round.preflight_check(&cryptos).await?;
+
+        // Swapping the cryptocurrencies is reported for both balance columns.
+        let swapped_cryptos: Vec<Cryptocurrency> = cryptos.iter().rev().cloned().collect();
+        let mismatches = round.check_deployment(&swapped_cryptos).await?;
+        assert_eq!(
+            mismatches
+                .iter()
+                .map(|mismatch| mismatch.field.as_str())
+                .collect::<Vec<_>>(),
+            vec!["cryptocurrency 0", "cryptocurrency 1"]
+        );
+        assert!(round
+            .preflight_check(&swapped_cryptos)
+            .await
+            .unwrap_err()
+            .to_string()
+            .starts_with("The round does not match the Summa deployment"));
+
+        // A verifying key contract deployed for another circuit size is reported as well.
+        let provider: Provider<Http> = Provider::try_from(anvil.endpoint().as_str())?;
+        let verifying_key_address = summa_contract.verifying_key().call().await?;
+        let verifying_key_code = provider.get_code(verifying_key_address, None).await?;
+        assert_eq!(
+            verifying_key_code.to_vec(),
+            VerifyingKeyCode::render(&params, &vk)?.to_bytes()
+        );
+        let mut tampered_code = verifying_key_code.to_vec();
+        tampered_code[63] += 1;
+        provider
+            .request::<_, ()>(
+                "anvil_setCode",
+                (verifying_key_address, Bytes::from(tampered_code)),
+            )
+            .await?;
+        let mismatches = round.check_deployment(&cryptos).await?;
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].field, "verifying key k");
+        assert!(round.preflight_check(&cryptos).await.is_err());
+        provider
+            .request::<_, ()>("anvil_setCode", (verifying_key_address, verifying_key_code))
+            .await?;
+
+        // Dispatch the liability commitment transaction to Summa contract.
+        let commitment_receipt = round.dispatch_commitment().await?;
+
+        // Check for updated logs to confirm the liability commitment transaction.
+        liability_commitment_logs = summa_contract
+            .liabilities_commitment_submitted_filter()
+            .query()
+            .await?;
+
+        assert_eq!(liability_commitment_logs.len(), 1);
+        assert_eq!(liability_commitment_logs[0].timestamp, U256::from(1));
+        assert_eq!(
+            liability_commitment_logs[0].total_balances,
+            vec![U256::from(556862), U256::from(556862)]
+        );
+        assert_eq!(liability_commitment_logs[0].grand_sum_proof.len(), 128);
+        assert_eq!(liability_commitment_logs[0].snark_proof.len(), 192);
+
+        // Generate and verify an inclusion proof for a random user.
+        let user_range: std::ops::Range<usize> = 0..N_USERS;
         let random_user_index = OsRng.gen_range(user_range) as usize;
         let inclusion_proof = round.get_proof_of_inclusion(random_user_index).unwrap();
 
-        // Check inclusion proof is not none
-        assert!(inclusion_proof.get_proof().len() > 0);
+        // Check inclusion proof is not none
+        assert!(inclusion_proof.get_proof().len() > 0);
+
+        // Verify the inclusion proof with onchain function
+        let verified = summa_contract
+            .verify_inclusion_proof(
+                U256::from(snapshot_time),
+                inclusion_proof.get_proof().clone(),
+                inclusion_proof
+                    .get_challenge()
+                    .clone()
+                    .expect("no challenge"),
+                inclusion_proof.get_input_values().clone(),
+            )
+            .await?;
+
+        assert!(verified);
+
+        // The read-only client reads the same commitment and verifies the same proof, without any key.
+        let summa_reader = SummaReader::new(
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+        assert!(
+            summa_reader
+                .verify_inclusion_proof(snapshot_time, &inclusion_proof)
+                .await?
+        );
+        assert_eq!(
+            summa_reader.get_commitment(snapshot_time).await?,
+            Some(
+                summa_contract
+                    .commitments(U256::from(snapshot_time))
+                    .call()
+                    .await?
+            )
+        );
+        assert_eq!(summa_reader.get_commitment_events().await?.len(), 1);
+
+        // The solvency report combines the committed liabilities with the assets of the proven addresses.
+        let manifest = CurrencyManifest {
+            currencies: vec![CurrencyAsset {
+                name: "ETH".to_string(),
+                chain: "ETH".to_string(),
+                token: None,
+            }],
+        };
+        let assets = AssetCollector::new(manifest)
+            .with_chain("ETH", anvil.endpoint().as_str(), None)?
+            .collect(&summa_reader.get_address_ownership_proofs().await?)
+            .await?;
+        let context = RoundContext {
+            chain_id: summa_reader.get_chain_id(),
+            summa_address: summa_contract.address(),
+            timestamp: snapshot_time,
+            k: K,
+            cryptocurrencies: cryptos.clone(),
+        };
+        let report = SolvencyReport::generate(
+            &summa_reader,
+            context.clone(),
+            &round.get_total_balances()?,
+            &assets,
+        )
+        .await?;
+        assert_eq!(
+            report.snark_verification,
+            SnarkVerification::VerifiedOnChain
+        );
+        assert_eq!(
+            report.commitment_transaction,
+            Some(commitment_receipt.transaction_hash)
+        );
+        assert_eq!(
+            report.ownership_transactions,
+            vec![ownership_report.receipts[0].transaction_hash]
+        );
+        // The report reads the same events when the logs are queried one block at a time.
+        let paged_report = SolvencyReport::generate(
+            &summa_reader.clone().with_batch_size(1),
+            context.clone(),
+            &round.get_total_balances()?,
+            &assets,
+        )
+        .await?;
+        assert_eq!(
+            paged_report.commitment_transaction,
+            report.commitment_transaction
+        );
+        assert_eq!(
+            paged_report.ownership_transactions,
+            report.ownership_transactions
+        );
+        assert_eq!(report.currencies[0].assets, U256::from(556864));
+        assert_eq!(report.currencies[0].liabilities, U256::from(556862));
+        assert!(report.currencies[0].is_solvent());
+        // The USDT balances are not collected, so the USDT liabilities are not covered.
+        assert_eq!(report.currencies[1].assets, U256::zero());
+        assert!(!report.is_solvent());
+
+        // The report of a round without commitment is not verified.
+        let uncommitted_report = SolvencyReport::generate(
+            &summa_reader,
+            RoundContext {
+                timestamp: snapshot_time + 1,
+                ..context
+            },
+            &round.get_total_balances()?,
+            &assets,
+        )
+        .await?;
+        assert_eq!(
+            uncommitted_report.snark_verification,
+            SnarkVerification::NotCommitted
+        );
+        assert_eq!(uncommitted_report.commitment_transaction, None);
+
+        // Verify the inclusion proof offline against the commitment and the verifying key stored on-chain.
+        let commitment = summa_contract
+            .commitments(U256::from(snapshot_time))
+            .call()
+            .await?;
+        let deployed_verifying_key =
+            DeployedVerifyingKey::from_code(&summa_reader.get_verifying_key_code().await?)?;
+        assert_eq!(
+            Some(deployed_verifying_key.challenge_s_g2(random_user_index)),
+            inclusion_proof.get_challenge().clone()
+        );
+        assert!(verify_inclusion_proof_with_verifying_key(
+            &deployed_verifying_key,
+            &commitment,
+            &inclusion_proof
+        )?);
+        assert_eq!(
+            decode_username(&inclusion_proof.get_input_values()[0])?,
+            entries[random_user_index].username()
+        );
+
+        // A tampered balance fails the offline verification.
+        let mut tampered_proof = serde_json::to_value(&inclusion_proof)?;
+        tampered_proof["input_values"][1] = serde_json::to_value(U256::from(1))?;
+        let tampered_proof: KZGProof = serde_json::from_value(tampered_proof)?;
+        assert!(!verify_inclusion_proof_with_verifying_key(
+            &deployed_verifying_key,
+            &commitment,
+            &tampered_proof
+        )?);
+
+        // The proof also verifies without relying on its challenge, recomputed from the verifying key instead.
+        assert!(verify_inclusion_proof_offline(
+            &params,
+            &vk,
+            &commitment,
+            &inclusion_proof
+        )?);
+        assert!(!verify_inclusion_proof_offline(
+            &params,
+            &vk,
+            &commitment,
+            &tampered_proof
+        )?);
+
+        // The proof does not open the columns at the row of another user.
+        let mut other_user_proof = serde_json::to_value(&inclusion_proof)?;
+        other_user_proof["user_index"] = ((random_user_index + 1) % N_USERS).into();
+        let other_user_proof: KZGProof = serde_json::from_value(other_user_proof)?;
+        assert!(!verify_inclusion_proof_offline(
+            &params,
+            &vk,
+            &commitment,
+            &other_user_proof
+        )?);
+        assert!(!verify_inclusion_proof_with_verifying_key(
+            &deployed_verifying_key,
+            &commitment,
+            &other_user_proof
+        )?);
+
+        // The challenge carried by the proof is not trusted, so replacing it does not forge a proof for another row.
+        let mut forged_proof = serde_json::to_value(&other_user_proof)?;
+        forged_proof["challenge_s_g2"] = serde_json::to_value(
+            deployed_verifying_key.challenge_s_g2((random_user_index + 1) % N_USERS),
+        )?;
+        let forged_proof: KZGProof = serde_json::from_value(forged_proof)?;
+        assert!(!verify_inclusion_proof_with_verifying_key(
+            &deployed_verifying_key,
+            &commitment,
+            &forged_proof
+        )?);
+
+        // Look up the inclusion proof of the same user by username.
+        let username = entries[random_user_index].username();
+        let inclusion_proof_by_username = round.get_proof_of_inclusion_for_user(username)?;
+        assert_eq!(
+            inclusion_proof_by_username.get_user_index(),
+            Some(random_user_index)
+        );
+        assert_eq!(inclusion_proof_by_username.get_username(), Some(username));
+        assert_eq!(
+            inclusion_proof_by_username.get_input_values(),
+            inclusion_proof.get_input_values()
+        );
+
+        let verified = summa_contract
+            .verify_inclusion_proof(
+                U256::from(snapshot_time),
+                inclusion_proof_by_username.get_proof().clone(),
+                inclusion_proof_by_username
+                    .get_challenge()
+                    .clone()
+                    .expect("no challenge"),
+                inclusion_proof_by_username.get_input_values().clone(),
+            )
+            .await?;
+        assert!(verified);
+
+        // Unknown usernames and out-of-range indices are rejected.
+        assert!(round.get_proof_of_inclusion_for_user("unknown").is_err());
+        assert!(round.get_proof_of_inclusion(N_USERS).is_err());
+
+        drop(anvil);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_round_save_and_load() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let entry_csv = "../csv/entry_16.csv";
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&str, N_CURRENCIES>(entry_csv, &mut entries, &mut cryptos).unwrap();
+
+        // A username longer than a field element, which cannot be recovered from the username polynomial
+        let long_username = "a-username-longer-than-the-31-bytes-of-a-field-element";
+        entries[2] = Entry::new(long_username.to_string(), entries[2].balances().clone())?;
+
+        let univariate_grand_sum_circuit = UnivariateGrandSum::<
+            N_USERS,
+            N_CURRENCIES,
+            UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
+        >::init(entries.to_vec());
+
+        let (params, pk, vk) =
+            generate_setup_artifacts(K, Some(PARAMS_PATH), &univariate_grand_sum_circuit).unwrap();
+
+        let (zk_snark_proof, advice_polys, _omega) = full_prover(
+            &params,
+            &pk,
+            univariate_grand_sum_circuit.clone(),
+            &[vec![Fp::zero()]],
+            MultiOpenScheme::Shplonk,
+            None,
+        );
+
+        let snapshot_time = 1u64;
+        let mut round = Round::<N_CURRENCIES, N_USERS>::new(
+            &signer,
+            zk_snark_proof,
+            advice_polys,
+            params.clone(),
+            vk,
+            &entries,
+            snapshot_time,
+        )?;
+        round.dispatch_commitment().await?;
+
+        // Persist the round and drop it, as if the backend was restarted
+        let snapshot_path = std::env::temp_dir().join("summa_round_test.snapshot");
+        round.save(&snapshot_path)?;
+        let inclusion_proof = round.get_proof_of_inclusion(0).unwrap();
+        drop(round);
+
+        let restored_round = Round::<N_CURRENCIES, N_USERS>::load::<
+            UnivariateGrandSum<
+                N_USERS,
+                N_CURRENCIES,
+                UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
+            >,
+            _,
+        >(&signer, &snapshot_path, params.clone())?;
+        assert_eq!(restored_round.get_timestamp(), snapshot_time);
+
+        // The usernames of the entries are restored along with the snapshot
+        let long_username_proof = restored_round.get_proof_of_inclusion_for_user(long_username)?;
+        assert_eq!(long_username_proof.get_user_index(), Some(2));
+        assert_eq!(long_username_proof.get_username(), Some(long_username));
+        assert_eq!(
+            restored_round
+                .get_proof_of_inclusion_for_user(entries[3].username())?
+                .get_user_index(),
+            Some(3)
+        );
+
+        // The restored round serves the same inclusion proofs, which are verified on-chain
+        let restored_inclusion_proof = restored_round.get_proof_of_inclusion(0).unwrap();
+        assert_eq!(
+            restored_inclusion_proof.get_proof(),
+            inclusion_proof.get_proof()
+        );
+        assert_eq!(
+            restored_inclusion_proof.get_input_values(),
+            inclusion_proof.get_input_values()
+        );
+
+        let verified = summa_contract
+            .verify_inclusion_proof(
+                U256::from(snapshot_time),
+                restored_inclusion_proof.get_proof().clone(),
+                restored_inclusion_proof
+                    .get_challenge()
+                    .clone()
+                    .expect("no challenge"),
+                restored_inclusion_proof.get_input_values().clone(),
+            )
+            .await?;
+        assert!(verified);
+
+        // The snapshot cannot be restored with other params than the ones it was generated with
+        let mut other_params = params;
+        other_params.downsize(K - 1);
+        assert!(Round::<N_CURRENCIES, N_USERS>::load::<
+            UnivariateGrandSum<
+                N_USERS,
+                N_CURRENCIES,
+                UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
+            >,
+            _,
+        >(&signer, &snapshot_path, other_params)
+        .is_err());
+
+        std::fs::remove_file(snapshot_path)?;
+        drop(anvil);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_round_manager() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        // The entries of the exchange change from one round to the next.
+        struct AlternatingEntrySource {
+            entry_csvs: [&'static str; 2],
+        }
+
+        #[async_trait]
+        impl EntrySource<N_CURRENCIES, N_USERS> for AlternatingEntrySource {
+            async fn fetch_entries(
+                &self,
+                timestamp: u64,
+            ) -> Result<(Vec<Cryptocurrency>, Vec<Entry<N_CURRENCIES>>), Box<dyn Error>>
+            {
+                let csv_source = CsvEntrySource::new(self.entry_csvs[timestamp as usize % 2]);
+                EntrySource::<N_CURRENCIES, N_USERS>::fetch_entries(&csv_source, timestamp).await
+            }
+        }
+        let entry_source = || AlternatingEntrySource {
+            entry_csvs: ["../csv/entry_16.csv", "../csv/entry_16_modified.csv"],
+        };
 
-        // Verify the inclusion proof with onchain function
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&str, N_CURRENCIES>(
+            "../csv/entry_16.csv",
+            &mut entries,
+            &mut cryptos,
+        )
+        .unwrap();
+        let univariate_grand_sum_circuit = UnivariateGrandSum::<
+            N_USERS,
+            N_CURRENCIES,
+            UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
+        >::init(entries.to_vec());
+        let (params, pk, _) =
+            generate_setup_artifacts(K, Some(PARAMS_PATH), &univariate_grand_sum_circuit).unwrap();
+
+        let state_dir =
+            std::env::temp_dir().join(format!("summa_round_manager_test_{}", OsRng.gen::<u64>()));
+        let round_manager = RoundManager::<N_CURRENCIES, N_USERS>::new(
+            &signer,
+            entry_source(),
+            params.clone(),
+            pk.clone(),
+            &state_dir,
+        )
+        .with_interval(Duration::from_secs(1))?;
+
+        // A round runs through all the stages, and running it again does not submit its commitment again.
+        let round_state = round_manager.run_round(1).await?;
+        assert_eq!(round_state.stage, RoundStage::Published);
+        assert_eq!(round_state.cryptocurrencies, cryptos);
+        assert!(round_state.commitment_transaction.is_some());
+        assert_eq!(round_state.n_published, N_USERS);
+        assert_eq!(round_manager.run_round(1).await?, round_state);
+        assert_eq!(
+            summa_contract
+                .liabilities_commitment_submitted_filter()
+                .query()
+                .await?
+                .len(),
+            1
+        );
+
+        // The published inclusion proofs verify against the submitted commitment.
+        let inclusion_proof_bundle = InclusionProofBundle::from_json(&std::fs::read_to_string(
+            round_manager
+                .get_round_dir(1)
+                .join("proofs")
+                .join("user_0_proof.json"),
+        )?)?;
+        assert_eq!(inclusion_proof_bundle.context.timestamp, 1);
         let verified = summa_contract
             .verify_inclusion_proof(
-                U256::from(snapshot_time),
+                U256::from(1),
+                inclusion_proof_bundle.proof.get_proof().clone(),
+                inclusion_proof_bundle
+                    .proof
+                    .get_challenge()
+                    .clone()
+                    .expect("no challenge"),
+                inclusion_proof_bundle.proof.get_input_values().clone(),
+            )
+            .await?;
+        assert!(verified);
+
+        // The next round crashes once proven, and its commitment is then submitted before the crash is recorded.
+        let round_state = round_manager.advance_round(2, RoundStage::Proven).await?;
+        assert_eq!(round_state.stage, RoundStage::Proven);
+        assert_eq!(signer.get_reader().get_commitment(2).await?, None);
+        drop(round_manager);
+        Round::<N_CURRENCIES, N_USERS>::load::<
+            UnivariateGrandSum<
+                N_USERS,
+                N_CURRENCIES,
+                UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
+            >,
+            _,
+        >(
+            &signer,
+            state_dir.join("round_2").join("snapshot.bin"),
+            params.clone(),
+        )?
+        .dispatch_commitment()
+        .await?;
+
+        // The restarted manager resumes the round from the saved snapshot, without submitting its commitment again.
+        let round_manager = RoundManager::<N_CURRENCIES, N_USERS>::new(
+            &signer,
+            entry_source(),
+            params.clone(),
+            pk.clone(),
+            &state_dir,
+        )
+        .with_interval(Duration::from_secs(1))?;
+        let resumed_states = round_manager.resume().await?;
+        assert_eq!(resumed_states.len(), 1);
+        assert_eq!(resumed_states[0].timestamp, 2);
+        assert_eq!(resumed_states[0].stage, RoundStage::Published);
+        assert_eq!(resumed_states[0].commitment_transaction, None);
+        assert_eq!(
+            summa_contract
+                .liabilities_commitment_submitted_filter()
+                .query()
+                .await?
+                .len(),
+            2
+        );
+
+        // The scheduled round runs at the start of the current interval.
+        let scheduled_states = round_manager.run(Some(1)).await?;
+        assert_eq!(scheduled_states.len(), 1);
+        assert_eq!(scheduled_states[0].stage, RoundStage::Published);
+        assert!(scheduled_states[0].timestamp > 2);
+        assert_eq!(
+            summa_contract
+                .liabilities_commitment_submitted_filter()
+                .query()
+                .await?
+                .len(),
+            3
+        );
+
+        let round_states = round_manager.get_states()?;
+        assert_eq!(round_states.len(), 3);
+        assert!(round_states
+            .iter()
+            .all(|round_state| round_state.stage == RoundStage::Published));
+
+        std::fs::remove_dir_all(state_dir)?;
+        drop(anvil);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tenant_registry() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract_a) = initialize_test_env(None).await;
+
+        // The second exchange deploys its own Summa contract from another account.
+        let exchange_b_key = "0x7c852118294e51e653712a81e05800f419141751be58f605c371e15141b007a6";
+        let client_b = Arc::new(SignerMiddleware::new(
+            Provider::<Http>::try_from(anvil.endpoint())?.interval(Duration::from_millis(10u64)),
+            exchange_b_key
+                .parse::<LocalWallet>()?
+                .with_chain_id(anvil.chain_id()),
+        ));
+        let summa_contract_b =
+            deploy_summa_contract(client_b, &[("ETH", "ETH"), ("USDT", "ETH")]).await;
+
+        let exchange_a_key = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+        let signer_a = SummaSigner::new(
+            exchange_a_key,
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract_a.address()),
+        )
+        .await?;
+        let signer_b = SummaSigner::new(
+            exchange_b_key,
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract_b.address()),
+        )
+        .await?;
+
+        let entry_csv = "../csv/entry_16.csv";
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&str, N_CURRENCIES>(entry_csv, &mut entries, &mut cryptos).unwrap();
+
+        // Both deployments use the verifier contracts of the same circuit, so the tenants share its keys.
+        let univariate_grand_sum_circuit = UnivariateGrandSum::<
+            N_USERS,
+            N_CURRENCIES,
+            UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
+        >::init(entries.to_vec());
+        let (params, pk, _) =
+            generate_setup_artifacts(K, Some(PARAMS_PATH), &univariate_grand_sum_circuit).unwrap();
+
+        let snapshots_dir =
+            std::env::temp_dir().join(format!("summa_tenants_{}", OsRng.gen::<u64>()));
+        let mut registry = TenantRegistry::new();
+        registry.register(SummaTenant::<N_CURRENCIES, N_USERS>::new(
+            "exchange-a",
+            signer_a,
+            params.clone(),
+            pk.clone(),
+            cryptos.clone(),
+            entry_csv,
+            snapshots_dir.join("exchange-a"),
+        )?)?;
+        registry.register(SummaTenant::<N_CURRENCIES, N_USERS>::new(
+            "exchange-b",
+            signer_b,
+            params.clone(),
+            pk.clone(),
+            cryptos.clone(),
+            "../csv/entry_16_modified.csv",
+            snapshots_dir.join("exchange-b"),
+        )?)?;
+        assert_eq!(
+            registry.get_tenant_names(),
+            vec!["exchange-a", "exchange-b"]
+        );
+
+        // The Summa contract of a tenant cannot be shared with another one, and the currencies of a tenant must be
+        // the ones of its entries.
+        let shared_contract_signer = SummaSigner::new(
+            exchange_a_key,
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract_a.address()),
+        )
+        .await?;
+        let shared_contract_tenant = SummaTenant::<N_CURRENCIES, N_USERS>::new(
+            "exchange-c",
+            shared_contract_signer,
+            params.clone(),
+            pk.clone(),
+            vec![
+                Cryptocurrency {
+                    name: "BTC".to_string(),
+                    chain: "BTC".to_string(),
+                },
+                cryptos[0].clone(),
+            ],
+            entry_csv,
+            snapshots_dir.join("exchange-c"),
+        )?;
+        assert!(shared_contract_tenant.run_round(1).await.is_err());
+        assert!(shared_contract_tenant.get_round_timestamps().is_empty());
+        assert!(registry.register(shared_contract_tenant).is_err());
+
+        // The rounds of the tenants run concurrently, each against its own Summa contract.
+        let rounds = registry.run_rounds(1).await;
+        assert_eq!(rounds.len(), 2);
+        for (name, round) in rounds {
+            let round = round?;
+            assert_eq!(round.tenant, name);
+            assert_eq!(round.timestamp, 1);
+            assert_eq!(round.receipt.event.total_balances, round.total_balances);
+        }
+        let commitment_logs_a = summa_contract_a
+            .liabilities_commitment_submitted_filter()
+            .query()
+            .await?;
+        assert_eq!(commitment_logs_a.len(), 1);
+        assert_eq!(
+            commitment_logs_a[0].total_balances,
+            vec![U256::from(556862), U256::from(556862)]
+        );
+        let commitment_logs_b = summa_contract_b
+            .liabilities_commitment_submitted_filter()
+            .query()
+            .await?;
+        assert_eq!(commitment_logs_b.len(), 1);
+        assert_ne!(
+            commitment_logs_b[0].total_balances,
+            commitment_logs_a[0].total_balances
+        );
+
+        // The rounds of a tenant are ordered, regardless of the rounds of the other tenants.
+        assert!(registry.run_round("exchange-a", 1).await.is_err());
+        assert_eq!(registry.run_round("exchange-b", 2).await?.timestamp, 2);
+        assert!(registry.run_round("exchange-d", 3).await.is_err());
+
+        let tenant_a = registry.get_tenant("exchange-a").unwrap();
+        let tenant_b = registry.get_tenant("exchange-b").unwrap();
+        assert_eq!(tenant_a.get_round_timestamps(), vec![1]);
+        assert_eq!(tenant_b.get_round_timestamps(), vec![1, 2]);
+        assert_eq!(
+            tenant_b.get_shape(),
+            CircuitShape {
+                n_currencies: N_CURRENCIES,
+                n_users: N_USERS,
+                k: K,
+            }
+        );
+
+        // Each tenant serves the proofs of inclusion of its own rounds.
+        let inclusion_proof = tenant_a.get_proof_of_inclusion(1, entries[0].username())?;
+        let verified = summa_contract_a
+            .verify_inclusion_proof(
+                U256::from(1),
                 inclusion_proof.get_proof().clone(),
                 inclusion_proof
                     .get_challenge()
@@ -383,9 +2015,351 @@ mod test {
                 inclusion_proof.get_input_values().clone(),
             )
             .await?;
+        assert!(verified);
+        assert!(tenant_a
+            .get_proof_of_inclusion(2, entries[0].username())
+            .is_err());
+
+        // The rounds are restored from the saved snapshots, which are loaded again once evicted from the cache.
+        let restarted_tenant_b = SummaTenant::<N_CURRENCIES, N_USERS>::new(
+            "exchange-b",
+            SummaSigner::new(
+                exchange_b_key,
+                anvil.endpoint().as_str(),
+                AddressInput::Address(summa_contract_b.address()),
+            )
+            .await?,
+            params.clone(),
+            pk.clone(),
+            cryptos.clone(),
+            "../csv/entry_16_modified.csv",
+            snapshots_dir.join("exchange-b"),
+        )?
+        .with_snapshot_cache_size(1);
+        assert_eq!(restarted_tenant_b.get_round_timestamps(), vec![1, 2]);
+        for timestamp in [1, 2, 1] {
+            assert_eq!(
+                restarted_tenant_b.get_proof_of_inclusion(timestamp, entries[0].username())?,
+                tenant_b.get_proof_of_inclusion(timestamp, entries[0].username())?
+            );
+        }
+
+        std::fs::remove_dir_all(&snapshots_dir)?;
+        drop(anvil);
+        Ok(())
+    }
+
+    #[cfg(feature = "indexer")]
+    #[tokio::test]
+    async fn test_summa_indexer() -> Result<(), Box<dyn Error>> {
+        use crate::indexer::SummaIndexer;
+
+        let (anvil, cex_addr_1, _, _, summa_contract) = initialize_test_env(None).await;
+        let provider: Provider<Http> = Provider::try_from(anvil.endpoint().as_str())?;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+        let summa_reader = SummaReader::new(
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let db_path = std::env::temp_dir().join(format!("summa_indexer_{}", OsRng.gen::<u64>()));
+        let indexer = SummaIndexer::open(&db_path, &summa_reader, 0)?.with_batch_size(2);
+
+        // Nothing is submitted yet.
+        let summary = indexer.sync().await?;
+        assert_eq!(summary.new_commitments, 0);
+        assert_eq!(indexer.get_latest_round()?, None);
+
+        let entry_csv = "../csv/entry_16.csv";
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&str, N_CURRENCIES>(entry_csv, &mut entries, &mut cryptos).unwrap();
+
+        let univariate_grand_sum_circuit = UnivariateGrandSum::<
+            N_USERS,
+            N_CURRENCIES,
+            UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
+        >::init(entries.to_vec());
+
+        let (params, pk, vk) =
+            generate_setup_artifacts(K, Some(PARAMS_PATH), &univariate_grand_sum_circuit).unwrap();
+
+        let instances = vec![Fp::zero(); 1];
+        let (zk_snark_proof, advice_polys, _omega) = full_prover(
+            &params,
+            &pk,
+            univariate_grand_sum_circuit.clone(),
+            &[instances.clone()],
+            MultiOpenScheme::Shplonk,
+            None,
+        );
+
+        let mut address_ownership_client =
+            AddressOwnership::new(&signer, "../csv/signatures.csv")?.allow_legacy_messages();
+        address_ownership_client
+            .dispatch_proof_of_address_ownership()
+            .await?;
+        let mut round_one = Round::<N_CURRENCIES, N_USERS>::new(
+            &signer,
+            zk_snark_proof.clone(),
+            advice_polys.clone(),
+            params.clone(),
+            vk.clone(),
+            &entries,
+            1,
+        )?;
+        let round_one_receipt = round_one.dispatch_commitment().await?;
+
+        // The submitted events are indexed.
+        let summary = indexer.sync().await?;
+        assert_eq!(summary.rolled_back_from, None);
+        assert_eq!(summary.new_commitments, 1);
+        assert_eq!(
+            summary.new_ownership_proofs,
+            address_ownership_client.get_ownership_proofs().len()
+        );
+
+        let latest_round = indexer.get_latest_round()?.unwrap();
+        assert_eq!(latest_round.timestamp, 1);
+        assert_eq!(
+            latest_round.location.transaction_hash,
+            round_one_receipt.transaction_hash
+        );
+        assert_eq!(
+            indexer.get_totals_over_time()?,
+            vec![(1, vec![U256::from(556862), U256::from(556862)])]
+        );
+        for proof in address_ownership_client.get_ownership_proofs() {
+            assert_eq!(
+                indexer
+                    .get_ownership_proof(&proof.cex_address)?
+                    .unwrap()
+                    .to_address_ownership_proof(),
+                *proof
+            );
+        }
+        assert_eq!(indexer.get_ownership_proof("unknown")?, None);
+        let synced_block = summary.last_block.unwrap();
+
+        // Submit a second round, then remove it from the chain with a reorganization.
+        let snapshot_id: U256 = provider.request("evm_snapshot", ()).await?;
+        let mut round_two = Round::<N_CURRENCIES, N_USERS>::new(
+            &signer,
+            zk_snark_proof,
+            advice_polys,
+            params,
+            vk,
+            &entries,
+            2,
+        )?;
+        let round_two_receipt = round_two.dispatch_commitment().await?;
+        indexer.sync().await?;
+        assert_eq!(indexer.get_latest_round()?.unwrap().timestamp, 2);
+
+        let reverted: bool = provider.request("evm_revert", [snapshot_id]).await?;
+        assert!(reverted);
+        provider.request::<_, ()>("anvil_mine", [3u64]).await?;
+
+        // The events of the removed blocks are rolled back, from the block following the last one still on the chain.
+        let summary = indexer.sync().await?;
+        assert_eq!(summary.rolled_back_from, Some(synced_block + 1));
+        assert!(summary.rolled_back_from.unwrap() <= round_two_receipt.block_number);
+        assert_eq!(summary.new_commitments, 0);
+        assert_eq!(indexer.get_round(2)?, None);
+        assert_eq!(indexer.get_latest_round()?.unwrap().timestamp, 1);
+        assert_eq!(indexer.get_rounds()?.len(), 1);
+
+        // The database cannot be reused for another contract.
+        drop(indexer);
+        let other_reader =
+            SummaReader::new(anvil.endpoint().as_str(), AddressInput::Address(cex_addr_1)).await?;
+        assert!(SummaIndexer::open(&db_path, &other_reader, 0).is_err());
+
+        std::fs::remove_dir_all(&db_path)?;
+        drop(anvil);
+        Ok(())
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_inclusion_proof_server() -> Result<(), Box<dyn Error>> {
+        use crate::server::{
+            InclusionProofServer, RateLimit, RoundMetadata, StaticTokenAuthenticator,
+        };
+        use reqwest::StatusCode;
+        use std::collections::HashMap;
+
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = SummaSigner::new(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let entry_csv = "../csv/entry_16.csv";
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&str, N_CURRENCIES>(entry_csv, &mut entries, &mut cryptos).unwrap();
+
+        let univariate_grand_sum_circuit = UnivariateGrandSum::<
+            N_USERS,
+            N_CURRENCIES,
+            UnivariateGrandSumConfig<N_CURRENCIES, N_USERS>,
+        >::init(entries.to_vec());
+
+        let (params, pk, vk) =
+            generate_setup_artifacts(K, Some(PARAMS_PATH), &univariate_grand_sum_circuit).unwrap();
+
+        let (zk_snark_proof, advice_polys, _omega) = full_prover(
+            &params,
+            &pk,
+            univariate_grand_sum_circuit,
+            &[vec![Fp::zero()]],
+            MultiOpenScheme::Shplonk,
+            None,
+        );
+
+        let snapshot_time = 1u64;
+        let mut round = Round::<N_CURRENCIES, N_USERS>::new(
+            &signer,
+            zk_snark_proof,
+            advice_polys,
+            params,
+            vk,
+            &entries,
+            snapshot_time,
+        )?;
+        round.dispatch_commitment().await?;
+
+        // Serve the round on an ephemeral port, allowing two inclusion proof requests per user.
+        let user_index = 3;
+        let username = entries[user_index].username().to_string();
+        let authenticator = StaticTokenAuthenticator::new(HashMap::from([(
+            "user-token".to_string(),
+            username.clone(),
+        )]));
+        let mut server = InclusionProofServer::<N_CURRENCIES, N_USERS>::new(
+            authenticator,
+            RateLimit {
+                max_requests: 2,
+                window: Duration::from_secs(3600),
+            },
+        );
+        server.add_round(round, &cryptos)?;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let base_url = format!("http://{}", listener.local_addr()?);
+        let server_handle = tokio::spawn(server.serve_on(listener));
+
+        let client = reqwest::Client::new();
+
+        // The round metadata and the commitment are public.
+        let rounds: Vec<RoundMetadata> = client
+            .get(format!("{}/rounds", base_url))
+            .send()
+            .await?
+            .json()
+            .await?;
+        assert_eq!(
+            rounds,
+            vec![RoundMetadata {
+                timestamp: snapshot_time,
+                n_currencies: N_CURRENCIES,
+                n_users: N_USERS,
+                k: K,
+            }]
+        );
+
+        let commitment = CommitmentBundle::from_json(
+            &client
+                .get(format!("{}/rounds/{}/commitment", base_url, snapshot_time))
+                .send()
+                .await?
+                .text()
+                .await?,
+        )?;
+        assert_eq!(commitment.context.timestamp, snapshot_time);
+        assert_eq!(commitment.context.summa_address, summa_contract.address());
+        assert_eq!(commitment.context.cryptocurrencies, cryptos);
+        assert_eq!(
+            commitment.total_balances,
+            vec![U256::from(556862), U256::from(556862)]
+        );
+        let onchain_commitment = summa_contract
+            .commitments(U256::from(snapshot_time))
+            .call()
+            .await?;
+        assert_eq!(
+            onchain_commitment.to_vec(),
+            commitment.snark_proof[..onchain_commitment.len()].to_vec()
+        );
+
+        let response = client.get(format!("{}/rounds/2", base_url)).send().await?;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // The inclusion proofs require a valid token.
+        let inclusion_proof_url = format!("{}/rounds/{}/inclusion_proof", base_url, snapshot_time);
+        let response = client.get(&inclusion_proof_url).send().await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = client
+            .get(&inclusion_proof_url)
+            .bearer_auth("invalid-token")
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // The authenticated user gets their own inclusion proof, which verifies on-chain.
+        let inclusion_proof_bundle = InclusionProofBundle::from_json(
+            &client
+                .get(&inclusion_proof_url)
+                .bearer_auth("user-token")
+                .send()
+                .await?
+                .text()
+                .await?,
+        )?;
+        assert_eq!(inclusion_proof_bundle.context, commitment.context);
+        let inclusion_proof = inclusion_proof_bundle.proof;
+        assert_eq!(inclusion_proof.get_user_index(), Some(user_index));
+        assert_eq!(inclusion_proof.get_username(), Some(username.as_str()));
 
+        let verified = summa_contract
+            .verify_inclusion_proof(
+                U256::from(snapshot_time),
+                inclusion_proof.get_proof().clone(),
+                inclusion_proof
+                    .get_challenge()
+                    .clone()
+                    .expect("no challenge"),
+                inclusion_proof.get_input_values().clone(),
+            )
+            .await?;
         assert!(verified);
 
+        // The third request of the user within the window is rate limited.
+        let response = client
+            .get(&inclusion_proof_url)
+            .bearer_auth("user-token")
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = client
+            .get(&inclusion_proof_url)
+            .bearer_auth("user-token")
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        server_handle.abort();
         drop(anvil);
         Ok(())
     }
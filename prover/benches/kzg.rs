@@ -13,9 +13,8 @@ use summa_solvency::{
         univariate_grand_sum::{CircuitConfig, UnivariateGrandSum},
         utils::{
             compute_h_parallel, full_prover, generate_setup_artifacts,
-            open_all_user_points_amortized, open_grand_sums, open_grand_sums_gwc,
-            open_single_user_point_amortized, open_user_points, verify_grand_sum_openings,
-            verify_user_inclusion,
+            open_all_user_points_amortized, open_grand_sums, open_single_user_point_amortized,
+            open_user_points, verify_grand_sum_openings, verify_user_inclusion, MultiOpenScheme,
         },
     },
     utils::{big_uint_to_fp, generate_dummy_entries},
@@ -70,14 +69,27 @@ fn bench_kzg<
         b.iter_batched(
             || circuit.clone(), // Setup function: clone the circuit for each iteration
             |circuit| {
-                full_prover(&params, &pk, circuit, &[vec![Fp::zero()]]);
+                full_prover(
+                    &params,
+                    &pk,
+                    circuit,
+                    &[vec![Fp::zero()]],
+                    MultiOpenScheme::Shplonk,
+                    None,
+                );
             },
             criterion::BatchSize::SmallInput, // Choose an appropriate batch size
         );
     });
 
-    let (zk_snark_proof, advice_polys, omega) =
-        full_prover(&params, &pk, circuit, &[vec![Fp::zero()]]);
+    let (zk_snark_proof, advice_polys, omega) = full_prover(
+        &params,
+        &pk,
+        circuit,
+        &[vec![Fp::zero()]],
+        MultiOpenScheme::Shplonk,
+        None,
+    );
 
     let poly_length = 1 << u64::from(K);
 
@@ -95,6 +107,8 @@ fn bench_kzg<
                         .map(|x| big_uint_to_fp(&(x)) * Fp::from(poly_length).invert().unwrap())
                         .collect::<Vec<Fp>>()
                         .as_slice(),
+                    MultiOpenScheme::Shplonk,
+                    None,
                 )
             },
             criterion::BatchSize::SmallInput,
@@ -105,7 +119,7 @@ fn bench_kzg<
         b.iter_batched(
             || 1..N_CURRENCIES + 1,
             |balance_column_range| {
-                open_grand_sums_gwc(
+                open_grand_sums(
                     &advice_polys.advice_polys,
                     &advice_polys.advice_blinds,
                     &params,
@@ -115,6 +129,8 @@ fn bench_kzg<
                         .map(|x| big_uint_to_fp(&(x)) * Fp::from(poly_length).invert().unwrap())
                         .collect::<Vec<Fp>>()
                         .as_slice(),
+                    MultiOpenScheme::Gwc,
+                    None,
                 )
             },
             criterion::BatchSize::SmallInput,
@@ -146,6 +162,8 @@ fn bench_kzg<
                                 .collect::<Vec<Fp>>()
                         })
                         .unwrap(),
+                    MultiOpenScheme::Shplonk,
+                    None,
                 )
             },
             criterion::BatchSize::SmallInput,
@@ -192,6 +210,8 @@ fn bench_kzg<
             .map(|x| big_uint_to_fp(&(x)) * Fp::from(poly_length).invert().unwrap())
             .collect::<Vec<Fp>>()
             .as_slice(),
+        MultiOpenScheme::Shplonk,
+        None,
     );
 
     c.bench_function(&verifying_grand_sum_bench_name, |b| {
@@ -210,6 +230,7 @@ fn bench_kzg<
                     &grand_sums_batch_proof,
                     poly_length,
                     balance_column_range,
+                    MultiOpenScheme::Shplonk,
                 )
             },
             criterion::BatchSize::SmallInput,
@@ -235,6 +256,8 @@ fn bench_kzg<
                     .collect::<Vec<Fp>>()
             })
             .unwrap(),
+        MultiOpenScheme::Shplonk,
+        None,
     );
 
     c.bench_function(&verifying_user_bench_name, |b| {
@@ -248,6 +271,7 @@ fn bench_kzg<
                     column_range,
                     omega,
                     user_index,
+                    MultiOpenScheme::Shplonk,
                 );
             },
             criterion::BatchSize::SmallInput,
@@ -17,7 +17,10 @@ use std::{fs::File, io::Write};
 use summa_solvency::{
     circuits::{
         univariate_grand_sum::{UnivariateGrandSum, UnivariateGrandSumConfig},
-        utils::{full_prover, full_verifier, generate_setup_artifacts},
+        utils::{
+            full_prover, full_verifier, generate_setup_artifacts, parse_proving_seed,
+            MultiOpenScheme,
+        },
     },
     cryptocurrency::Cryptocurrency,
     entry::Entry,
@@ -38,6 +41,13 @@ struct CommitmentSolidityCallData {
     total_balances: Vec<U256>,
 }
 
+// The seed of the prover randomness, allowing the Auditor to regenerate the same proof from the same inputs.
+// It reveals the blinding factors of the proof, so it is written to a separate private file.
+#[derive(Serialize, Deserialize)]
+struct AuditorProvingSeed {
+    proving_seed: String,
+}
+
 #[derive(Serialize, Deserialize)]
 struct InclusionProofCallData {
     proof: String,
@@ -47,6 +57,24 @@ struct InclusionProofCallData {
 }
 
 fn main() {
+    // The multi-open scheme has to match the one of the SNARK verifier contract generated by `generate_verifier`
+    let scheme: MultiOpenScheme = std::env::args()
+        .nth(1)
+        .map(|arg| arg.parse().unwrap())
+        .unwrap_or_default();
+
+    // An optional 32-byte seed, given as 64 hexadecimal characters, makes the generated proof reproducible
+    let proving_seed = std::env::args()
+        .nth(2)
+        .map(|arg| {
+            parse_proving_seed(&arg).unwrap_or_else(|e| {
+                panic!(
+                    "Invalid proving seed, expected 64 hexadecimal characters optionally prefixed with 0x: {}",
+                    e
+                )
+            })
+        });
+
     // Initialize with entries
     let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
     let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
@@ -75,6 +103,8 @@ fn main() {
         &pk,
         univariate_grand_sum_circuit.clone(),
         &[instances.clone()],
+        scheme,
+        proving_seed,
     );
 
     // Verify the proof to ensure validity
@@ -82,7 +112,8 @@ fn main() {
         &params,
         pk.get_vk(),
         &zk_snark_proof,
-        &[instances]
+        &[instances],
+        scheme
     ));
 
     let challenge = Fp::zero();
@@ -158,6 +189,21 @@ fn main() {
     file.write_all(serialized_data.as_bytes())
         .expect("Unable to write data to file");
 
+    // Save the proving seed apart from the public calldata, to be handed over to the Auditor only
+    if let Some(proving_seed) = proving_seed {
+        let auditor_seed = AuditorProvingSeed {
+            proving_seed: format!("0x{}", hex::encode(proving_seed)),
+        };
+        let mut file =
+            File::create("./bin/auditor_proving_seed.json").expect("Unable to create file");
+        file.write_all(
+            to_string_pretty(&auditor_seed)
+                .expect("Failed to serialize data")
+                .as_bytes(),
+        )
+        .expect("Unable to write data to file");
+    }
+
     // For testing, open user balances and generate a proof for a specific user index
     let user_index = 1_u16; // Example user index for proof generation
     let challenge = omega.pow_vartime([user_index as u64]);
@@ -1,22 +1,11 @@
 #![feature(generic_const_exprs)]
-use halo2_proofs::{
-    halo2curves::bn256::Fr as Fp,
-    poly::kzg::{
-        multiopen::{ProverSHPLONK, VerifierSHPLONK},
-        strategy::SingleStrategy,
-    },
-    transcript::TranscriptWriterBuffer,
-};
-use halo2_solidity_verifier::{
-    compile_solidity, encode_calldata, BatchOpenScheme::Bdfg21, Evm, Keccak256Transcript,
-    SolidityGenerator,
-};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use halo2_solidity_verifier::{compile_solidity, encode_calldata, Evm, SolidityGenerator};
 use prelude::*;
-use rand::rngs::OsRng;
 use summa_solvency::{
     circuits::{
         univariate_grand_sum::{UnivariateGrandSum, UnivariateGrandSumConfig},
-        utils::generate_setup_artifacts,
+        utils::{full_prover, full_verifier, generate_setup_artifacts, MultiOpenScheme},
     },
     cryptocurrency::Cryptocurrency,
     entry::Entry,
@@ -28,6 +17,13 @@ const N_CURRENCIES: usize = 2;
 const N_USERS: usize = 16;
 
 fn main() {
+    // The multi-open scheme can be passed as an argument, e.g. `cargo run --bin generate_verifier -- gwc`.
+    // The SNARK proofs verified by the generated contract have to be generated with the same scheme.
+    let scheme: MultiOpenScheme = std::env::args()
+        .nth(1)
+        .map(|arg| arg.parse().unwrap())
+        .unwrap_or_default();
+
     // In order to generate the verifier we create the circuit using the init_empty() method, which means that the circuit is not initialized with any data.
     let circuit = UnivariateGrandSum::<
         N_USERS,
@@ -56,7 +52,7 @@ fn main() {
     // the instance value is not used in proving, but it is necessary to SolidityGenerator and it should at least 1.
     let num_instance = 1_usize;
     let generator: SolidityGenerator<'_> =
-        SolidityGenerator::new(&params, pk.get_vk(), Bdfg21, num_instance);
+        SolidityGenerator::new(&params, pk.get_vk(), scheme.into(), num_instance);
     let (verifier_solidity, vk_verifier) = generator.render_separately().unwrap();
 
     let verifier_solidity_fixed = verifier_solidity
@@ -70,35 +66,23 @@ fn main() {
     //
     // the instance values has to be at least more than one due to verifier contract that generated from SolidityGenerator.
     let instances: Vec<Fr> = vec![Fp::zero(); 1];
-    let mut transcript = Keccak256Transcript::new(Vec::new());
-
-    let result = create_proof::<_, ProverSHPLONK<_>, _, _, _, _>(
+    let (zk_snark_proof, _, _) = full_prover(
         &params,
         &pk,
-        &[univariate_grand_sum_circuit],
-        &[&[&instances]],
-        &mut OsRng,
-        &mut transcript,
+        univariate_grand_sum_circuit,
+        &[instances.clone()],
+        scheme,
+        None,
     );
-    assert!(result.is_ok());
-
-    let result_unwrapped = result.unwrap();
-    result_unwrapped.0.expect("prover should not fail");
-
-    let zk_snark_proof = transcript.finalize();
 
     // Check verification on verifier function
-    let verified = {
-        let mut transcript = Keccak256Transcript::new(zk_snark_proof.as_slice());
-        verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
-            &params,
-            pk.get_vk(),
-            SingleStrategy::new(&params),
-            &[&[&instances]],
-            &mut transcript,
-        )
-    };
-    assert!(verified.is_ok());
+    assert!(full_verifier(
+        &params,
+        pk.get_vk(),
+        &zk_snark_proof,
+        &[instances.clone()],
+        scheme
+    ));
 
     // 3. Deploy Snark Verifier Contract and verify snark proof
     let mut evm = Evm::default();
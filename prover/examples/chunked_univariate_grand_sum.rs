@@ -14,7 +14,7 @@ use summa_solvency::circuits::utils::generate_setup_artifacts;
 use summa_solvency::{
     circuits::{
         univariate_grand_sum::{NoRangeCheckConfig, UnivariateGrandSum},
-        utils::full_prover,
+        utils::{full_prover, MultiOpenScheme},
     },
     cryptocurrency::Cryptocurrency,
     entry::Entry,
@@ -85,8 +85,22 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // The zkSNARK proofs encode the balances of the first chunk and the second chunk
     // in the corresponding advice polynomials
-    let (proof_1, advice_polys_1, _) = full_prover(&params, &pk, circuit_1.clone(), &[vec![]]);
-    let (proof_2, advice_polys_2, _) = full_prover(&params, &pk, circuit_2.clone(), &[vec![]]);
+    let (proof_1, advice_polys_1, _) = full_prover(
+        &params,
+        &pk,
+        circuit_1.clone(),
+        &[vec![]],
+        MultiOpenScheme::Shplonk,
+        None,
+    );
+    let (proof_2, advice_polys_2, _) = full_prover(
+        &params,
+        &pk,
+        circuit_2.clone(),
+        &[vec![]],
+        MultiOpenScheme::Shplonk,
+        None,
+    );
 
     // Get the BALANCES_INDEX advice polynomial from each chunk
     let f_poly_1 = advice_polys_1.advice_polys.get(BALANCES_INDEX).unwrap();
@@ -0,0 +1,282 @@
+use std::fmt;
+
+use halo2_proofs::{
+    arithmetic::best_fft,
+    halo2curves::{
+        bn256::{Bn256, Fr as Fp, G1Affine},
+        group::Curve,
+    },
+    plonk::{AdviceSingle, VerifyingKey},
+    poly::{kzg::commitment::ParamsKZG, Coeff, Polynomial},
+};
+
+use crate::{
+    circuits::utils::{Keccak, TranscriptType},
+    cryptocurrency::Cryptocurrency,
+    entry::Entry,
+    utils::{amortized_kzg::commit_kzg, big_uint_to_fp},
+};
+
+/// An advice column whose commitment recomputed from the raw data differs from the one in the zkSNARK proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitmentMismatch {
+    /// The index of the advice column, the username column being at index 0
+    pub column_index: usize,
+    /// The name of the column, as in the header of the entry CSV file
+    pub column_name: String,
+    /// The commitment recomputed from the entries and the blinding values
+    pub recomputed: G1Affine,
+    /// The commitment read from the zkSNARK proof transcript
+    pub committed: G1Affine,
+}
+
+impl fmt::Display for CommitmentMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Commitment mismatch in column {} ({}): recomputed {:?}, committed {:?}",
+            self.column_index, self.column_name, self.recomputed, self.committed
+        )
+    }
+}
+
+/// Returns the number of rows at the end of the advice columns that the prover fills with random values.
+///
+/// Only the blinded advice columns (i.e., the username column) get random values, the unblinded balance columns are zero in these rows.
+pub fn blinding_rows(vk: &VerifyingKey<G1Affine>) -> usize {
+    vk.cs().blinding_factors() + 1
+}
+
+/// Extracts the values of the blinding rows of the advice columns from the advice polynomials generated by the prover.
+///
+/// The Custodian hands these values over to the Auditor along with the entries, so that the Auditor can recompute the commitments.
+///
+/// # Arguments
+///
+/// * `vk` - the verifying key of the circuit
+/// * `advice_polys` - the advice polynomials returned by the prover
+/// * `n_columns` - the number of advice columns of interest, i.e., the username column and the balance columns
+///
+/// # Returns
+///
+/// * `Vec<Vec<Fp>>` - the values of the blinding rows, one vector per advice column
+pub fn extract_blinding_values(
+    vk: &VerifyingKey<G1Affine>,
+    advice_polys: &AdviceSingle<G1Affine, Coeff>,
+    n_columns: usize,
+) -> Vec<Vec<Fp>> {
+    let domain = vk.get_domain();
+    let k = domain.k();
+    let n_blinding_rows = blinding_rows(vk);
+
+    advice_polys.advice_polys[..n_columns]
+        .iter()
+        .map(|poly| {
+            // Evaluate the polynomial over the domain to get back the column values
+            let mut values = poly.to_vec();
+            best_fft(&mut values, domain.get_omega(), k);
+            values.split_off(values.len() - n_blinding_rows)
+        })
+        .collect()
+}
+
+/// Rebuilds the advice polynomials of the username and balance columns from the entries and the values of the blinding rows.
+///
+/// # Arguments
+///
+/// * `vk` - the verifying key of the circuit
+/// * `entries` - the user entries, assigned to the rows of the columns in order
+/// * `blinding_values` - the values of the blinding rows, one vector per column; an empty vector stands for an unblinded column
+///
+/// # Returns
+///
+/// * `Vec<Polynomial<Fp, Coeff>>` - the username polynomial followed by the balance polynomials
+pub fn rebuild_advice_polys<const N_CURRENCIES: usize>(
+    vk: &VerifyingKey<G1Affine>,
+    entries: &[Entry<N_CURRENCIES>],
+    blinding_values: &[Vec<Fp>],
+) -> Result<Vec<Polynomial<Fp, Coeff>>, &'static str> {
+    let domain = vk.get_domain();
+    let n = 1 << domain.k();
+    let n_blinding_rows = blinding_rows(vk);
+
+    if blinding_values.len() != N_CURRENCIES + 1 {
+        return Err("Blinding values should be provided for the username and balance columns");
+    }
+    if entries.len() > n - n_blinding_rows {
+        return Err("Too many entries for the circuit size");
+    }
+
+    (0..N_CURRENCIES + 1)
+        .map(|column_index| {
+            let mut values = vec![Fp::zero(); n];
+            for (row, entry) in entries.iter().enumerate() {
+                values[row] = if column_index == 0 {
+                    big_uint_to_fp(entry.username_as_big_uint())
+                } else {
+                    big_uint_to_fp(&entry.balances()[column_index - 1])
+                };
+            }
+
+            let column_blinding_values = &blinding_values[column_index];
+            if !column_blinding_values.is_empty() {
+                if column_blinding_values.len() != n_blinding_rows {
+                    return Err("Invalid number of blinding values");
+                }
+                values[n - n_blinding_rows..].copy_from_slice(column_blinding_values);
+            }
+
+            Ok(domain.lagrange_to_coeff(domain.lagrange_from_vec(values)))
+        })
+        .collect()
+}
+
+/// Recomputes the commitments to the username and balance columns from the raw data
+/// and compares them against the advice commitments of the zkSNARK proof.
+///
+/// The KZG commitments do not depend on the polynomial blinds, only on the values of the blinding rows,
+/// which is why the Custodian only needs to disclose the latter for the username column (see [`extract_blinding_values`]).
+///
+/// # Arguments
+///
+/// * `params` - the KZG parameters
+/// * `vk` - the verifying key of the circuit
+/// * `zk_snark_proof` - the zkSNARK proof, e.g., as submitted on-chain
+/// * `entries` - the user entries
+/// * `cryptocurrencies` - the cryptocurrencies of the balance columns, used to name the columns in the report
+/// * `blinding_values` - the values of the blinding rows, one vector per column; an empty vector stands for an unblinded column
+///
+/// # Returns
+///
+/// * `Vec<CommitmentMismatch>` - the columns whose commitments differ, empty if all the commitments match
+pub fn audit_advice_commitments<const N_CURRENCIES: usize>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    zk_snark_proof: &[u8],
+    entries: &[Entry<N_CURRENCIES>],
+    cryptocurrencies: &[Cryptocurrency],
+    blinding_values: &[Vec<Fp>],
+) -> Result<Vec<CommitmentMismatch>, &'static str> {
+    if cryptocurrencies.len() != N_CURRENCIES {
+        return Err("The number of cryptocurrencies should match the number of balance columns");
+    }
+
+    let committed = Keccak::read_points(zk_snark_proof, N_CURRENCIES + 1)
+        .map_err(|_| "Failed to read the advice commitments from the proof")?;
+    let advice_polys = rebuild_advice_polys(vk, entries, blinding_values)?;
+
+    Ok(advice_polys
+        .iter()
+        .zip(committed)
+        .enumerate()
+        .filter_map(|(column_index, (poly, committed))| {
+            let recomputed = commit_kzg(params, poly).to_affine();
+            (recomputed != committed).then(|| CommitmentMismatch {
+                column_index,
+                column_name: if column_index == 0 {
+                    "username".to_owned()
+                } else {
+                    let cryptocurrency = &cryptocurrencies[column_index - 1];
+                    format!("balance_{}_{}", cryptocurrency.name, cryptocurrency.chain)
+                },
+                recomputed,
+                committed,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::univariate_grand_sum::{NoRangeCheckConfig, UnivariateGrandSum};
+    use crate::circuits::utils::{full_prover, generate_setup_artifacts, MultiOpenScheme};
+    use crate::utils::parse_csv_to_entries;
+    use num_bigint::BigUint;
+
+    const N_CURRENCIES: usize = 2;
+    const N_USERS: usize = 16;
+
+    #[test]
+    fn test_audit_advice_commitments() {
+        let mut entries: Vec<Entry<N_CURRENCIES>> = vec![Entry::init_empty(); N_USERS];
+        let mut cryptos = vec![Cryptocurrency::init_empty(); N_CURRENCIES];
+        parse_csv_to_entries::<&str, N_CURRENCIES>(
+            "../csv/entry_16.csv",
+            &mut entries,
+            &mut cryptos,
+        )
+        .unwrap();
+
+        let circuit = UnivariateGrandSum::<
+            N_USERS,
+            N_CURRENCIES,
+            NoRangeCheckConfig<N_CURRENCIES, N_USERS>,
+        >::init(entries.clone());
+        let (params, pk, vk) = generate_setup_artifacts(9, None, &circuit).unwrap();
+        let (zk_snark_proof, advice_polys, _) = full_prover(
+            &params,
+            &pk,
+            circuit,
+            &[vec![Fp::zero()]],
+            MultiOpenScheme::Shplonk,
+            None,
+        );
+
+        // The Custodian discloses the blinding values of the username column only, the balance columns being unblinded
+        let mut blinding_values = extract_blinding_values(&vk, &advice_polys, N_CURRENCIES + 1);
+        assert!(blinding_values[1..]
+            .iter()
+            .all(|values| values.iter().all(|value| *value == Fp::zero())));
+        blinding_values[1..].iter_mut().for_each(Vec::clear);
+
+        let mismatches = audit_advice_commitments(
+            &params,
+            &vk,
+            &zk_snark_proof,
+            &entries,
+            &cryptos,
+            &blinding_values,
+        )
+        .unwrap();
+        assert!(mismatches.is_empty());
+
+        // A tampered balance is reported in its column only
+        let mut tampered_entries = entries.clone();
+        let mut balances = tampered_entries[5].balances().clone();
+        balances[1] += BigUint::from(1u32);
+        tampered_entries[5] =
+            Entry::new(tampered_entries[5].username().to_owned(), balances).unwrap();
+        let mismatches = audit_advice_commitments(
+            &params,
+            &vk,
+            &zk_snark_proof,
+            &tampered_entries,
+            &cryptos,
+            &blinding_values,
+        )
+        .unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].column_index, 2);
+        assert_eq!(
+            mismatches[0].column_name,
+            format!("balance_{}_{}", cryptos[1].name, cryptos[1].chain)
+        );
+
+        // Without the blinding values, the username commitment cannot be recomputed
+        let mut missing_blinding_values = blinding_values.clone();
+        missing_blinding_values[0].clear();
+        let mismatches = audit_advice_commitments(
+            &params,
+            &vk,
+            &zk_snark_proof,
+            &entries,
+            &cryptos,
+            &missing_blinding_values,
+        )
+        .unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].column_index, 0);
+        assert_eq!(mismatches[0].column_name, "username");
+    }
+}
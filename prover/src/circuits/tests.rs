@@ -5,9 +5,13 @@ mod test {
         CircuitConfig, NoRangeCheckConfig, UnivariateGrandSum, UnivariateGrandSumConfig,
     };
     use crate::circuits::utils::{
-        compute_h_parallel, full_prover, full_verifier, generate_setup_artifacts,
-        open_all_user_points_amortized, open_grand_sums, open_single_user_point_amortized,
-        open_user_points, verify_grand_sum_openings, verify_user_inclusion,
+        compute_h_parallel, full_prover, full_prover_with_transcript, full_verifier,
+        full_verifier_with_transcript, generate_setup_artifacts, open_all_user_points_amortized,
+        open_grand_sums, open_grand_sums_with_transcript, open_single_user_point_amortized,
+        open_user_points, open_user_points_with_transcript, parse_proving_seed,
+        verify_grand_sum_openings, verify_grand_sum_openings_with_transcript,
+        verify_user_inclusion, verify_user_inclusion_with_transcript, Keccak, MultiOpenScheme,
+        Poseidon, ProvingSeed,
     };
     use crate::cryptocurrency::Cryptocurrency;
     use crate::entry::Entry;
@@ -17,8 +21,9 @@ mod test {
     use halo2_proofs::arithmetic::Field;
     use halo2_proofs::dev::{FailureLocation, MockProver, VerifyFailure};
     use halo2_proofs::halo2curves::bn256::{Bn256, Fr as Fp, G1Affine};
-    use halo2_proofs::plonk::{Any, ProvingKey, VerifyingKey};
+    use halo2_proofs::plonk::{AdviceSingle, Any, ProvingKey, VerifyingKey};
     use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+    use halo2_proofs::poly::Coeff;
     use num_bigint::BigUint;
     use rand::rngs::OsRng;
     use rand::Rng;
@@ -26,6 +31,7 @@ mod test {
     const K: u32 = 17;
     const N_CURRENCIES: usize = 2;
     const N_USERS: usize = 16;
+    const NO_RANGE_CHECK_K: u32 = 9;
 
     #[test]
     fn test_amortized_kzg() {
@@ -34,8 +40,14 @@ mod test {
         let (entries, circuit, pk, _, params) =
             set_up::<9, N_USERS, N_CURRENCIES, NoRangeCheckConfig<N_CURRENCIES, N_USERS>>(path);
 
-        let (_, advice_polys, omega) =
-            full_prover(&params, &pk, circuit.clone(), &[vec![Fp::zero()]]);
+        let (_, advice_polys, omega) = full_prover(
+            &params,
+            &pk,
+            circuit.clone(),
+            &[vec![Fp::zero()]],
+            MultiOpenScheme::Shplonk,
+            None,
+        );
 
         // Select the first user balance polynomial for the example
         let f_poly = advice_polys.advice_polys.get(1).unwrap();
@@ -184,8 +196,14 @@ mod test {
         // 1. Proving phase
         // The Custodian generates the ZK-SNARK Halo2 proof that commits to the user entry values in advice polynomials
         // and also range-checks the user balance values
-        let (zk_snark_proof, advice_polys, omega) =
-            full_prover(&params, &pk, circuit.clone(), &[vec![Fp::zero()]]);
+        let (zk_snark_proof, advice_polys, omega) = full_prover(
+            &params,
+            &pk,
+            circuit.clone(),
+            &[vec![Fp::zero()]],
+            MultiOpenScheme::Shplonk,
+            None,
+        );
 
         // Both the Custodian and the Verifier know what column range are the balance columns
         // (The first column is the user IDs)
@@ -206,6 +224,8 @@ mod test {
                 .map(|x| big_uint_to_fp(&(x)) * Fp::from(poly_length).invert().unwrap())
                 .collect::<Vec<Fp>>()
                 .as_slice(),
+            MultiOpenScheme::Shplonk,
+            None,
         );
 
         // The Custodian creates a KZG batch proof of the 4th user ID & balances inclusion
@@ -227,6 +247,8 @@ mod test {
                         .collect::<Vec<Fp>>()
                 })
                 .unwrap(),
+            MultiOpenScheme::Shplonk,
+            None,
         );
 
         // 2. Verification phase
@@ -235,7 +257,8 @@ mod test {
             &params,
             &vk,
             &zk_snark_proof,
-            &[vec![Fp::zero()]]
+            &[vec![Fp::zero()]],
+            MultiOpenScheme::Shplonk
         ));
 
         // The Verifier is able to independently extract the omega from the verification key
@@ -255,6 +278,7 @@ mod test {
             &grand_sums_batch_proof,
             poly_length,
             balance_column_range,
+            MultiOpenScheme::Shplonk,
         );
 
         assert!(verified);
@@ -271,6 +295,7 @@ mod test {
             column_range,
             omega,
             user_index,
+            MultiOpenScheme::Shplonk,
         );
 
         assert!(inclusion_verified);
@@ -290,6 +315,294 @@ mod test {
         }
     }
 
+    // Every proof and opening round-trips under both multi-open schemes, but is rejected under the other one
+    #[test]
+    fn test_multi_open_schemes_round_trip() {
+        let (entries, circuit, pk, vk, params) = set_up_no_range_check();
+
+        let mut csv_total: Vec<BigUint> = vec![BigUint::from(0u32); N_CURRENCIES];
+        for entry in &entries {
+            for (i, balance) in entry.balances().iter().enumerate() {
+                csv_total[i] += balance;
+            }
+        }
+        let poly_length = 1 << NO_RANGE_CHECK_K;
+        let user_index = 3_u16;
+        let user_values = std::iter::once(big_uint_to_fp(entries[3].username_as_big_uint()))
+            .chain(entries[3].balances().iter().map(big_uint_to_fp))
+            .collect::<Vec<Fp>>();
+
+        for (scheme, other_scheme) in [
+            (MultiOpenScheme::Shplonk, MultiOpenScheme::Gwc),
+            (MultiOpenScheme::Gwc, MultiOpenScheme::Shplonk),
+        ] {
+            let (zk_snark_proof, advice_polys, omega) = full_prover(
+                &params,
+                &pk,
+                circuit.clone(),
+                &[vec![Fp::zero()]],
+                scheme,
+                None,
+            );
+            assert!(full_verifier(
+                &params,
+                &vk,
+                &zk_snark_proof,
+                &[vec![Fp::zero()]],
+                scheme
+            ));
+            assert!(!full_verifier(
+                &params,
+                &vk,
+                &zk_snark_proof,
+                &[vec![Fp::zero()]],
+                other_scheme
+            ));
+
+            let grand_sums_batch_proof = open_grand_sums(
+                &advice_polys.advice_polys,
+                &advice_polys.advice_blinds,
+                &params,
+                1..N_CURRENCIES + 1,
+                csv_total
+                    .iter()
+                    .map(|x| big_uint_to_fp(x) * Fp::from(poly_length).invert().unwrap())
+                    .collect::<Vec<Fp>>()
+                    .as_slice(),
+                scheme,
+                None,
+            );
+            let (verified, grand_sum) = verify_grand_sum_openings::<N_CURRENCIES>(
+                &params,
+                &zk_snark_proof,
+                &grand_sums_batch_proof,
+                poly_length,
+                1..N_CURRENCIES + 1,
+                scheme,
+            );
+            assert!(verified);
+            assert_eq!(csv_total, grand_sum);
+            let (verified, _) = verify_grand_sum_openings::<N_CURRENCIES>(
+                &params,
+                &zk_snark_proof,
+                &grand_sums_batch_proof,
+                poly_length,
+                1..N_CURRENCIES + 1,
+                other_scheme,
+            );
+            assert!(!verified);
+
+            let openings_batch_proof = open_user_points(
+                &advice_polys.advice_polys,
+                &advice_polys.advice_blinds,
+                &params,
+                0..N_CURRENCIES + 1,
+                omega,
+                user_index,
+                &user_values,
+                scheme,
+                None,
+            );
+            let (inclusion_verified, id_and_balance_values) = verify_user_inclusion(
+                &params,
+                &zk_snark_proof,
+                &openings_batch_proof,
+                0..N_CURRENCIES + 1,
+                omega,
+                user_index,
+                scheme,
+            );
+            assert!(inclusion_verified);
+            assert_eq!(*entries[3].username_as_big_uint(), id_and_balance_values[0]);
+            let (inclusion_verified, _) = verify_user_inclusion(
+                &params,
+                &zk_snark_proof,
+                &openings_batch_proof,
+                0..N_CURRENCIES + 1,
+                omega,
+                user_index,
+                other_scheme,
+            );
+            assert!(!inclusion_verified);
+        }
+    }
+
+    // The same inputs and seed yield byte-identical proofs, while the unseeded proofs are randomized
+    #[test]
+    fn test_seeded_prover_is_deterministic() {
+        let (entries, circuit, pk, vk, params) = set_up_no_range_check();
+        let scheme = MultiOpenScheme::Shplonk;
+
+        let prove = |seed: Option<ProvingSeed>| {
+            full_prover(
+                &params,
+                &pk,
+                circuit.clone(),
+                &[vec![Fp::zero()]],
+                scheme,
+                seed,
+            )
+        };
+
+        let (proof_1, advice_polys_1, omega) = prove(Some([42; 32]));
+        let (proof_2, advice_polys_2, _) = prove(Some([42; 32]));
+        assert_eq!(proof_1, proof_2);
+        assert!(full_verifier(
+            &params,
+            &vk,
+            &proof_1,
+            &[vec![Fp::zero()]],
+            scheme
+        ));
+
+        // A different seed or no seed at all changes the blinding factors, hence the proof
+        assert_ne!(proof_1, prove(Some([43; 32])).0);
+        assert_ne!(prove(None).0, prove(None).0);
+
+        let user_values = std::iter::once(big_uint_to_fp(entries[3].username_as_big_uint()))
+            .chain(entries[3].balances().iter().map(big_uint_to_fp))
+            .collect::<Vec<Fp>>();
+        let open = |advice_polys: &AdviceSingle<G1Affine, Coeff>, seed: Option<ProvingSeed>| {
+            open_user_points(
+                &advice_polys.advice_polys,
+                &advice_polys.advice_blinds,
+                &params,
+                0..N_CURRENCIES + 1,
+                omega,
+                3,
+                &user_values,
+                scheme,
+                seed,
+            )
+        };
+        let opening_proof = open(&advice_polys_1, Some([7; 32]));
+        assert_eq!(opening_proof, open(&advice_polys_2, Some([7; 32])));
+        assert!(
+            verify_user_inclusion(
+                &params,
+                &proof_1,
+                &opening_proof,
+                0..N_CURRENCIES + 1,
+                omega,
+                3,
+                scheme
+            )
+            .0
+        );
+    }
+
+    #[test]
+    fn test_parse_proving_seed() {
+        let seed = "0x0101010101010101010101010101010101010101010101010101010101010101";
+        assert_eq!(parse_proving_seed(seed).unwrap(), [1; 32]);
+        assert_eq!(parse_proving_seed(&seed[2..]).unwrap(), [1; 32]);
+
+        // A short seed would make the blinding factors guessable
+        assert!(parse_proving_seed("42").is_err());
+        assert!(parse_proving_seed(&format!("{}00", seed)).is_err());
+        assert!(parse_proving_seed("not a seed").is_err());
+    }
+
+    // The proofs generated with the Poseidon transcript are verified with the same transcript only
+    #[test]
+    fn test_poseidon_transcript_full_prover() {
+        let (entries, circuit, pk, vk, params) = set_up_no_range_check();
+
+        let mut csv_total: Vec<BigUint> = vec![BigUint::from(0u32); N_CURRENCIES];
+        for entry in &entries {
+            for (i, balance) in entry.balances().iter().enumerate() {
+                csv_total[i] += balance;
+            }
+        }
+        let poly_length = 1 << NO_RANGE_CHECK_K;
+        let scheme = MultiOpenScheme::Shplonk;
+
+        let (zk_snark_proof, advice_polys, omega) = full_prover_with_transcript::<Poseidon, _>(
+            &params,
+            &pk,
+            circuit,
+            &[vec![Fp::zero()]],
+            scheme,
+            None,
+        );
+        assert!(full_verifier_with_transcript::<Poseidon>(
+            &params,
+            &vk,
+            &zk_snark_proof,
+            &[vec![Fp::zero()]],
+            scheme
+        ));
+        assert!(!full_verifier_with_transcript::<Keccak>(
+            &params,
+            &vk,
+            &zk_snark_proof,
+            &[vec![Fp::zero()]],
+            scheme
+        ));
+
+        let grand_sums_batch_proof = open_grand_sums_with_transcript::<Poseidon>(
+            &advice_polys.advice_polys,
+            &advice_polys.advice_blinds,
+            &params,
+            1..N_CURRENCIES + 1,
+            csv_total
+                .iter()
+                .map(|x| big_uint_to_fp(x) * Fp::from(poly_length).invert().unwrap())
+                .collect::<Vec<Fp>>()
+                .as_slice(),
+            scheme,
+            None,
+        );
+        let (verified, grand_sum) =
+            verify_grand_sum_openings_with_transcript::<Poseidon, N_CURRENCIES>(
+                &params,
+                &zk_snark_proof,
+                &grand_sums_batch_proof,
+                poly_length,
+                1..N_CURRENCIES + 1,
+                scheme,
+            );
+        assert!(verified);
+        assert_eq!(csv_total, grand_sum);
+        let (verified, _) = verify_grand_sum_openings::<N_CURRENCIES>(
+            &params,
+            &zk_snark_proof,
+            &grand_sums_batch_proof,
+            poly_length,
+            1..N_CURRENCIES + 1,
+            scheme,
+        );
+        assert!(!verified);
+
+        let user_index = 3_u16;
+        let openings_batch_proof = open_user_points_with_transcript::<Poseidon>(
+            &advice_polys.advice_polys,
+            &advice_polys.advice_blinds,
+            &params,
+            0..N_CURRENCIES + 1,
+            omega,
+            user_index,
+            &std::iter::once(big_uint_to_fp(entries[3].username_as_big_uint()))
+                .chain(entries[3].balances().iter().map(big_uint_to_fp))
+                .collect::<Vec<Fp>>(),
+            scheme,
+            None,
+        );
+        let (inclusion_verified, id_and_balance_values) =
+            verify_user_inclusion_with_transcript::<Poseidon>(
+                &params,
+                &zk_snark_proof,
+                &openings_batch_proof,
+                0..N_CURRENCIES + 1,
+                omega,
+                user_index,
+                scheme,
+            );
+        assert!(inclusion_verified);
+        assert_eq!(*entries[3].username_as_big_uint(), id_and_balance_values[0]);
+        assert_eq!(entries[3].balances()[0], id_and_balance_values[1]);
+    }
+
     // The prover communicates an invalid omega to the verifier, therefore the opening proof of user inclusion should fail
     #[test]
     fn test_invalid_omega_univariate_grand_sum_proof() {
@@ -302,8 +615,14 @@ mod test {
 
         // 1. Proving phase
         // The Custodian generates the ZK proof
-        let (zk_snark_proof, advice_polys, omega) =
-            full_prover(&params, &pk, circuit.clone(), &[vec![Fp::zero()]]);
+        let (zk_snark_proof, advice_polys, omega) = full_prover(
+            &params,
+            &pk,
+            circuit.clone(),
+            &[vec![Fp::zero()]],
+            MultiOpenScheme::Shplonk,
+            None,
+        );
 
         // The Custodian creates a KZG batch proof of the 4th user ID & balances inclusion
         let user_index = 3_u16;
@@ -324,6 +643,8 @@ mod test {
                         .collect::<Vec<Fp>>()
                 })
                 .unwrap(),
+            MultiOpenScheme::Shplonk,
+            None,
         );
 
         // 2. Verification phase
@@ -332,7 +653,8 @@ mod test {
             &params,
             &vk,
             &zk_snark_proof,
-            &[vec![Fp::zero()]]
+            &[vec![Fp::zero()]],
+            MultiOpenScheme::Shplonk
         ));
 
         // The Verifier is able to independently extract the omega from the verification key
@@ -351,6 +673,7 @@ mod test {
             balance_column_range,
             bad_omega,
             user_index,
+            MultiOpenScheme::Shplonk,
         );
         //The verification should fail
         assert!(!balances_verified);
@@ -378,8 +701,14 @@ mod test {
         // 1. Proving phase
         // The Custodian generates the ZK-SNARK Halo2 proof that commits to the user entry values in advice polynomials
         // and also range-checks the user balance values
-        let (zk_snark_proof, advice_polys, _) =
-            full_prover(&params, &pk, circuit.clone(), &[vec![Fp::zero()]]);
+        let (zk_snark_proof, advice_polys, _) = full_prover(
+            &params,
+            &pk,
+            circuit.clone(),
+            &[vec![Fp::zero()]],
+            MultiOpenScheme::Shplonk,
+            None,
+        );
 
         // Both the Custodian and the Verifier know what column range are the balance columns
         // (The first column is the user IDs)
@@ -399,6 +728,8 @@ mod test {
                 .map(|x| big_uint_to_fp(&(x)) * Fp::from(poly_length).invert().unwrap())
                 .collect::<Vec<Fp>>()
                 .as_slice(),
+            MultiOpenScheme::Shplonk,
+            None,
         );
 
         // 2. Verification phase
@@ -407,7 +738,8 @@ mod test {
             &params,
             &vk,
             &zk_snark_proof,
-            &[vec![Fp::zero()]]
+            &[vec![Fp::zero()]],
+            MultiOpenScheme::Shplonk
         ));
 
         // The Custodian communicates the (invalid) polynomial length to the Verifier
@@ -424,6 +756,7 @@ mod test {
             &grand_sums_batch_proof,
             invalid_poly_length,
             balance_column_range,
+            MultiOpenScheme::Shplonk,
         );
 
         // The opened grand sum is not equal to the actual sum of balances extracted from the csv file
@@ -620,4 +953,17 @@ mod test {
 
         (entries, circuit, pk, vk, params)
     }
+
+    // Sets up the circuit without the range check over the 16 entries of `entry_16.csv`, which fits in a smaller K
+    fn set_up_no_range_check() -> (
+        Vec<Entry<N_CURRENCIES>>,
+        UnivariateGrandSum<N_USERS, N_CURRENCIES, NoRangeCheckConfig<N_CURRENCIES, N_USERS>>,
+        ProvingKey<G1Affine>,
+        VerifyingKey<G1Affine>,
+        ParamsKZG<Bn256>,
+    ) {
+        set_up::<NO_RANGE_CHECK_K, N_USERS, N_CURRENCIES, NoRangeCheckConfig<N_CURRENCIES, N_USERS>>(
+            "../csv/entry_16.csv",
+        )
+    }
 }
@@ -1,4 +1,4 @@
-use std::{fs::File, ops::Range};
+use std::{fmt, fs::File, io, ops::Range, str::FromStr};
 
 use ark_std::{end_timer, start_timer};
 use ethers::types::U256;
@@ -17,19 +17,274 @@ use halo2_proofs::{
         commitment::{Blind, CommitmentScheme, Params, Prover, Verifier},
         kzg::{
             commitment::{KZGCommitmentScheme, ParamsKZG},
-            multiopen::{ProverGWC, ProverSHPLONK, VerifierSHPLONK},
+            multiopen::{ProverGWC, ProverSHPLONK, VerifierGWC, VerifierSHPLONK},
             strategy::SingleStrategy,
         },
         Coeff, Polynomial, ProverQuery, VerificationStrategy, VerifierQuery,
     },
     transcript::{EncodedChallenge, TranscriptRead, TranscriptWriterBuffer},
 };
-use halo2_solidity_verifier::Keccak256Transcript;
+use halo2_solidity_verifier::{BatchOpenScheme, Keccak256Transcript};
 use num_bigint::BigUint;
-use rand::rngs::OsRng;
+use rand::{rngs::OsRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use rayon::prelude::*;
 
-use crate::utils::{amortized_kzg::compute_h, fp_to_big_uint};
+use crate::utils::{
+    amortized_kzg::compute_h, fp_to_big_uint, poseidon_transcript::PoseidonTranscript,
+};
+
+/// The multi-open scheme used to batch the KZG openings in the zkSNARK proofs and in the opening proofs.
+///
+/// The same scheme should be used for proving and verification,
+/// including the Solidity verifier generated by `generate_verifier`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MultiOpenScheme {
+    /// SHPLONK multi-open scheme as per BDFG21 (https://eprint.iacr.org/2020/081)
+    #[default]
+    Shplonk,
+    /// Multi-open scheme as per GWC19 (https://eprint.iacr.org/2019/953)
+    Gwc,
+}
+
+impl From<MultiOpenScheme> for BatchOpenScheme {
+    fn from(scheme: MultiOpenScheme) -> Self {
+        match scheme {
+            MultiOpenScheme::Shplonk => BatchOpenScheme::Bdfg21,
+            MultiOpenScheme::Gwc => BatchOpenScheme::Gwc19,
+        }
+    }
+}
+
+impl FromStr for MultiOpenScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "shplonk" | "bdfg21" => Ok(MultiOpenScheme::Shplonk),
+            "gwc" | "gwc19" => Ok(MultiOpenScheme::Gwc),
+            _ => Err(format!("Unknown multi-open scheme: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for MultiOpenScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiOpenScheme::Shplonk => write!(f, "shplonk"),
+            MultiOpenScheme::Gwc => write!(f, "gwc"),
+        }
+    }
+}
+
+/// The seed of the prover randomness, from which the blinding factors of a reproducible proof are derived
+pub type ProvingSeed = [u8; 32];
+
+/// Parses a proving seed given as 64 hexadecimal characters, with or without the `0x` prefix
+pub fn parse_proving_seed(seed: &str) -> Result<ProvingSeed, String> {
+    let bytes = hex::decode(seed.trim_start_matches("0x")).map_err(|e| e.to_string())?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        format!("The proving seed has {} bytes, expected 32", bytes.len())
+    })
+}
+
+/// The transcript used to derive the Fiat-Shamir challenges of the zkSNARK proofs and of the opening proofs.
+///
+/// [`Keccak`] is the default, as it is the transcript expected by the Solidity verifier.
+/// [`Poseidon`] is cheap to verify in a circuit, which makes the recursive verification and the aggregation of the proofs practical.
+pub trait TranscriptType {
+    /// Generates the zkSNARK proof and returns it along with the advice polynomials
+    fn create_proof<C: Circuit<Fp>>(
+        params: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+        circuit: C,
+        instances: &[&[&[Fp]]],
+        scheme: MultiOpenScheme,
+        seed: Option<ProvingSeed>,
+    ) -> (Vec<u8>, AdviceSingle<G1Affine, Coeff>);
+
+    /// Verifies the zkSNARK proof
+    fn verify_proof(
+        params: &ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+        proof: &[u8],
+        instances: &[&[&[Fp]]],
+        scheme: MultiOpenScheme,
+    ) -> bool;
+
+    /// Creates a KZG batch proof for the polynomial evaluations at a challenge
+    fn create_opening_proof(
+        params: &ParamsKZG<Bn256>,
+        polynomials: &[Polynomial<Fp, Coeff>],
+        blinds: &[Blind<Fp>],
+        challenge: Fp,
+        polynomial_evaluations: &[Fp],
+        scheme: MultiOpenScheme,
+        seed: Option<ProvingSeed>,
+    ) -> Vec<u8>;
+
+    /// Verifies a KZG batch proof for the polynomial evaluations at a challenge
+    fn verify_opening_proof(
+        params: &ParamsKZG<Bn256>,
+        proof: &[u8],
+        challenge: Fp,
+        commitment_points: &[G1Affine],
+        scheme: MultiOpenScheme,
+    ) -> Result<(bool, Vec<Fp>), Box<Error>>;
+
+    /// Reads the first `n_points` points of the zkSNARK proof, i.e., the commitments to the first advice polynomials
+    fn read_points(proof: &[u8], n_points: usize) -> io::Result<Vec<G1Affine>>;
+}
+
+/// Transcript using Keccak256, as expected by the Solidity verifier
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Keccak;
+
+/// Transcript using Poseidon, suitable for the recursive verification of the proofs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Poseidon;
+
+impl TranscriptType for Keccak {
+    fn create_proof<C: Circuit<Fp>>(
+        params: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+        circuit: C,
+        instances: &[&[&[Fp]]],
+        scheme: MultiOpenScheme,
+        seed: Option<ProvingSeed>,
+    ) -> (Vec<u8>, AdviceSingle<G1Affine, Coeff>) {
+        create_proof_with_transcript::<_, _, Keccak256Transcript<G1Affine, Vec<u8>>>(
+            params, pk, circuit, instances, scheme, seed,
+        )
+    }
+
+    fn verify_proof(
+        params: &ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+        proof: &[u8],
+        instances: &[&[&[Fp]]],
+        scheme: MultiOpenScheme,
+    ) -> bool {
+        verify_proof_with_transcript(
+            params,
+            vk,
+            &mut Keccak256Transcript::new(proof),
+            instances,
+            scheme,
+        )
+    }
+
+    fn create_opening_proof(
+        params: &ParamsKZG<Bn256>,
+        polynomials: &[Polynomial<Fp, Coeff>],
+        blinds: &[Blind<Fp>],
+        challenge: Fp,
+        polynomial_evaluations: &[Fp],
+        scheme: MultiOpenScheme,
+        seed: Option<ProvingSeed>,
+    ) -> Vec<u8> {
+        open_at_challenge::<_, Keccak256Transcript<G1Affine, Vec<u8>>>(
+            params,
+            polynomials,
+            blinds,
+            challenge,
+            polynomial_evaluations,
+            scheme,
+            seed,
+        )
+    }
+
+    fn verify_opening_proof(
+        params: &ParamsKZG<Bn256>,
+        proof: &[u8],
+        challenge: Fp,
+        commitment_points: &[G1Affine],
+        scheme: MultiOpenScheme,
+    ) -> Result<(bool, Vec<Fp>), Box<Error>> {
+        verify_opening_at_challenge(
+            params,
+            Keccak256Transcript::new(proof),
+            challenge,
+            commitment_points,
+            scheme,
+        )
+    }
+
+    fn read_points(proof: &[u8], n_points: usize) -> io::Result<Vec<G1Affine>> {
+        read_points_from_transcript(&mut Keccak256Transcript::new(proof), n_points)
+    }
+}
+
+impl TranscriptType for Poseidon {
+    fn create_proof<C: Circuit<Fp>>(
+        params: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+        circuit: C,
+        instances: &[&[&[Fp]]],
+        scheme: MultiOpenScheme,
+        seed: Option<ProvingSeed>,
+    ) -> (Vec<u8>, AdviceSingle<G1Affine, Coeff>) {
+        create_proof_with_transcript::<_, _, PoseidonTranscript<Vec<u8>>>(
+            params, pk, circuit, instances, scheme, seed,
+        )
+    }
+
+    fn verify_proof(
+        params: &ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+        proof: &[u8],
+        instances: &[&[&[Fp]]],
+        scheme: MultiOpenScheme,
+    ) -> bool {
+        verify_proof_with_transcript(
+            params,
+            vk,
+            &mut PoseidonTranscript::new(proof),
+            instances,
+            scheme,
+        )
+    }
+
+    fn create_opening_proof(
+        params: &ParamsKZG<Bn256>,
+        polynomials: &[Polynomial<Fp, Coeff>],
+        blinds: &[Blind<Fp>],
+        challenge: Fp,
+        polynomial_evaluations: &[Fp],
+        scheme: MultiOpenScheme,
+        seed: Option<ProvingSeed>,
+    ) -> Vec<u8> {
+        open_at_challenge::<_, PoseidonTranscript<Vec<u8>>>(
+            params,
+            polynomials,
+            blinds,
+            challenge,
+            polynomial_evaluations,
+            scheme,
+            seed,
+        )
+    }
+
+    fn verify_opening_proof(
+        params: &ParamsKZG<Bn256>,
+        proof: &[u8],
+        challenge: Fp,
+        commitment_points: &[G1Affine],
+        scheme: MultiOpenScheme,
+    ) -> Result<(bool, Vec<Fp>), Box<Error>> {
+        verify_opening_at_challenge(
+            params,
+            PoseidonTranscript::new(proof),
+            challenge,
+            commitment_points,
+            scheme,
+        )
+    }
+
+    fn read_points(proof: &[u8], n_points: usize) -> io::Result<Vec<G1Affine>> {
+        read_points_from_transcript(&mut PoseidonTranscript::new(proof), n_points)
+    }
+}
 
 /// Generate setup artifacts for a circuit of size `k`, where 2^k represents the number of rows in the circuit.
 ///
@@ -80,12 +335,36 @@ pub fn generate_setup_artifacts<C: Circuit<Fp>>(
     Ok((params, pk, vk))
 }
 
-/// Generates a proof given the public setup, the proving key, the initialized circuit and its public inputs.
+/// Generates a proof given the public setup, the proving key, the initialized circuit, its public inputs and the multi-open scheme.
+///
+/// The proof uses the Keccak256 transcript expected by the Solidity verifier.
+/// If a `seed` is given, the randomness of the prover (including the blinding factors) is derived from it,
+/// so that the same inputs and seed always yield byte-identical proofs. Otherwise, the randomness is drawn from the OS.
+/// Anyone knowing the seed can recompute the blinding factors, so it must be kept private, e.g., shared with the Auditor only.
 pub fn full_prover<C: Circuit<Fp>>(
     params: &ParamsKZG<Bn256>,
     pk: &ProvingKey<G1Affine>,
     circuit: C,
     public_inputs: &[Vec<Fp>],
+    scheme: MultiOpenScheme,
+    seed: Option<ProvingSeed>,
+) -> (
+    Vec<u8>,
+    AdviceSingle<halo2_proofs::halo2curves::bn256::G1Affine, Coeff>,
+    Fp,
+) {
+    full_prover_with_transcript::<Keccak, C>(params, pk, circuit, public_inputs, scheme, seed)
+}
+
+/// Generates a proof given the public setup, the proving key, the initialized circuit, its public inputs and the multi-open scheme,
+/// deriving the challenges with the transcript `T`.
+pub fn full_prover_with_transcript<T: TranscriptType, C: Circuit<Fp>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    public_inputs: &[Vec<Fp>],
+    scheme: MultiOpenScheme,
+    seed: Option<ProvingSeed>,
 ) -> (
     Vec<u8>,
     AdviceSingle<halo2_proofs::halo2curves::bn256::G1Affine, Coeff>,
@@ -97,24 +376,10 @@ pub fn full_prover<C: Circuit<Fp>>(
     let instance: Vec<&[Fp]> = public_inputs.iter().map(|input| &input[..]).collect();
     let instances = &[&instance[..]];
 
-    let mut transcript: Keccak256Transcript<G1Affine, Vec<u8>> = Keccak256Transcript::new(vec![]);
-    let result = create_proof::<
-        KZGCommitmentScheme<Bn256>,
-        ProverSHPLONK<'_, Bn256>,
-        _,
-        _,
-        Keccak256Transcript<G1Affine, Vec<u8>>,
-        _,
-    >(params, pk, &[circuit], instances, OsRng, &mut transcript);
-
-    let result_unwrapped = result.unwrap();
-    result_unwrapped.0.expect("prover should not fail");
-    let advice_polys = result_unwrapped.1.clone();
-    let proof = transcript.finalize();
+    let (proof, advice_polys) = T::create_proof(params, pk, circuit, instances, scheme, seed);
 
     #[cfg(feature = "profiling")]
     end_timer!(pf_time);
-    let advice_polys = advice_polys[0].clone();
 
     let omega = pk.get_vk().get_domain().get_omega();
 
@@ -131,6 +396,8 @@ pub fn full_prover<C: Circuit<Fp>>(
 /// * `params` - the KZG parameters
 /// * `balance_column_range` - the range of the balance columns used to calculate the grand sums
 /// * `constant_terms` - the evaluations of the polynomials at X = 0
+/// * `scheme` - the multi-open scheme used to batch the openings
+/// * `seed` - the optional 32-byte seed of the prover randomness, for reproducible proofs
 ///
 /// # Returns
 ///
@@ -142,42 +409,40 @@ pub fn open_grand_sums(
     params: &ParamsKZG<Bn256>,
     balance_column_range: Range<usize>,
     constant_terms: &[Fp],
+    scheme: MultiOpenScheme,
+    seed: Option<ProvingSeed>,
 ) -> Vec<u8> {
-    let challenge = Fp::zero();
-    create_opening_proof_at_challenge::<
-        _,
-        ProverSHPLONK<'_, Bn256>,
-        _,
-        Keccak256Transcript<G1Affine, Vec<u8>>,
-    >(
-        params,
-        &advice_polys[balance_column_range],
+    open_grand_sums_with_transcript::<Keccak>(
+        advice_polys,
         advice_blinds,
-        challenge,
+        params,
+        balance_column_range,
         constant_terms,
+        scheme,
+        seed,
     )
 }
 
-// This function is for comparison with open_grand_sums and is using GWC instead of ProverSHPLONK
-pub fn open_grand_sums_gwc(
+/// Creates the univariate polynomial grand sum openings, deriving the challenges with the transcript `T`.
+/// See [`open_grand_sums`] for the arguments.
+pub fn open_grand_sums_with_transcript<T: TranscriptType>(
     advice_polys: &[Polynomial<Fp, Coeff>],
     advice_blinds: &[Blind<Fp>],
     params: &ParamsKZG<Bn256>,
     balance_column_range: Range<usize>,
     constant_terms: &[Fp],
+    scheme: MultiOpenScheme,
+    seed: Option<ProvingSeed>,
 ) -> Vec<u8> {
     let challenge = Fp::zero();
-    create_opening_proof_at_challenge::<
-        _,
-        ProverGWC<'_, Bn256>,
-        _,
-        Keccak256Transcript<G1Affine, Vec<u8>>,
-    >(
+    T::create_opening_proof(
         params,
         &advice_polys[balance_column_range],
         advice_blinds,
         challenge,
         constant_terms,
+        scheme,
+        seed,
     )
 }
 
@@ -193,11 +458,14 @@ pub fn open_grand_sums_gwc(
 /// * `omega` - $\omega$, the generator of the $2^k$ order multiplicative subgroup used to interpolate the polynomials.
 /// * `user_index` - the index of the user whose entry is being proven
 /// * `user_balances` - the evaluations of the polynomials at the point corresponding to the `user_index`
+/// * `scheme` - the multi-open scheme used to batch the openings
+/// * `seed` - the optional 32-byte seed of the prover randomness, for reproducible proofs
 ///
 /// # Returns
 ///
 /// * `Vec<u8>` - the KZG batch proof containing the quotient polynomial commitments
 /// and the evaluations of the polynomials at the point corresponding to the `user_index`
+#[allow(clippy::too_many_arguments)]
 pub fn open_user_points(
     advice_polys: &[Polynomial<Fp, Coeff>],
     advice_blinds: &[Blind<Fp>],
@@ -206,19 +474,45 @@ pub fn open_user_points(
     omega: Fp,
     user_index: u16,
     user_balances: &[Fp],
+    scheme: MultiOpenScheme,
+    seed: Option<ProvingSeed>,
+) -> Vec<u8> {
+    open_user_points_with_transcript::<Keccak>(
+        advice_polys,
+        advice_blinds,
+        params,
+        column_range,
+        omega,
+        user_index,
+        user_balances,
+        scheme,
+        seed,
+    )
+}
+
+/// Creates a KZG batch proof for the `advice_polys` polynomial openings at a point corresponding to the `user_index`,
+/// deriving the challenges with the transcript `T`. See [`open_user_points`] for the arguments.
+#[allow(clippy::too_many_arguments)]
+pub fn open_user_points_with_transcript<T: TranscriptType>(
+    advice_polys: &[Polynomial<Fp, Coeff>],
+    advice_blinds: &[Blind<Fp>],
+    params: &ParamsKZG<Bn256>,
+    column_range: Range<usize>,
+    omega: Fp,
+    user_index: u16,
+    user_balances: &[Fp],
+    scheme: MultiOpenScheme,
+    seed: Option<ProvingSeed>,
 ) -> Vec<u8> {
     let omega_raised = omega.pow_vartime([u64::from(user_index)]);
-    create_opening_proof_at_challenge::<
-        _,
-        ProverSHPLONK<'_, Bn256>,
-        _,
-        Keccak256Transcript<G1Affine, Vec<u8>>,
-    >(
+    T::create_opening_proof(
         params,
         &advice_polys[column_range],
         advice_blinds,
         omega_raised,
         user_balances,
+        scheme,
+        seed,
     )
 }
 
@@ -318,6 +612,7 @@ pub fn open_single_user_point_amortized(
 /// * `grand_sum_opening_batch_proof` - the KZG batch proof of the grand sum polynomials
 /// * `polynomial_length` - the length of the polynomials
 /// * `balance_column_range` - the range of the advice columns that represent user balances
+/// * `scheme` - the multi-open scheme used to batch the openings
 ///
 /// # Returns
 ///
@@ -329,26 +624,43 @@ pub fn verify_grand_sum_openings<const N_CURRENCIES: usize>(
     grand_sum_opening_batch_proof: &[u8],
     polynomial_length: u64,
     balance_column_range: Range<usize>,
+    scheme: MultiOpenScheme,
 ) -> (bool, Vec<BigUint>) {
-    let mut transcript = Keccak256Transcript::new(zk_snark_proof);
-
-    //Read the commitment points for all the advice polynomials from the proof transcript and put them into a vector
-    let mut advice_commitments = Vec::new();
-    for i in 0..N_CURRENCIES + balance_column_range.start {
-        let point = transcript.read_point().unwrap();
-        // Skip the advice commitments before the desired range
-        if i >= balance_column_range.start {
-            advice_commitments.push(point);
-        }
-    }
+    verify_grand_sum_openings_with_transcript::<Keccak, N_CURRENCIES>(
+        params,
+        zk_snark_proof,
+        grand_sum_opening_batch_proof,
+        polynomial_length,
+        balance_column_range,
+        scheme,
+    )
+}
 
-    let opening_result =
-        verify_opening::<KZGCommitmentScheme<_>, VerifierSHPLONK<Bn256>, SingleStrategy<_>>(
-            params,
-            grand_sum_opening_batch_proof,
-            Fp::zero(),
-            &advice_commitments,
-        );
+/// Verifies the univariate polynomial grand sum openings and calculates the grand sums,
+/// the proofs being generated with the transcript `T`. See [`verify_grand_sum_openings`] for the arguments.
+pub fn verify_grand_sum_openings_with_transcript<T: TranscriptType, const N_CURRENCIES: usize>(
+    params: &ParamsKZG<Bn256>,
+    zk_snark_proof: &[u8],
+    grand_sum_opening_batch_proof: &[u8],
+    polynomial_length: u64,
+    balance_column_range: Range<usize>,
+    scheme: MultiOpenScheme,
+) -> (bool, Vec<BigUint>) {
+    //Read the commitment points for all the advice polynomials from the proof transcript
+    let advice_commitments =
+        match T::read_points(zk_snark_proof, N_CURRENCIES + balance_column_range.start) {
+            // Skip the advice commitments before the desired range
+            Ok(points) => points[balance_column_range.start..].to_vec(),
+            Err(_) => return (false, vec![]),
+        };
+
+    let opening_result = T::verify_opening_proof(
+        params,
+        grand_sum_opening_batch_proof,
+        Fp::zero(),
+        &advice_commitments,
+        scheme,
+    );
 
     match opening_result {
         Ok((verified, evaluations)) => (
@@ -372,6 +684,7 @@ pub fn verify_grand_sum_openings<const N_CURRENCIES: usize>(
 /// * `column_range` - the range of the advice columns that represent user entry
 /// * `omega` - $\omega$, the generator of the $2^k$ order multiplicative subgroup used to interpolate the polynomials.
 /// * `user_index` - the index of the user whose entry is being proven
+/// * `scheme` - the multi-open scheme used to batch the openings
 ///
 /// # Returns
 /// * `bool` - whether the user entry openings are verified correctly
@@ -383,26 +696,44 @@ pub fn verify_user_inclusion(
     column_range: Range<usize>,
     omega: Fp,
     user_index: u16,
+    scheme: MultiOpenScheme,
 ) -> (bool, Vec<BigUint>) {
-    let mut transcript = Keccak256Transcript::new(zk_snark_proof);
+    verify_user_inclusion_with_transcript::<Keccak>(
+        params,
+        zk_snark_proof,
+        balance_opening_batch_proof,
+        column_range,
+        omega,
+        user_index,
+        scheme,
+    )
+}
 
-    //Read the commitment points for all the  advice polynomials from the proof transcript and put them into a vector
-    let mut advice_commitments = Vec::new();
-    for i in 0..column_range.end {
-        let point = transcript.read_point().unwrap();
+/// Verifies the KZG batch proof of the user entry openings, the proofs being generated with the transcript `T`.
+/// See [`verify_user_inclusion`] for the arguments.
+pub fn verify_user_inclusion_with_transcript<T: TranscriptType>(
+    params: &ParamsKZG<Bn256>,
+    zk_snark_proof: &[u8],
+    balance_opening_batch_proof: &[u8],
+    column_range: Range<usize>,
+    omega: Fp,
+    user_index: u16,
+    scheme: MultiOpenScheme,
+) -> (bool, Vec<BigUint>) {
+    //Read the commitment points for all the advice polynomials from the proof transcript
+    let advice_commitments = match T::read_points(zk_snark_proof, column_range.end) {
         //Skip advice polynomial commitments before the desired range
-        if i >= column_range.start {
-            advice_commitments.push(point);
-        }
-    }
+        Ok(points) => points[column_range.start..].to_vec(),
+        Err(_) => return (false, vec![]),
+    };
 
-    let opening_result =
-        verify_opening::<KZGCommitmentScheme<_>, VerifierSHPLONK<Bn256>, SingleStrategy<_>>(
-            params,
-            balance_opening_batch_proof,
-            omega.pow_vartime([user_index as u64]),
-            &advice_commitments,
-        );
+    let opening_result = T::verify_opening_proof(
+        params,
+        balance_opening_batch_proof,
+        omega.pow_vartime([user_index as u64]),
+        &advice_commitments,
+        scheme,
+    );
 
     // return result error if it exists
     match opening_result {
@@ -417,6 +748,162 @@ pub fn verify_user_inclusion(
     }
 }
 
+/// Returns the randomness source of the prover: a ChaCha20 RNG seeded with `seed` if given, the OS RNG otherwise
+fn proving_rng(seed: Option<ProvingSeed>) -> Box<dyn RngCore> {
+    match seed {
+        Some(seed) => Box::new(ChaCha20Rng::from_seed(seed)),
+        None => Box::new(OsRng),
+    }
+}
+
+/// Generates the zkSNARK proof with the transcript `T` using the given multi-open scheme
+fn create_proof_with_transcript<
+    C: Circuit<Fp>,
+    E: EncodedChallenge<G1Affine>,
+    T: TranscriptWriterBuffer<Vec<u8>, G1Affine, E>,
+>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instances: &[&[&[Fp]]],
+    scheme: MultiOpenScheme,
+    seed: Option<ProvingSeed>,
+) -> (Vec<u8>, AdviceSingle<G1Affine, Coeff>) {
+    let rng = proving_rng(seed);
+    let mut transcript = T::init(vec![]);
+    let result = match scheme {
+        MultiOpenScheme::Shplonk => {
+            create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, E, _, T, _>(
+                params,
+                pk,
+                &[circuit],
+                instances,
+                rng,
+                &mut transcript,
+            )
+        }
+        MultiOpenScheme::Gwc => create_proof::<
+            KZGCommitmentScheme<Bn256>,
+            ProverGWC<'_, Bn256>,
+            E,
+            _,
+            T,
+            _,
+        >(params, pk, &[circuit], instances, rng, &mut transcript),
+    };
+
+    let result_unwrapped = result.unwrap();
+    result_unwrapped.0.expect("prover should not fail");
+    let advice_polys = result_unwrapped.1[0].clone();
+
+    (transcript.finalize(), advice_polys)
+}
+
+/// Verifies the zkSNARK proof read from the transcript using the given multi-open scheme
+fn verify_proof_with_transcript<E: EncodedChallenge<G1Affine>, T: TranscriptRead<G1Affine, E>>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    transcript: &mut T,
+    instances: &[&[&[Fp]]],
+    scheme: MultiOpenScheme,
+) -> bool {
+    match scheme {
+        MultiOpenScheme::Shplonk => verify_proof::<_, VerifierSHPLONK<_>, E, T, _>(
+            params,
+            vk,
+            SingleStrategy::new(params),
+            instances,
+            transcript,
+        )
+        .is_ok(),
+        MultiOpenScheme::Gwc => verify_proof::<_, VerifierGWC<_>, E, T, _>(
+            params,
+            vk,
+            SingleStrategy::new(params),
+            instances,
+            transcript,
+        )
+        .is_ok(),
+    }
+}
+
+/// Reads the first `n_points` points from the transcript
+fn read_points_from_transcript<E: EncodedChallenge<G1Affine>, T: TranscriptRead<G1Affine, E>>(
+    transcript: &mut T,
+    n_points: usize,
+) -> io::Result<Vec<G1Affine>> {
+    (0..n_points).map(|_| transcript.read_point()).collect()
+}
+
+/// Creates a KZG batch proof for the polynomial evaluations at a challenge with the transcript `T` using the given multi-open scheme
+fn open_at_challenge<
+    E: EncodedChallenge<G1Affine>,
+    T: TranscriptWriterBuffer<Vec<u8>, G1Affine, E>,
+>(
+    params: &ParamsKZG<Bn256>,
+    polynomials: &[Polynomial<Fp, Coeff>],
+    blinds: &[Blind<Fp>],
+    challenge: Fp,
+    polynomial_evaluations: &[Fp],
+    scheme: MultiOpenScheme,
+    seed: Option<ProvingSeed>,
+) -> Vec<u8> {
+    match scheme {
+        MultiOpenScheme::Shplonk => create_opening_proof_at_challenge::<
+            KZGCommitmentScheme<Bn256>,
+            ProverSHPLONK<'_, Bn256>,
+            E,
+            T,
+        >(
+            params,
+            polynomials,
+            blinds,
+            challenge,
+            polynomial_evaluations,
+            seed,
+        ),
+        MultiOpenScheme::Gwc => create_opening_proof_at_challenge::<
+            KZGCommitmentScheme<Bn256>,
+            ProverGWC<'_, Bn256>,
+            E,
+            T,
+        >(
+            params,
+            polynomials,
+            blinds,
+            challenge,
+            polynomial_evaluations,
+            seed,
+        ),
+    }
+}
+
+/// Verifies a KZG batch proof for the polynomial evaluations at a challenge read from the transcript using the given multi-open scheme
+fn verify_opening_at_challenge<E: EncodedChallenge<G1Affine>, T: TranscriptRead<G1Affine, E>>(
+    params: &ParamsKZG<Bn256>,
+    transcript: T,
+    challenge: Fp,
+    commitment_points: &[G1Affine],
+    scheme: MultiOpenScheme,
+) -> Result<(bool, Vec<Fp>), Box<Error>> {
+    match scheme {
+        MultiOpenScheme::Shplonk => verify_opening_with_transcript::<
+            KZGCommitmentScheme<_>,
+            VerifierSHPLONK<Bn256>,
+            SingleStrategy<_>,
+            E,
+            T,
+        >(params, transcript, challenge, commitment_points),
+        MultiOpenScheme::Gwc => verify_opening_with_transcript::<
+            KZGCommitmentScheme<_>,
+            VerifierGWC<Bn256>,
+            SingleStrategy<_>,
+            E,
+            T,
+        >(params, transcript, challenge, commitment_points),
+    }
+}
+
 /// Creates a KZG batch proof for the polynomial evaluations at a challenge
 ///
 /// # Arguments
@@ -426,6 +913,7 @@ pub fn verify_user_inclusion(
 /// * `blinds` - the polynomials blinds
 /// * `challenge` - the challenge at which the polynomials are evaluated
 /// * `polynomial_evaluations` - the evaluations of the polynomials at the challenge
+/// * `seed` - the optional 32-byte seed of the prover randomness, for reproducible proofs
 ///
 /// # Returns
 ///
@@ -443,6 +931,7 @@ fn create_opening_proof_at_challenge<
     blinds: &[Blind<Fp>],
     challenge: Fp,
     polynomial_evaluations: &[Fp],
+    seed: Option<ProvingSeed>,
 ) -> Vec<u8>
 where
     Scheme::Scalar: WithSmallOrderMulGroup<3>,
@@ -464,7 +953,7 @@ where
     // Create proof
     let prover = P::new(params);
     prover
-        .create_proof(&mut OsRng, &mut transcript, queries)
+        .create_proof(proving_rng(seed), &mut transcript, queries)
         .unwrap();
 
     // Finalize transcript and return the proof
@@ -501,8 +990,32 @@ pub fn verify_opening<
 where
     Scheme::Scalar: WithSmallOrderMulGroup<3>,
 {
-    let mut transcript = Keccak256Transcript::new(proof);
+    verify_opening_with_transcript::<Scheme, V, Strategy, _, _>(
+        params,
+        Keccak256Transcript::new(proof),
+        challenge,
+        commitment_points,
+    )
+}
 
+/// Verifies a KZG batch proof read from the given transcript, e.g., a `PoseidonTranscript`.
+/// See [`verify_opening`] for the other arguments and the returned values.
+pub fn verify_opening_with_transcript<
+    'params,
+    Scheme: CommitmentScheme<Curve = halo2_proofs::halo2curves::bn256::G1Affine, Scalar = Fp>,
+    V: Verifier<'params, Scheme>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptRead<Scheme::Curve, E>,
+>(
+    params: &'params Scheme::ParamsVerifier,
+    mut transcript: T,
+    challenge: Fp,
+    commitment_points: &[G1Affine],
+) -> Result<(bool, Vec<Fp>), Box<Error>>
+where
+    Scheme::Scalar: WithSmallOrderMulGroup<3>,
+{
     // Read the polynomial evaluations from the transcript
     let n_points = commitment_points.len();
     let evaluations = (0..n_points)
@@ -532,25 +1045,32 @@ where
     }
 }
 
-/// Verifies a proof given the public setup, the verification key, the proof and the public inputs of the circuit.
+/// Verifies a proof given the public setup, the verification key, the proof, the public inputs of the circuit and the multi-open scheme.
+///
+/// The proof is expected to use the Keccak256 transcript, as for the Solidity verifier.
 pub fn full_verifier(
     params: &ParamsKZG<Bn256>,
     vk: &VerifyingKey<G1Affine>,
     proof: &[u8],
     public_inputs: &[Vec<Fp>],
+    scheme: MultiOpenScheme,
+) -> bool {
+    full_verifier_with_transcript::<Keccak>(params, vk, proof, public_inputs, scheme)
+}
+
+/// Verifies a proof given the public setup, the verification key, the proof, the public inputs of the circuit and the multi-open scheme,
+/// the challenges being derived with the transcript `T`.
+pub fn full_verifier_with_transcript<T: TranscriptType>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
+    public_inputs: &[Vec<Fp>],
+    scheme: MultiOpenScheme,
 ) -> bool {
     let instance: Vec<&[Fp]> = public_inputs.iter().map(|input| &input[..]).collect();
     let instances = &[&instance[..]];
 
-    let mut transcript = Keccak256Transcript::new(proof);
-    verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
-        params,
-        vk,
-        SingleStrategy::new(params),
-        instances,
-        &mut transcript,
-    )
-    .is_ok()
+    T::verify_proof(params, vk, proof, instances, scheme)
 }
 
 /// Converts a field element to a Solidity calldata
@@ -3,6 +3,8 @@
 
 #![feature(generic_const_exprs)]
 
+/// Independent recomputation of the advice commitments from the raw data, for the Auditor.
+pub mod auditor;
 /// Zk circuit subcomponents aka chips.
 pub mod chips;
 /// Zk circuits with a full prover and verifier. A circuit can be viewed as an assembly of chips.
@@ -2,6 +2,7 @@ pub mod amortized_kzg;
 mod csv_parser;
 mod dummy_entries;
 mod operation_helpers;
+pub mod poseidon_transcript;
 
 pub use csv_parser::parse_csv_to_entries;
 pub use dummy_entries::generate_dummy_entries;
@@ -0,0 +1,255 @@
+use std::{
+    io::{self, Read, Write},
+    sync::OnceLock,
+};
+
+use halo2_gadgets::poseidon::primitives::{generate_constants, ConstantLength, Hash, Mds, Spec};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, Field},
+    halo2curves::{
+        bn256::{Fq, Fr as Fp, G1Affine},
+        ff::PrimeField,
+        group::prime::PrimeCurveAffine,
+    },
+    transcript::{
+        Challenge255, EncodedChallenge, Transcript, TranscriptRead, TranscriptReadBuffer,
+        TranscriptWrite, TranscriptWriterBuffer,
+    },
+};
+
+/// Domain separator absorbed before each challenge is squeezed out of the transcript
+const SQUEEZE_DOMAIN: u64 = 1;
+
+/// Poseidon specification over the BN256 scalar field with a width of 3 and a rate of 2.
+///
+/// It uses the x^5 S-box and the number of rounds recommended for 128-bit security (8 full rounds and 57 partial rounds).
+#[derive(Debug, Clone, Copy)]
+pub struct PoseidonSpec;
+
+impl Spec<Fp, 3, 2> for PoseidonSpec {
+    fn full_rounds() -> usize {
+        8
+    }
+
+    fn partial_rounds() -> usize {
+        57
+    }
+
+    fn sbox(val: Fp) -> Fp {
+        val.pow_vartime([5])
+    }
+
+    fn secure_mds() -> usize {
+        0
+    }
+
+    fn constants() -> (Vec<[Fp; 3]>, Mds<Fp, 3>, Mds<Fp, 3>) {
+        // The round constants and the MDS matrices are generated once and reused for every hash
+        static CONSTANTS: OnceLock<(Vec<[Fp; 3]>, Mds<Fp, 3>, Mds<Fp, 3>)> = OnceLock::new();
+        CONSTANTS
+            .get_or_init(generate_constants::<_, Self, 3, 2>)
+            .clone()
+    }
+}
+
+/// Transcript using Poseidon as the hash function to derive the Fiat-Shamir challenges.
+///
+/// Unlike the Keccak256 transcript used by the Solidity verifier, the Poseidon transcript is cheap to verify in a circuit,
+/// which makes the recursive verification and the aggregation of the proofs practical.
+///
+/// The transcript state is a single field element that absorbs the transcript elements one by one, i.e., `state = H(state, element)`.
+/// The points are absorbed as the 128-bit limbs of their coordinates, because the coordinates are elements of the base field.
+/// The proof layout is the same as the one of the Keccak256 transcript:
+/// the points are serialized as their 32-byte big-endian coordinates and the scalars as 32-byte big-endian integers.
+#[derive(Debug, Clone)]
+pub struct PoseidonTranscript<S> {
+    stream: S,
+    state: Fp,
+}
+
+impl<S> PoseidonTranscript<S> {
+    /// Returns a `PoseidonTranscript` over the given stream
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            state: Fp::zero(),
+        }
+    }
+
+    fn absorb(&mut self, element: Fp) {
+        self.state =
+            Hash::<Fp, PoseidonSpec, ConstantLength<2>, 3, 2>::init().hash([self.state, element]);
+    }
+
+    fn absorb_base(&mut self, element: Fq) {
+        let repr = element.to_repr();
+        let (low, high) = repr.as_ref().split_at(16);
+        self.absorb(Fp::from_u128(u128::from_le_bytes(low.try_into().unwrap())));
+        self.absorb(Fp::from_u128(u128::from_le_bytes(high.try_into().unwrap())));
+    }
+}
+
+/// Returns the affine coordinates of the point, the point at infinity being encoded as (0, 0)
+fn point_to_coordinates(point: &G1Affine) -> (Fq, Fq) {
+    Option::from(point.coordinates())
+        .map(|coordinates| (*coordinates.x(), *coordinates.y()))
+        .unwrap_or((Fq::zero(), Fq::zero()))
+}
+
+fn base_to_bytes(element: Fq) -> [u8; 32] {
+    let mut bytes = element.to_repr();
+    bytes.as_mut().reverse();
+    bytes.as_ref().try_into().unwrap()
+}
+
+fn read_base<R: Read>(reader: &mut R) -> io::Result<Fq> {
+    let mut bytes = [0u8; 32];
+    reader.read_exact(&mut bytes)?;
+    bytes.reverse();
+    Option::from(Fq::from_repr(bytes.into())).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "invalid field element encoding in proof",
+        )
+    })
+}
+
+impl<S> Transcript<G1Affine, Challenge255<G1Affine>> for PoseidonTranscript<S> {
+    fn squeeze_challenge(&mut self) -> Challenge255<G1Affine> {
+        self.absorb(Fp::from(SQUEEZE_DOMAIN));
+        let mut challenge_input = [0u8; 64];
+        challenge_input[..32].copy_from_slice(self.state.to_repr().as_ref());
+        Challenge255::new(&challenge_input)
+    }
+
+    fn common_point(&mut self, point: G1Affine) -> io::Result<()> {
+        let (x, y) = point_to_coordinates(&point);
+        self.absorb_base(x);
+        self.absorb_base(y);
+        Ok(())
+    }
+
+    fn common_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.absorb(scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read> TranscriptRead<G1Affine, Challenge255<G1Affine>> for PoseidonTranscript<R> {
+    fn read_point(&mut self) -> io::Result<G1Affine> {
+        let x = read_base(&mut self.stream)?;
+        let y = read_base(&mut self.stream)?;
+        let point = if x.is_zero_vartime() && y.is_zero_vartime() {
+            G1Affine::identity()
+        } else {
+            Option::from(G1Affine::from_xy(x, y)).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof")
+            })?
+        };
+        self.common_point(point)?;
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<Fp> {
+        let mut bytes = [0u8; 32];
+        self.stream.read_exact(&mut bytes)?;
+        bytes.reverse();
+        let scalar: Fp = Option::from(Fp::from_repr(bytes)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "invalid field element encoding in proof",
+            )
+        })?;
+        self.common_scalar(scalar)?;
+        Ok(scalar)
+    }
+}
+
+impl<R: Read> TranscriptReadBuffer<R, G1Affine, Challenge255<G1Affine>> for PoseidonTranscript<R> {
+    fn init(reader: R) -> Self {
+        Self::new(reader)
+    }
+}
+
+impl<W: Write> TranscriptWrite<G1Affine, Challenge255<G1Affine>> for PoseidonTranscript<W> {
+    fn write_point(&mut self, point: G1Affine) -> io::Result<()> {
+        self.common_point(point)?;
+        let (x, y) = point_to_coordinates(&point);
+        self.stream.write_all(&base_to_bytes(x))?;
+        self.stream.write_all(&base_to_bytes(y))
+    }
+
+    fn write_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        let mut bytes = scalar.to_repr();
+        bytes.reverse();
+        self.stream.write_all(&bytes)
+    }
+}
+
+impl<W: Write> TranscriptWriterBuffer<W, G1Affine, Challenge255<G1Affine>>
+    for PoseidonTranscript<W>
+{
+    fn init(writer: W) -> Self {
+        Self::new(writer)
+    }
+
+    fn finalize(self) -> W {
+        self.stream
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::halo2curves::{
+        bn256::G1,
+        group::{Curve, Group},
+    };
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_poseidon_transcript_round_trip() {
+        let point = G1::random(OsRng).to_affine();
+        let scalar = Fp::random(OsRng);
+
+        let mut writer = PoseidonTranscript::new(vec![]);
+        writer.write_point(point).unwrap();
+        writer.write_point(G1Affine::identity()).unwrap();
+        let prover_challenge = writer.squeeze_challenge().get_scalar();
+        writer.write_scalar(scalar).unwrap();
+        let prover_final_challenge = writer.squeeze_challenge().get_scalar();
+        let proof = writer.finalize();
+
+        // The points and the scalars are serialized in 32-byte words
+        assert_eq!(proof.len(), 64 * 2 + 32);
+
+        let mut reader = PoseidonTranscript::new(proof.as_slice());
+        assert_eq!(reader.read_point().unwrap(), point);
+        assert_eq!(reader.read_point().unwrap(), G1Affine::identity());
+        assert_eq!(reader.squeeze_challenge().get_scalar(), prover_challenge);
+        assert_eq!(reader.read_scalar().unwrap(), scalar);
+        assert_eq!(
+            reader.squeeze_challenge().get_scalar(),
+            prover_final_challenge
+        );
+        assert!(reader.read_scalar().is_err());
+    }
+
+    #[test]
+    fn test_poseidon_transcript_binds_elements() {
+        let point = (G1Affine::generator() * Fp::from(2)).to_affine();
+
+        let mut transcript = PoseidonTranscript::new(vec![]);
+        transcript.write_point(G1Affine::generator()).unwrap();
+        let challenge = transcript.squeeze_challenge().get_scalar();
+
+        let mut other_transcript = PoseidonTranscript::new(vec![]);
+        other_transcript.write_point(point).unwrap();
+        let other_challenge = other_transcript.squeeze_challenge().get_scalar();
+
+        // Different transcripts yield different challenges, and consecutive challenges differ
+        assert_ne!(challenge, other_challenge);
+        assert_ne!(challenge, transcript.squeeze_challenge().get_scalar());
+    }
+}